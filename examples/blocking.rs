@@ -0,0 +1,11 @@
+extern crate web3;
+
+fn main() {
+    let web3 = web3::blocking::Web3::http("http://localhost:8545").unwrap();
+
+    let height = web3.abos().block_number().unwrap();
+    println!("height: {:?}", height);
+
+    let meta_data = web3.abos().meta_data(None).unwrap();
+    println!("meta data: {:?}", meta_data);
+}
@@ -0,0 +1,432 @@
+//! Partial implementation of the `Accounts` namespace.
+
+use crate::api::{Eth, Namespace};
+use crate::fees;
+use crate::helpers::{self, CallFuture};
+use crate::signing::{self, LocalSigner, SecretKey, Signer};
+use crate::types::{
+    Address, BlockNumber, SignedData, SignedTransaction, TransactionParameters, TransactionRequest, TypedData, H256,
+    U256,
+};
+use crate::{Error, Transport};
+use futures::future::{self, Either, FutureResult};
+use futures::Future;
+
+/// `Accounts` namespace
+#[derive(Debug, Clone)]
+pub struct Accounts<T> {
+    transport: T,
+}
+
+impl<T: Transport> Namespace<T> for Accounts<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        Accounts { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> Accounts<T> {
+    /// Gets the parent `eth` namespace
+    fn eth(&self) -> Eth<T> {
+        Eth::new(self.transport.clone())
+    }
+
+    /// Fills the parameters missing to sign a transaction sent from given address.
+    ///
+    /// Nonce, chain id, gas (with `eth_estimateGas`), gas price (legacy and EIP-2930 transactions)
+    /// and EIP-1559 fees (EIP-1559 transactions) are fetched from the node when missing.
+    pub fn fill_transaction(
+        &self,
+        tx: TransactionParameters,
+        from: Address,
+    ) -> impl Future<Item = TransactionParameters, Error = Error> {
+        let eth = self.eth();
+        let transaction_type = tx.resolved_type();
+
+        let nonce = or_fetch(tx.nonce, || eth.transaction_count(from, Some(BlockNumber::Pending)));
+        let chain_id = or_fetch(tx.chain_id, || eth.chain_id().map(|id| id.low_u64()));
+        let gas = or_fetch(tx.gas, || self.estimate_gas(&tx, from));
+        // EIP-1559 transactions don't carry a gas price.
+        let gas_price = match transaction_type {
+            2 => Either::A(future::ok(tx.gas_price)),
+            _ => Either::B(or_fetch(tx.gas_price, || eth.gas_price()).map(Some)),
+        };
+        let eip1559_fees = match (transaction_type, tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+            (2, max_fee, max_priority_fee) if max_fee.is_none() || max_priority_fee.is_none() => {
+                Either::B(fees::estimate_eip1559_fees(&eth).map(move |estimated| {
+                    (
+                        Some(max_fee.unwrap_or(estimated.max_fee_per_gas)),
+                        Some(max_priority_fee.unwrap_or(estimated.max_priority_fee_per_gas)),
+                    )
+                }))
+            }
+            (_, max_fee, max_priority_fee) => Either::A(future::ok((max_fee, max_priority_fee))),
+        };
+
+        nonce.join5(chain_id, gas, gas_price, eip1559_fees).map(
+            move |(nonce, chain_id, gas, gas_price, (max_fee_per_gas, max_priority_fee_per_gas))| {
+                TransactionParameters {
+                    nonce: Some(nonce),
+                    chain_id: Some(chain_id),
+                    gas: Some(gas),
+                    gas_price,
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                    ..tx
+                }
+            },
+        )
+    }
+
+    /// Estimates gas used by given transaction sent from given address.
+    ///
+    /// Sent as a `TransactionRequest`, since a `CallRequest` can't describe contract creations.
+    fn estimate_gas(&self, tx: &TransactionParameters, from: Address) -> CallFuture<U256, T::Out> {
+        let request = TransactionRequest {
+            from,
+            to: tx.to,
+            gas: None,
+            gas_price: None,
+            value: Some(tx.value),
+            data: Some(tx.data.clone()),
+            nonce: None,
+            condition: None,
+            transaction_type: None,
+            access_list: tx.access_list.clone(),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+        };
+        let request = helpers::serialize(&request);
+        let block = helpers::serialize(&BlockNumber::Latest);
+
+        CallFuture::new(self.transport.execute("eth_estimateGas", vec![request, block]))
+    }
+
+    /// Signs an Ethereum transaction with given signer.
+    ///
+    /// Missing parameters are filled with `fill_transaction` first.
+    /// The returned `raw_transaction` can be sent with `Eth::send_raw_transaction`.
+    pub fn sign_transaction_with<S>(
+        &self,
+        tx: TransactionParameters,
+        signer: S,
+    ) -> impl Future<Item = SignedTransaction, Error = Error>
+    where
+        S: Signer,
+    {
+        self.fill_transaction(tx, signer.address())
+            .and_then(move |tx| signer.sign_transaction(tx))
+    }
+
+    /// Signs an Ethereum transaction with a given private key.
+    ///
+    /// Missing nonce, chain id, gas, gas price and EIP-1559 fees are fetched from the node.
+    /// The returned `raw_transaction` can be sent with `Eth::send_raw_transaction`.
+    pub fn sign_transaction(
+        &self,
+        tx: TransactionParameters,
+        key: &SecretKey,
+    ) -> impl Future<Item = SignedTransaction, Error = Error> {
+        self.sign_transaction_with(tx, LocalSigner::new(*key))
+    }
+
+    /// Signs EIP-712 typed structured data with a given private key.
+    ///
+    /// The signature is in 'Electrum' notation, see `sign`.
+    pub fn sign_typed_data(&self, typed_data: &TypedData, key: &SecretKey) -> Result<SignedData, Error> {
+        Ok(signing::sign_typed_data(
+            typed_data.domain_separator()?,
+            typed_data.struct_hash()?,
+            key,
+        ))
+    }
+
+    /// Signs EIP-712 typed structured data with given signer.
+    pub fn sign_typed_data_with<S>(
+        &self,
+        typed_data: &TypedData,
+        signer: S,
+    ) -> impl Future<Item = SignedData, Error = Error>
+    where
+        S: Signer,
+    {
+        future::result(
+            typed_data
+                .domain_separator()
+                .and_then(|domain| Ok((domain, typed_data.struct_hash()?))),
+        )
+        .and_then(move |(domain_separator, struct_hash)| signer.sign_typed_data(domain_separator, struct_hash))
+    }
+
+    /// Recovers the address which signed given hash.
+    ///
+    /// Accepts 65 bytes signatures (`r || s || v`) such as `SignedData::signature`.
+    pub fn recover(&self, hash: H256, signature: &[u8]) -> Result<Address, Error> {
+        signing::recover(&hash, signature)
+    }
+
+    /// Hash a message according to EIP-191.
+    ///
+    /// The data is a UTF-8 encoded string and will enveloped as follows:
+    /// `"\x19Ethereum Signed Message:\n" + message.length + message` and hashed
+    /// using keccak256.
+    pub fn hash_message<S>(&self, message: S) -> H256
+    where
+        S: AsRef<[u8]>,
+    {
+        signing::hash_message(message)
+    }
+
+    /// Sign arbitrary string data.
+    ///
+    /// The data is UTF-8 encoded and enveloped the same way as with
+    /// `hash_message`. The returned signed data's signature is in 'Electrum'
+    /// notation, that is the recovery value `v` is either `27` or `28` (as
+    /// opposed to the standard notation where `v` is either `0` or `1`). This
+    /// is important to consider when using this signature with other crates.
+    pub fn sign<S>(&self, message: S, key: &SecretKey) -> SignedData
+    where
+        S: AsRef<[u8]>,
+    {
+        signing::sign_message(message.as_ref(), key)
+    }
+}
+
+/// Resolves to the given value or fetches it from the node if missing.
+fn or_fetch<V, F, G>(value: Option<V>, fetch: G) -> Either<FutureResult<V, Error>, F>
+where
+    F: Future<Item = V, Error = Error>,
+    G: FnOnce() -> F,
+{
+    match value {
+        Some(value) => Either::A(future::ok(value)),
+        None => Either::B(fetch()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Accounts;
+    use crate::api::Namespace;
+    use crate::helpers::tests::TestTransport;
+    use crate::signing::{MockSigner, SecretKey, SignerRequest};
+    use crate::types::{AccessListItem, Address, Bytes, SignedData, TransactionParameters, H256};
+    use futures::Future;
+    use rustc_hex::FromHex;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    fn key() -> SecretKey {
+        SecretKey::from_str("4646464646464646464646464646464646464646464646464646464646464646").unwrap()
+    }
+
+    fn transaction() -> TransactionParameters {
+        TransactionParameters {
+            nonce: Some(9.into()),
+            to: Some(Address::from_str("3535353535353535353535353535353535353535").unwrap()),
+            gas: Some(21_000.into()),
+            gas_price: Some(20_000_000_000u64.into()),
+            value: 1_000_000_000_000_000_000u64.into(),
+            chain_id: Some(1),
+            ..Default::default()
+        }
+    }
+
+    fn bytes(hex: &str) -> Bytes {
+        hex.from_hex::<Vec<u8>>().unwrap().into()
+    }
+
+    #[test]
+    fn should_sign_legacy_transaction() {
+        // given
+        let mut transport = TestTransport::default();
+        let accounts = Accounts::new(&transport);
+
+        // when
+        let signed = accounts.sign_transaction(transaction(), &key()).wait().unwrap();
+
+        // then
+        transport.assert_no_more_requests();
+        assert_eq!(
+            signed.message_hash,
+            H256::from_str("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53").unwrap()
+        );
+        assert_eq!(signed.v, 37);
+        assert_eq!(
+            signed.raw_transaction,
+            bytes("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")
+        );
+        assert_eq!(
+            signed.transaction_hash,
+            H256::from_str("33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_sign_access_list_transaction() {
+        // given
+        let mut transport = TestTransport::default();
+        let accounts = Accounts::new(&transport);
+        let tx = TransactionParameters {
+            access_list: Some(vec![AccessListItem {
+                address: Address::from_str("3535353535353535353535353535353535353535").unwrap(),
+                storage_keys: vec![H256::from_low_u64_be(1)],
+            }]),
+            ..transaction()
+        };
+
+        // when
+        let signed = accounts.sign_transaction(tx, &key()).wait().unwrap();
+
+        // then
+        transport.assert_no_more_requests();
+        assert_eq!(
+            signed.message_hash,
+            H256::from_str("a4441968a0519a1bb79f9a4c352397415c411f98ff1e3bead9dea1f8813aaa37").unwrap()
+        );
+        assert_eq!(signed.v, 0);
+        assert_eq!(
+            signed.raw_transaction,
+            bytes("01f8a701098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080f838f7943535353535353535353535353535353535353535e1a0000000000000000000000000000000000000000000000000000000000000000180a0381f5272732aaecaa36e57234061be5c675ad65be3a7a8900421c6fe9060965fa01dc3c104e6b5f480132d393594bf20f1375e716a60a449b3bf855cd51f61b870")
+        );
+        assert_eq!(
+            signed.transaction_hash,
+            H256::from_str("e4eae69c2439a5b9b8f32eb7f0a31e965c0dca62947e2df71990fc6c32937249").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_sign_eip1559_transaction() {
+        // given
+        let mut transport = TestTransport::default();
+        let accounts = Accounts::new(&transport);
+        let tx = TransactionParameters {
+            gas_price: None,
+            max_fee_per_gas: Some(30_000_000_000u64.into()),
+            max_priority_fee_per_gas: Some(2_000_000_000u64.into()),
+            ..transaction()
+        };
+
+        // when
+        let signed = accounts.sign_transaction(tx, &key()).wait().unwrap();
+
+        // then
+        transport.assert_no_more_requests();
+        assert_eq!(
+            signed.message_hash,
+            H256::from_str("fae77debb64203fbaea6213fcde74f1b138c6854c3d7b44ba1c2ced52c2d8c4d").unwrap()
+        );
+        assert_eq!(signed.v, 0);
+        assert_eq!(
+            signed.raw_transaction,
+            bytes("02f873010984773594008506fc23ac00825208943535353535353535353535353535353535353535880de0b6b3a764000080c080a02b03b67e070f45175ce9d07c4512720168bd468a24edb6997977a53d48c87a12a0733d775fdd689d306e08ac8ab399f34b5a0253b47ed81b8bf2d2a6ea607fcac7")
+        );
+        assert_eq!(
+            signed.transaction_hash,
+            H256::from_str("bb94970b7e5afad02e4e38a462eacd085a96791deacaab2827d61aeb20e0778e").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_fetch_missing_transaction_parameters() {
+        // given
+        let mut transport = TestTransport::default();
+        transport.add_response(json!("0x9"));
+        transport.add_response(json!("0x1"));
+        transport.add_response(json!("0x4a817c800"));
+        let accounts = Accounts::new(&transport);
+        let tx = TransactionParameters {
+            nonce: None,
+            gas_price: None,
+            chain_id: None,
+            ..transaction()
+        };
+
+        // when
+        let signed = accounts.sign_transaction(tx, &key()).wait().unwrap();
+
+        // then
+        transport.assert_request(
+            "eth_getTransactionCount",
+            &[
+                r#""0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f""#.into(),
+                r#""pending""#.into(),
+            ],
+        );
+        transport.assert_request("eth_chainId", &[]);
+        transport.assert_request("eth_gasPrice", &[]);
+        transport.assert_no_more_requests();
+        assert_eq!(
+            signed.transaction_hash,
+            H256::from_str("33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_sign_filled_transaction_with_signer() {
+        // given
+        let mut transport = TestTransport::default();
+        transport.add_response(json!("0x9"));
+        let accounts = Accounts::new(&transport);
+        let signer = MockSigner::new(key());
+
+        // when
+        let signed = accounts
+            .sign_transaction_with(
+                TransactionParameters {
+                    nonce: None,
+                    ..transaction()
+                },
+                signer.clone(),
+            )
+            .wait()
+            .unwrap();
+
+        // then
+        transport.assert_request(
+            "eth_getTransactionCount",
+            &[
+                r#""0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f""#.into(),
+                r#""pending""#.into(),
+            ],
+        );
+        transport.assert_no_more_requests();
+        assert_eq!(signer.requests(), vec![SignerRequest::Transaction(transaction())]);
+        assert_eq!(
+            signed.transaction_hash,
+            H256::from_str("33469b22e9f636356c4160a87eb19df52b7412e8eac32a4a55ffe88ea8350788").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_sign_message() {
+        // given
+        let mut transport = TestTransport::default();
+        let accounts = Accounts::new(&transport);
+        let key = SecretKey::from_str("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap();
+
+        // when
+        let signed = accounts.sign("Some data", &key);
+
+        // then
+        transport.assert_no_more_requests();
+        assert_eq!(
+            signed,
+            SignedData {
+                message: b"Some data".to_vec(),
+                message_hash: H256::from_str("1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655")
+                    .unwrap(),
+                v: 28,
+                r: H256::from_str("b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd").unwrap(),
+                s: H256::from_str("6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a029").unwrap(),
+                signature: bytes("b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c"),
+            }
+        );
+    }
+}
@@ -144,11 +144,13 @@ impl<T: BatchTransport> Future for ReceiptProof<T> {
                 ReceiptProofState::Receipts(ref transaction, ref block, ref mut future) => {
                     let receipts = try_ready!(future.poll());
                     // build proof
-                    let raw_receipts:Vec<RawReceipt> = receipts.into_iter().filter(|x| x.is_some()).map(|x| {x.unwrap().into()}).collect();
-                    if raw_receipts.len() != block.transactions.len() {
+                    let rlp_receipts:Vec<Vec<u8>> = receipts.into_iter().filter_map(|x| x).map(|r| {
+                        let transaction_type = r.transaction_type.map(|t| t.low_u64() as u8);
+                        RawReceipt::from(r).trie_value(transaction_type)
+                    }).collect();
+                    if rlp_receipts.len() != block.transactions.len() {
                         return Err(Error::InvalidResponse("Expected got batch success".into()).into());
                     }
-                    let rlp_receipts:Vec<Vec<u8>> = raw_receipts.into_iter().map(|r| rlp::encode(&r)).collect();
                     let transaction_index: U128 = transaction.transaction_index.ok_or(Error::InvalidResponse("Expected transaction index".into()))?;
                     let index = transaction_index.low_u64() as usize;
                     let mut trie = build_order_trie(rlp_receipts)?;
@@ -3,8 +3,9 @@
 use crate::api::Namespace;
 use crate::helpers::{self, CallFuture};
 use crate::types::{
-    Address, Block, BlockId, BlockNumber, Bytes, CallRequest, Filter, Index, Log, SyncState, Transaction,
-    TransactionId, TransactionReceipt, TransactionRequest, Work, H256, H520, H64, U256, U64,
+    AccessListWithGasUsed, Address, Block, BlockId, BlockNumber, Bytes, CallRequest, FeeHistory, Filter, Index, Log,
+    SyncState, Transaction, TransactionId, TransactionReceipt, TransactionRequest, TypedData, Work, H256, H520, H64,
+    U256, U64,
 };
 use crate::Transport;
 
@@ -46,6 +47,11 @@ impl<T: Transport> Eth<T> {
         CallFuture::new(self.transport.execute("eth_call", vec![req, block]))
     }
 
+    /// Get chain id (EIP-695)
+    pub fn chain_id(&self) -> CallFuture<U256, T::Out> {
+        CallFuture::new(self.transport.execute("eth_chainId", vec![]))
+    }
+
     /// Get coinbase address
     pub fn coinbase(&self) -> CallFuture<Address, T::Out> {
         CallFuture::new(self.transport.execute("eth_coinbase", vec![]))
@@ -69,6 +75,18 @@ impl<T: Transport> Eth<T> {
         CallFuture::new(self.transport.execute("eth_compileSerpent", vec![code]))
     }
 
+    /// Generate an access list (EIP-2930) for a call along with the gas it would use.
+    pub fn create_access_list(
+        &self,
+        req: CallRequest,
+        block: Option<BlockNumber>,
+    ) -> CallFuture<AccessListWithGasUsed, T::Out> {
+        let req = helpers::serialize(&req);
+        let block = helpers::serialize(&block.unwrap_or(BlockNumber::Latest));
+
+        CallFuture::new(self.transport.execute("eth_createAccessList", vec![req, block]))
+    }
+
     /// Call a contract without changing the state of the blockchain to estimate gas usage.
     pub fn estimate_gas(&self, req: CallRequest, block: Option<BlockNumber>) -> CallFuture<U256, T::Out> {
         let req = helpers::serialize(&req);
@@ -77,6 +95,26 @@ impl<T: Transport> Eth<T> {
         CallFuture::new(self.transport.execute("eth_estimateGas", vec![req, block]))
     }
 
+    /// Get base fees and priority fees paid in a range of recent blocks.
+    ///
+    /// `reward_percentiles` are the percentiles (0 to 100, ascending) of effective priority fees
+    /// to sample from each block.
+    pub fn fee_history(
+        &self,
+        block_count: U256,
+        newest_block: BlockNumber,
+        reward_percentiles: Option<Vec<f64>>,
+    ) -> CallFuture<FeeHistory, T::Out> {
+        let block_count = helpers::serialize(&block_count);
+        let newest_block = helpers::serialize(&newest_block);
+        let reward_percentiles = helpers::serialize(&reward_percentiles.unwrap_or_default());
+
+        CallFuture::new(
+            self.transport
+                .execute("eth_feeHistory", vec![block_count, newest_block, reward_percentiles]),
+        )
+    }
+
     /// Get current recommended gas price
     pub fn gas_price(&self) -> CallFuture<U256, T::Out> {
         CallFuture::new(self.transport.execute("eth_gasPrice", vec![]))
@@ -256,6 +294,11 @@ impl<T: Transport> Eth<T> {
         CallFuture::new(self.transport.execute("eth_hashrate", vec![]))
     }
 
+    /// Get current recommended priority fee per gas (EIP-1559)
+    pub fn max_priority_fee_per_gas(&self) -> CallFuture<U256, T::Out> {
+        CallFuture::new(self.transport.execute("eth_maxPriorityFeePerGas", vec![]))
+    }
+
     /// Get mining status
     pub fn mining(&self) -> CallFuture<bool, T::Out> {
         CallFuture::new(self.transport.execute("eth_mining", vec![]))
@@ -295,6 +338,13 @@ impl<T: Transport> Eth<T> {
         CallFuture::new(self.transport.execute("eth_sign", vec![address, data]))
     }
 
+    /// Signs EIP-712 typed structured data (`eth_signTypedData_v4`)
+    pub fn sign_typed_data(&self, address: Address, typed_data: TypedData) -> CallFuture<H520, T::Out> {
+        let address = helpers::serialize(&address);
+        let typed_data = helpers::serialize(&typed_data);
+        CallFuture::new(self.transport.execute("eth_signTypedData_v4", vec![address, typed_data]))
+    }
+
     /// Submit hashrate of external miner
     pub fn submit_hashrate(&self, rate: U256, id: H256) -> CallFuture<bool, T::Out> {
         let rate = helpers::serialize(&rate);
@@ -325,10 +375,12 @@ mod tests {
     use serde_json::json;
 
     use crate::api::Namespace;
+    use crate::helpers::tests::TestTransport;
     use crate::rpc::Value;
     use crate::types::{
-        Address, Block, BlockId, BlockNumber, Bytes, CallRequest, FilterBuilder, Log, SyncInfo, SyncState, Transaction,
-        TransactionId, TransactionReceipt, TransactionRequest, Work, H256, H520, H64,
+        AccessListItem, AccessListWithGasUsed, Address, Block, BlockId, BlockNumber, Bytes, CallRequest, FeeHistory,
+        FilterBuilder, Log, SyncInfo, SyncState, Transaction, TransactionId, TransactionReceipt, TransactionRequest,
+        TypedData, Work, H256, H520, H64, U256,
     };
 
     use super::Eth;
@@ -392,6 +444,13 @@ mod tests {
   }"#;
 
     // taken from RPC docs.
+    const EXAMPLE_FEE_HISTORY: &'static str = r#"{
+    "oldestBlock": "0xc5043f",
+    "baseFeePerGas": ["0x3da8e7618", "0x3e1ba3b1b", "0x3dbd8aab3"],
+    "gasUsedRatio": [0.5290747666666666, 0.49240453333333334],
+    "reward": [["0x2540be400", "0x3b9aca00"], ["0x2540be400", "0x59682f00"]]
+  }"#;
+
     const EXAMPLE_RECEIPT: &'static str = r#"{
     "hash": "0xb903239f8543d04b5dc1ba6579132b143087c68db1b2168786408fcbce568238",
     "index": "0x1",
@@ -416,11 +475,18 @@ mod tests {
     Value::String("0x123".into()) => 0x123
   );
 
+    rpc_test! (
+    Eth:chain_id => "eth_chainId";
+    Value::String("0x1".into()) => 1
+  );
+
     rpc_test! (
     Eth:call, CallRequest {
       from: None, to: Address::from_low_u64_be(0x123),
       gas: None, gas_price: None,
       value: Some(0x1.into()), data: None,
+      transaction_type: None, access_list: None,
+      max_fee_per_gas: None, max_priority_fee_per_gas: None,
     }, None
     =>
     "eth_call", vec![r#"{"to":"0x0000000000000000000000000000000000000123","value":"0x1"}"#, r#""latest""#];
@@ -447,17 +513,53 @@ mod tests {
     Value::String("0x0123".into()) => Bytes(vec![0x1, 0x23])
   );
 
+    rpc_test! (
+    Eth:create_access_list, CallRequest {
+      from: Some(Address::from_low_u64_be(0x5)), to: Address::from_low_u64_be(0x123),
+      gas: None, gas_price: None,
+      value: None, data: Some(Bytes(vec![1, 2])),
+      transaction_type: None, access_list: None,
+      max_fee_per_gas: None, max_priority_fee_per_gas: None,
+    }, Some(BlockNumber::Pending)
+    =>
+    "eth_createAccessList", vec![r#"{"data":"0x0102","from":"0x0000000000000000000000000000000000000005","to":"0x0000000000000000000000000000000000000123"}"#, r#""pending""#];
+    json!({
+      "accessList": [{
+        "address": "0x0000000000000000000000000000000000000123",
+        "storageKeys": ["0x0000000000000000000000000000000000000000000000000000000000000001"]
+      }],
+      "gasUsed": "0x5e0b"
+    }) => AccessListWithGasUsed {
+      access_list: vec![AccessListItem {
+        address: Address::from_low_u64_be(0x123),
+        storage_keys: vec![H256::from_low_u64_be(1)],
+      }],
+      gas_used: 0x5e0b.into(),
+      error: None,
+    }
+  );
+
     rpc_test! (
     Eth:estimate_gas, CallRequest {
       from: None, to: Address::from_low_u64_be(0x123),
       gas: None, gas_price: None,
       value: Some(0x1.into()), data: None,
+      transaction_type: None, access_list: None,
+      max_fee_per_gas: None, max_priority_fee_per_gas: None,
     }, None
     =>
     "eth_estimateGas", vec![r#"{"to":"0x0000000000000000000000000000000000000123","value":"0x1"}"#, r#""latest""#];
     Value::String("0x123".into()) => 0x123
   );
 
+    rpc_test! (
+    Eth:fee_history, U256::from(0x2), BlockNumber::Latest, Some(vec![10.0, 50.0])
+    =>
+    "eth_feeHistory", vec![r#""0x2""#, r#""latest""#, r#"[10.0,50.0]"#];
+    ::serde_json::from_str(EXAMPLE_FEE_HISTORY).unwrap()
+    => ::serde_json::from_str::<FeeHistory>(EXAMPLE_FEE_HISTORY).unwrap()
+  );
+
     rpc_test! (
     Eth:gas_price => "eth_gasPrice";
     Value::String("0x123".into()) => 0x123
@@ -644,6 +746,11 @@ mod tests {
     Value::String("0x123".into()) => 0x123
   );
 
+    rpc_test! (
+    Eth:max_priority_fee_per_gas => "eth_maxPriorityFeePerGas";
+    Value::String("0x3b9aca00".into()) => 0x3b9aca00
+  );
+
     rpc_test! (
     Eth:mining => "eth_mining";
     Value::Bool(true) => true
@@ -676,6 +783,8 @@ mod tests {
       gas: None, gas_price: Some(0x1.into()),
       value: Some(0x1.into()), data: None,
       nonce: None, condition: None,
+      transaction_type: None, access_list: None,
+      max_fee_per_gas: None, max_priority_fee_per_gas: None,
     }
     =>
     "eth_sendTransaction", vec![r#"{"from":"0x0000000000000000000000000000000000000123","gasPrice":"0x1","to":"0x0000000000000000000000000000000000000123","value":"0x1"}"#];
@@ -689,6 +798,36 @@ mod tests {
     Value::String("0x0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000123".into()) => H520::from_low_u64_be(0x123)
   );
 
+    #[test]
+    fn sign_typed_data() {
+        // given
+        let mut transport = TestTransport::default();
+        transport.set_response(Value::String(format!("{:?}", H520::from_low_u64_be(0x123))));
+        let typed_data: TypedData = serde_json::from_value(json!({
+            "types": { "Test": [{ "name": "value", "type": "uint256" }] },
+            "primaryType": "Test",
+            "domain": { "name": "Test" },
+            "message": { "value": 1 }
+        }))
+        .unwrap();
+
+        // when
+        let result = Eth::new(&transport)
+            .sign_typed_data(Address::from_low_u64_be(0x123), typed_data)
+            .wait();
+
+        // then
+        transport.assert_request(
+            "eth_signTypedData_v4",
+            &[
+                r#""0x0000000000000000000000000000000000000123""#.into(),
+                r#"{"domain":{"name":"Test"},"message":{"value":1},"primaryType":"Test","types":{"Test":[{"name":"value","type":"uint256"}]}}"#.into(),
+            ],
+        );
+        transport.assert_no_more_requests();
+        assert_eq!(result, Ok(H520::from_low_u64_be(0x123)));
+    }
+
     rpc_test! (
     Eth:submit_hashrate, 0x123, H256::from_low_u64_be(0x456)
     =>
@@ -1,5 +1,7 @@
 //! `Web3` implementation
 
+#[cfg(feature = "signing")]
+mod accounts;
 mod eth;
 mod eth_filter;
 mod eth_subscribe;
@@ -12,6 +14,8 @@ mod web3;
 mod abos;
 mod bool;
 
+#[cfg(feature = "signing")]
+pub use self::accounts::Accounts;
 pub use self::eth::Eth;
 pub use self::eth_filter::{BaseFilter, CreateFilter, EthFilter, FilterStream};
 pub use self::eth_subscribe::{EthSubscribe, SubscriptionId, SubscriptionResult, SubscriptionStream};
@@ -60,6 +64,12 @@ impl<T: Transport> Web3<T> {
         A::new(self.transport.clone())
     }
 
+    /// Access methods from `accounts` namespace
+    #[cfg(feature = "signing")]
+    pub fn accounts(&self) -> accounts::Accounts<T> {
+        self.api()
+    }
+
     /// Access methods from `eth` namespace
     pub fn eth(&self) -> eth::Eth<T> {
         self.api()
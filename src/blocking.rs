@@ -0,0 +1,408 @@
+//! Blocking (synchronous) client.
+//!
+//! Wraps `Web3` so that every call waits for its result, for use in CLIs and scripts
+//! which don't want to manage an event loop:
+//!
+//! ```rust,no_run
+//! let web3 = web3::blocking::Web3::http("http://localhost:8545").unwrap();
+//! let accounts = web3.eth().accounts().unwrap();
+//! println!("Accounts: {:?}", accounts);
+//! ```
+
+use crate::abos_types as abos;
+use crate::api;
+use crate::contract::{self, deploy, tokens::Detokenize, tokens::Tokenize, Options};
+use crate::error::Error;
+#[cfg(feature = "signing")]
+use crate::signing::SecretKey;
+#[cfg(any(feature = "ipc", feature = "http", feature = "ws"))]
+use crate::transports::EventLoopHandle;
+use crate::types::{
+    AccessListWithGasUsed, Address, Block, BlockId, BlockNumber, Bytes, CallRequest, FeeHistory, Filter, Index, Log,
+    RawHeader, RawReceipt, SyncState, Transaction, TransactionId, TransactionReceipt, TransactionRequest, TypedData,
+    Work, H256, H520, H64, U256, U64,
+};
+use crate::{BatchTransport, Transport};
+use futures::Future;
+use std::time::Duration;
+
+/// Blocking `Web3` client.
+///
+/// Owns the event loop of the transport (if any), which is stopped when the client is dropped.
+#[derive(Debug, Clone)]
+pub struct Web3<T: Transport> {
+    web3: api::Web3<T>,
+    #[cfg(any(feature = "ipc", feature = "http", feature = "ws"))]
+    _event_loop: Option<std::sync::Arc<EventLoopHandle>>,
+}
+
+impl<T: Transport> Web3<T> {
+    /// Creates new blocking client with given transport.
+    ///
+    /// The transport has to make progress on its own, e.g. be driven by an `EventLoopHandle`
+    /// that outlives the client.
+    pub fn new(transport: T) -> Self {
+        Web3 {
+            web3: api::Web3::new(transport),
+            #[cfg(any(feature = "ipc", feature = "http", feature = "ws"))]
+            _event_loop: None,
+        }
+    }
+
+    /// Creates new blocking client taking ownership of the transport's event loop.
+    #[cfg(any(feature = "ipc", feature = "http", feature = "ws"))]
+    pub fn with_event_loop(event_loop: EventLoopHandle, transport: T) -> Self {
+        Web3 {
+            web3: api::Web3::new(transport),
+            _event_loop: Some(std::sync::Arc::new(event_loop)),
+        }
+    }
+
+    /// Borrows a transport.
+    pub fn transport(&self) -> &T {
+        self.web3.transport()
+    }
+
+    /// Returns the asynchronous client.
+    pub fn inner(&self) -> &api::Web3<T> {
+        &self.web3
+    }
+
+    /// Access methods from `eth` namespace
+    pub fn eth(&self) -> Eth<T> {
+        Eth { api: self.web3.eth() }
+    }
+
+    /// Access methods from `abos` namespace
+    pub fn abos(&self) -> Abos<T> {
+        Abos { api: self.web3.abos() }
+    }
+
+    /// Access contract at given address.
+    pub fn contract(&self, address: Address, json: &[u8]) -> Result<Contract<T>, ethabi::Error> {
+        contract::Contract::from_json(self.web3.eth(), address, json).map(|contract| Contract { contract })
+    }
+
+    /// Deploys a contract, waiting for the deployment transaction to be confirmed.
+    ///
+    /// Use `inner()` and `contract::Contract::deploy` for more deployment options.
+    pub fn deploy<P, V>(&self, json: &[u8], code: V, params: P, from: Address) -> Result<Contract<T>, deploy::Error>
+    where
+        P: Tokenize,
+        V: AsRef<str>,
+    {
+        contract::Contract::deploy(self.web3.eth(), json)?
+            .execute(code, params, from)?
+            .wait()
+            .map(|contract| Contract { contract })
+    }
+
+    /// Sends transaction and waits until it is confirmed
+    pub fn send_transaction_with_confirmation(
+        &self,
+        tx: TransactionRequest,
+        poll_interval: Duration,
+        confirmations: usize,
+    ) -> Result<TransactionReceipt, Error> {
+        self.web3
+            .send_transaction_with_confirmation(tx, poll_interval, confirmations)
+            .wait()
+    }
+
+    /// Sends raw transaction and waits until it is confirmed
+    pub fn send_raw_transaction_with_confirmation(
+        &self,
+        tx: Bytes,
+        poll_interval: Duration,
+        confirmations: usize,
+    ) -> Result<TransactionReceipt, Error> {
+        self.web3
+            .send_raw_transaction_with_confirmation(tx, poll_interval, confirmations)
+            .wait()
+    }
+}
+
+impl<T: BatchTransport> Web3<T> {
+    /// Access methods from `bool` namespace
+    pub fn bool(&self) -> Bool<T> {
+        Bool { api: self.web3.api() }
+    }
+}
+
+#[cfg(feature = "http")]
+impl Web3<crate::transports::Http> {
+    /// Creates new blocking client connected to given HTTP endpoint.
+    pub fn http(url: &str) -> Result<Self, Error> {
+        let (event_loop, transport) = crate::transports::Http::new(url)?;
+        Ok(Web3::with_event_loop(event_loop, transport))
+    }
+}
+
+#[cfg(feature = "ws")]
+impl Web3<crate::transports::WebSocket> {
+    /// Creates new blocking client connected to given WebSocket endpoint.
+    pub fn ws(url: &str) -> Result<Self, Error> {
+        let (event_loop, transport) = crate::transports::WebSocket::new(url)?;
+        Ok(Web3::with_event_loop(event_loop, transport))
+    }
+}
+
+#[cfg(feature = "ipc")]
+impl Web3<crate::transports::Ipc> {
+    /// Creates new blocking client connected to given IPC socket.
+    pub fn ipc<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Error> {
+        let (event_loop, transport) = crate::transports::Ipc::new(path)?;
+        Ok(Web3::with_event_loop(event_loop, transport))
+    }
+}
+
+macro_rules! blocking_namespace {
+    (
+        $(#[$meta:meta])*
+        $name:ident<T: $bound:ident> {
+            $( fn $method:ident(&self $(, $arg:ident: $arg_ty:ty)*) -> $ret:ty; )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone)]
+        pub struct $name<T> {
+            api: api::$name<T>,
+        }
+
+        impl<T: $bound> $name<T> {
+            /// Returns the asynchronous namespace.
+            pub fn inner(&self) -> &api::$name<T> {
+                &self.api
+            }
+
+            $(
+                #[doc = concat!("Blocking version of `", stringify!($name), "::", stringify!($method), "`.")]
+                pub fn $method(&self $(, $arg: $arg_ty)*) -> Result<$ret, Error> {
+                    self.api.$method($($arg),*).wait()
+                }
+            )*
+        }
+    };
+}
+
+blocking_namespace! {
+    /// Blocking `eth` namespace
+    Eth<T: Transport> {
+        fn accounts(&self) -> Vec<Address>;
+        fn block_number(&self) -> U64;
+        fn call(&self, req: CallRequest, block: Option<BlockNumber>) -> Bytes;
+        fn chain_id(&self) -> U256;
+        fn coinbase(&self) -> Address;
+        fn compile_lll(&self, code: String) -> Bytes;
+        fn compile_solidity(&self, code: String) -> Bytes;
+        fn compile_serpent(&self, code: String) -> Bytes;
+        fn create_access_list(&self, req: CallRequest, block: Option<BlockNumber>) -> AccessListWithGasUsed;
+        fn estimate_gas(&self, req: CallRequest, block: Option<BlockNumber>) -> U256;
+        fn fee_history(
+            &self,
+            block_count: U256,
+            newest_block: BlockNumber,
+            reward_percentiles: Option<Vec<f64>>
+        ) -> FeeHistory;
+        fn gas_price(&self) -> U256;
+        fn balance(&self, address: Address, block: Option<BlockNumber>) -> U256;
+        fn logs(&self, filter: Filter) -> Vec<Log>;
+        fn block(&self, block: BlockId) -> Option<Block<H256>>;
+        fn block_with_txs(&self, block: BlockId) -> Option<Block<Transaction>>;
+        fn block_transaction_count(&self, block: BlockId) -> Option<U256>;
+        fn code(&self, address: Address, block: Option<BlockNumber>) -> Bytes;
+        fn compilers(&self) -> Vec<String>;
+        fn storage(&self, address: Address, idx: U256, block: Option<BlockNumber>) -> H256;
+        fn transaction_count(&self, address: Address, block: Option<BlockNumber>) -> U256;
+        fn transaction(&self, id: TransactionId) -> Option<Transaction>;
+        fn transaction_receipt(&self, hash: H256) -> Option<TransactionReceipt>;
+        fn uncle(&self, block: BlockId, index: Index) -> Option<Block<H256>>;
+        fn uncle_count(&self, block: BlockId) -> Option<U256>;
+        fn work(&self) -> Work;
+        fn hashrate(&self) -> U256;
+        fn max_priority_fee_per_gas(&self) -> U256;
+        fn mining(&self) -> bool;
+        fn new_block_filter(&self) -> U256;
+        fn new_pending_transaction_filter(&self) -> U256;
+        fn protocol_version(&self) -> String;
+        fn send_raw_transaction(&self, rlp: Bytes) -> H256;
+        fn send_transaction(&self, tx: TransactionRequest) -> H256;
+        fn sign(&self, address: Address, data: Bytes) -> H520;
+        fn sign_typed_data(&self, address: Address, typed_data: TypedData) -> H520;
+        fn submit_hashrate(&self, rate: U256, id: H256) -> bool;
+        fn submit_work(&self, nonce: H64, pow_hash: H256, mix_hash: H256) -> bool;
+        fn syncing(&self) -> SyncState;
+    }
+}
+
+blocking_namespace! {
+    /// Blocking `abos` namespace
+    Abos<T: Transport> {
+        fn block_number(&self) -> U64;
+        fn peer_count(&self) -> U256;
+        fn send_raw_transaction(&self, rlp: Bytes) -> abos::TxResponse;
+        fn send_transaction(&self, tx: String) -> abos::TxResponse;
+        fn block(&self, block: BlockId) -> Option<abos::Block>;
+        fn transaction_receipt(&self, hash: H256) -> Option<abos::Receipt>;
+        fn logs(&self, filter: abos::Filter) -> Vec<abos::Log>;
+        fn call(&self, req: abos::CallRequest, block: Option<abos::BlockNumber>) -> Bytes;
+        fn transaction(&self, hash: H256) -> Option<abos::BlockTransaction>;
+        fn transaction_count(&self, address: Address, block: Option<abos::BlockNumber>) -> U256;
+        fn code(&self, address: Address, block: Option<abos::BlockNumber>) -> Bytes;
+        fn abi(&self, address: Address, block: Option<abos::BlockNumber>) -> Bytes;
+        fn balance(&self, address: Address, block: Option<abos::BlockNumber>) -> U256;
+        fn new_filter(&self, filter: abos::Filter) -> U256;
+        fn new_block_filter(&self) -> U256;
+        fn uninstall_filter(&self, quantity: U256) -> bool;
+        fn filter_changes(&self, quantity: U256) -> Vec<abos::Log>;
+        fn filter_logs(&self, quantity: U256) -> Vec<abos::Log>;
+        fn transaction_proof(&self, data: H256) -> Option<Bytes>;
+        fn meta_data(&self, block: Option<abos::BlockNumber>) -> abos::MetaData;
+        fn get_storage_at(&self, address: Address, key: H256, block: Option<abos::BlockNumber>) -> Option<Bytes>;
+    }
+}
+
+blocking_namespace! {
+    /// Blocking `bool` namespace
+    Bool<T: BatchTransport> {
+        fn raw_header(&self, block_id: BlockId) -> Option<RawHeader>;
+        fn raw_transaction_receipt(&self, hash: H256) -> Option<RawReceipt>;
+        fn receipts(&self, hashs: Vec<H256>) -> Vec<Option<TransactionReceipt>>;
+        fn blocks(&self, block_ids: Vec<BlockId>) -> Vec<Option<Block<H256>>>;
+        fn receipt_proof(&self, hash: H256) -> Option<(u64, Vec<u8>, H256)>;
+    }
+}
+
+/// Blocking contract interface
+#[derive(Debug, Clone)]
+pub struct Contract<T: Transport> {
+    contract: contract::Contract<T>,
+}
+
+impl<T: Transport> Contract<T> {
+    /// Returns the asynchronous contract.
+    pub fn inner(&self) -> &contract::Contract<T> {
+        &self.contract
+    }
+
+    /// Returns contract address
+    pub fn address(&self) -> Address {
+        self.contract.address()
+    }
+
+    /// Execute a contract function, returns the transaction hash.
+    pub fn call<P: Tokenize>(
+        &self,
+        func: &str,
+        params: P,
+        from: Address,
+        options: Options,
+    ) -> Result<H256, contract::Error> {
+        self.contract.call(func, params, from, options).wait()
+    }
+
+    /// Execute a contract function and wait for confirmations
+    pub fn call_with_confirmations<P: Tokenize>(
+        &self,
+        func: &str,
+        params: P,
+        from: Address,
+        options: Options,
+        confirmations: usize,
+    ) -> Result<TransactionReceipt, Error> {
+        self.contract
+            .call_with_confirmations(func, params, from, options, confirmations)
+            .wait()
+    }
+
+    /// Execute a contract function with a transaction signed locally by the given key.
+    #[cfg(feature = "signing")]
+    pub fn signed_call<P: Tokenize>(
+        &self,
+        func: &str,
+        params: P,
+        options: Options,
+        key: &SecretKey,
+    ) -> Result<H256, contract::Error> {
+        self.contract.signed_call(func, params, options, key).wait()
+    }
+
+    /// Estimate gas required for this function call.
+    pub fn estimate_gas<P: Tokenize>(
+        &self,
+        func: &str,
+        params: P,
+        from: Address,
+        options: Options,
+    ) -> Result<U256, contract::Error> {
+        self.contract.estimate_gas(func, params, from, options).wait()
+    }
+
+    /// Call constant function
+    pub fn query<R, A, B, P>(
+        &self,
+        func: &str,
+        params: P,
+        from: A,
+        options: Options,
+        block: B,
+    ) -> Result<R, contract::Error>
+    where
+        R: Detokenize,
+        A: Into<Option<Address>>,
+        B: Into<Option<BlockNumber>>,
+        P: Tokenize,
+    {
+        self.contract.query(func, params, from, options, block).wait()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Web3;
+    use crate::transports::MockTransport;
+    use crate::types::{Address, U256, U64};
+    use crate::Error;
+
+    #[test]
+    fn should_wait_for_results() {
+        // given
+        let mock = MockTransport::new();
+        mock.respond("eth_blockNumber", "0x5");
+        mock.fail("eth_gasPrice", Error::Unreachable);
+        let web3 = Web3::new(mock.clone());
+
+        // when
+        let number = web3.eth().block_number();
+        let gas_price = web3.eth().gas_price();
+
+        // then
+        assert_eq!(number, Ok(U64::from(5)));
+        assert_eq!(gas_price, Err(Error::Unreachable));
+        mock.assert_request("eth_blockNumber", &[]);
+        mock.assert_request("eth_gasPrice", &[]);
+        mock.assert_no_more_requests();
+    }
+
+    #[test]
+    fn should_query_contract() {
+        // given
+        let mock = MockTransport::new();
+        mock.respond(
+            "eth_call",
+            "0x0000000000000000000000000000000000000000000000000000000000000020",
+        );
+        let web3 = Web3::new(mock);
+        let token = web3
+            .contract(Address::from_low_u64_be(1), include_bytes!("./contract/res/token.json"))
+            .unwrap();
+
+        // when
+        let balance: U256 = token
+            .query("balanceOf", Address::from_low_u64_be(5), None, Default::default(), None)
+            .unwrap();
+
+        // then
+        assert_eq!(balance, 0x20.into());
+    }
+}
@@ -0,0 +1,205 @@
+//! Compatibility layer between the futures 0.1 API and `std::future` (async/await).
+//!
+//! This is not a native async API: the crate, including all built-in transports, is still built on
+//! futures 0.1 and tokio-core. This module only adapts it:
+//!
+//! - every future and stream returned by the API (`CallFuture`, `FilterStream`, `Confirmations`, ...)
+//!   can be awaited after calling `.compat()` from `Future01CompatExt`/`Stream01CompatExt`,
+//! - `Async` exposes any `Transport` through the `AsyncTransport`/`AsyncBatchTransport` traits,
+//! - `Legacy` turns any `AsyncTransport` into a `Transport` usable with `Web3`.
+//!
+//! Built-in transports drive their connections on their own `EventLoopHandle` thread, so the
+//! resulting futures can be awaited from any executor. The opposite does not hold: futures returned
+//! through `Legacy` are polled by whoever polls the `Web3` future (e.g. `.wait()` or a tokio-core
+//! reactor), so an `AsyncTransport` relying on a runtime context (like tokio 1 sockets or timers)
+//! panics unless it is polled within that runtime.
+
+use crate::api::SubscriptionId;
+use crate::rpc;
+use crate::transports::Result;
+use crate::{BatchTransport, DuplexTransport, RequestId, Transport};
+use futures03::compat::{Compat, Compat01As03};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+pub use futures03::compat::{Future01CompatExt, Stream01CompatExt};
+
+/// Boxed `std::future` result.
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'static>>;
+
+/// Transport implementation returning `std::future` futures.
+///
+/// Mirrors `Transport`.
+pub trait AsyncTransport: fmt::Debug + Clone {
+    /// The type of future this transport returns when a call is made.
+    type Out: Future<Output = Result<rpc::Value>>;
+
+    /// Prepare serializable RPC call for given method with parameters.
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call);
+
+    /// Execute prepared RPC call.
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out;
+
+    /// Execute remote method with given parameters.
+    fn execute(&self, method: &str, params: Vec<rpc::Value>) -> Self::Out {
+        let (id, request) = self.prepare(method, params);
+        self.send(id, request)
+    }
+}
+
+/// Async transport implementation supporting batch requests.
+///
+/// Mirrors `BatchTransport`.
+pub trait AsyncBatchTransport: AsyncTransport {
+    /// The type of future this transport returns when a batch is sent.
+    type Batch: Future<Output = Result<Vec<Result<rpc::Value>>>>;
+
+    /// Sends a batch of prepared RPC calls.
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, rpc::Call)>;
+}
+
+/// Exposes a futures 0.1 `Transport` as an `AsyncTransport`.
+#[derive(Debug, Clone)]
+pub struct Async<T> {
+    transport: T,
+}
+
+impl<T: Transport> Async<T> {
+    /// Wraps given transport.
+    pub fn new(transport: T) -> Self {
+        Async { transport }
+    }
+
+    /// Returns the underlying transport.
+    pub fn inner(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: DuplexTransport> Async<T> {
+    /// Returns a `std` stream of notifications of given subscription.
+    pub fn subscribe(&self, id: &SubscriptionId) -> Compat01As03<T::NotificationStream> {
+        Compat01As03::new(self.transport.subscribe(id))
+    }
+
+    /// Removes a subscription.
+    pub fn unsubscribe(&self, id: &SubscriptionId) {
+        self.transport.unsubscribe(id)
+    }
+}
+
+impl<T: Transport> AsyncTransport for Async<T> {
+    type Out = Compat01As03<T::Out>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        Compat01As03::new(self.transport.send(id, request))
+    }
+}
+
+impl<T: BatchTransport> AsyncBatchTransport for Async<T> {
+    type Batch = Compat01As03<T::Batch>;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        Compat01As03::new(self.transport.send_batch(requests))
+    }
+}
+
+/// Exposes an `AsyncTransport` as a futures 0.1 `Transport`, e.g. to use it with `Web3`.
+///
+/// The wrapped futures are polled by the futures 0.1 task driving the call, the transport
+/// must not rely on being polled within a particular runtime (see the module docs).
+#[derive(Debug, Clone)]
+pub struct Legacy<T> {
+    transport: T,
+}
+
+impl<T: AsyncTransport> Legacy<T> {
+    /// Wraps given transport.
+    pub fn new(transport: T) -> Self {
+        Legacy { transport }
+    }
+
+    /// Returns the underlying transport.
+    pub fn inner(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: AsyncTransport> Transport for Legacy<T> {
+    type Out = Compat<Pin<Box<T::Out>>>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        Compat::new(Box::pin(self.transport.send(id, request)))
+    }
+}
+
+impl<T: AsyncBatchTransport> BatchTransport for Legacy<T> {
+    type Batch = Compat<Pin<Box<T::Batch>>>;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        Compat::new(Box::pin(self.transport.send_batch(requests)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Async, AsyncBatchTransport, AsyncTransport, Future01CompatExt, Legacy};
+    use crate::api::Web3;
+    use crate::rpc;
+    use crate::transports::MockTransport;
+    use crate::types::U64;
+    use crate::Error;
+    use futures::Future;
+    use futures03::executor::block_on;
+
+    #[test]
+    fn should_await_transport_calls() {
+        // given
+        let mock = MockTransport::new();
+        mock.respond("eth_blockNumber", "0x1");
+        mock.fail("eth_gasPrice", Error::Unreachable);
+        let transport = Async::new(mock);
+
+        // when
+        let number = block_on(transport.execute("eth_blockNumber", vec![]));
+        let batch = block_on(transport.send_batch(vec![transport.prepare("eth_gasPrice", vec![])]));
+
+        // then
+        assert_eq!(number, Ok(rpc::Value::String("0x1".into())));
+        assert_eq!(batch, Ok(vec![Err(Error::Unreachable)]));
+    }
+
+    #[test]
+    fn should_use_async_transport_with_web3() {
+        // given
+        let mock = MockTransport::new();
+        mock.respond("eth_blockNumber", "0x2");
+        mock.respond("eth_blockNumber", "0x3");
+        let web3 = Web3::new(Legacy::new(Async::new(mock)));
+
+        // when
+        let blocking = web3.eth().block_number().wait();
+        let awaited = block_on(web3.eth().block_number().compat());
+
+        // then
+        assert_eq!(blocking, Ok(U64::from(2)));
+        assert_eq!(awaited, Ok(U64::from(3)));
+    }
+}
@@ -2,8 +2,14 @@
 
 use std::time::Duration;
 
+#[cfg(feature = "signing")]
+use crate::api::Accounts;
 use crate::api::{CreateFilter, Eth, EthFilter, FilterStream, Namespace};
 use crate::helpers::CallFuture;
+#[cfg(feature = "signing")]
+use crate::signing::Signer;
+#[cfg(feature = "signing")]
+use crate::types::TransactionParameters;
 use crate::types::{Bytes, TransactionReceipt, TransactionRequest, H256, U64};
 use crate::{Error, Transport};
 use futures::stream::Skip;
@@ -324,6 +330,26 @@ where
     SendTransactionWithConfirmation::raw(transport, tx, poll_interval, confirmations)
 }
 
+/// Signs transaction with given signer, sends it and returns future resolved after transaction is confirmed
+#[cfg(feature = "signing")]
+pub fn send_transaction_with_signer<T, S>(
+    transport: T,
+    tx: TransactionParameters,
+    signer: S,
+    poll_interval: Duration,
+    confirmations: usize,
+) -> impl Future<Item = TransactionReceipt, Error = Error>
+where
+    T: Transport,
+    S: Signer,
+{
+    Accounts::new(transport.clone())
+        .sign_transaction_with(tx, signer)
+        .and_then(move |signed| {
+            send_raw_transaction_with_confirmation(transport, signed.raw_transaction, poll_interval, confirmations)
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::send_transaction_with_confirmation;
@@ -347,6 +373,10 @@ mod tests {
             data: None,
             nonce: None,
             condition: None,
+            transaction_type: None,
+            access_list: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
         };
 
         let transaction_receipt = TransactionReceipt {
@@ -361,6 +391,8 @@ mod tests {
             status: Some(1.into()),
             logs_bloom: Default::default(),
             root: None,
+            transaction_type: None,
+            effective_gas_price: None,
         };
 
         let poll_interval = Duration::from_secs(0);
@@ -8,7 +8,9 @@ use std::{collections::HashMap, time};
 use crate::api::{Eth, Namespace};
 use crate::confirm;
 use crate::contract::tokens::Tokenize;
-use crate::contract::{Contract, Options};
+use crate::contract::{Contract, CustomError, Options};
+#[cfg(feature = "signing")]
+use crate::signing::Signer;
 use crate::types::{Address, Bytes, TransactionReceipt, TransactionRequest};
 use crate::Transport;
 
@@ -19,6 +21,7 @@ pub use crate::contract::error::deploy::Error;
 pub struct Builder<T: Transport> {
     pub(crate) eth: Eth<T>,
     pub(crate) abi: ethabi::Contract,
+    pub(crate) errors: Vec<CustomError>,
     pub(crate) options: Options,
     pub(crate) confirmations: usize,
     pub(crate) poll_interval: time::Duration,
@@ -93,6 +96,33 @@ impl<T: Transport> Builder<T> {
         })
     }
 
+    /// Execute deployment passing code and contructor parameters.
+    ///
+    /// The deployment transaction is signed by given `signer` and sent with
+    /// `send_raw_transaction_with_confirmation`. Missing nonce, gas, gas price, chain id and EIP-1559 fees
+    /// are fetched from the node.
+    #[cfg(feature = "signing")]
+    pub fn execute_with_signer<P, V, S>(
+        self,
+        code: V,
+        params: P,
+        signer: S,
+    ) -> Result<PendingContract<T, impl Future<Item = TransactionReceipt, Error = crate::error::Error>>, ethabi::Error>
+    where
+        P: Tokenize,
+        V: AsRef<str>,
+        S: Signer,
+    {
+        let transport = self.eth.transport().clone();
+        let poll_interval = self.poll_interval;
+        let confirmations = self.confirmations;
+        let from = signer.address();
+
+        self.do_execute(code, params, from, move |tx| {
+            confirm::send_transaction_with_signer(transport, tx.into(), signer, poll_interval, confirmations)
+        })
+    }
+
     fn do_execute<P, V, Ft>(
         self,
         code: V,
@@ -142,6 +172,10 @@ impl<T: Transport> Builder<T> {
             nonce: options.nonce,
             data: Some(Bytes(data)),
             condition: options.condition,
+            transaction_type: options.transaction_type,
+            access_list: options.access_list,
+            max_fee_per_gas: options.max_fee_per_gas,
+            max_priority_fee_per_gas: options.max_priority_fee_per_gas,
         };
 
         let waiting = send(tx);
@@ -149,6 +183,7 @@ impl<T: Transport> Builder<T> {
         Ok(PendingContract {
             eth: Some(eth),
             abi: Some(abi),
+            errors: self.errors,
             waiting,
         })
     }
@@ -161,6 +196,7 @@ pub struct PendingContract<
 > {
     eth: Option<Eth<T>>,
     abi: Option<ethabi::Contract>,
+    errors: Vec<CustomError>,
     waiting: F,
 }
 
@@ -178,7 +214,10 @@ impl<T: Transport, F: Future<Item = TransactionReceipt, Error = crate::error::Er
             // If the `status` field is not present we use the presence of `contract_address` to
             // determine if deployment was successfull.
             _ => match receipt.contract_address {
-                Some(address) => Ok(Async::Ready(Contract::new(eth, address, abi))),
+                Some(address) => {
+                    let errors = std::mem::replace(&mut self.errors, vec![]);
+                    Ok(Async::Ready(Contract::new(eth, address, abi).with_errors(errors)))
+                }
                 None => Err(Error::ContractDeploymentFailure(receipt.transaction_hash)),
             },
         }
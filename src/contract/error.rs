@@ -3,6 +3,7 @@
 use ethabi::Error as EthError;
 
 use crate::error::Error as ApiError;
+use crate::types::{CustomError, Revert};
 use derive_more::{Display, From};
 
 /// Contract error.
@@ -17,6 +18,19 @@ pub enum Error {
     /// Rpc error
     #[display(fmt = "Api error: {}", _0)]
     Api(ApiError),
+    /// call reverted
+    #[display(fmt = "Execution reverted: {}", _0)]
+    Revert(Revert),
+}
+
+impl Error {
+    /// Converts a reverted call into `Error::Revert`, matching custom errors against given definitions.
+    pub(crate) fn from_api(error: ApiError, errors: &[CustomError]) -> Self {
+        match error.revert_data() {
+            Some(data) => Error::Revert(Revert::decode(&data.0, errors)),
+            None => Error::Api(error),
+        }
+    }
 }
 
 impl std::error::Error for Error {
@@ -25,6 +39,7 @@ impl std::error::Error for Error {
             Error::InvalidOutputType(_) => None,
             Error::Abi(ref e) => Some(e),
             Error::Api(ref e) => Some(e),
+            Error::Revert(_) => None,
         }
     }
 }
@@ -43,6 +58,9 @@ pub mod deploy {
         /// Contract deployment failed
         #[display(fmt = "Failure during deployment.Tx hash: {:?}", _0)]
         ContractDeploymentFailure(H256),
+        /// eth abi error
+        #[display(fmt = "Abi error: {}", _0)]
+        Abi(EthError),
     }
 
     impl std::error::Error for Error {
@@ -50,6 +68,7 @@ pub mod deploy {
             match *self {
                 Error::Api(ref e) => Some(e),
                 Error::ContractDeploymentFailure(_) => None,
+                Error::Abi(ref e) => Some(e),
             }
         }
     }
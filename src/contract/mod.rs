@@ -2,11 +2,22 @@
 
 use ethabi;
 
+#[cfg(feature = "signing")]
+use crate::api::Accounts;
 use crate::api::{Eth, Namespace};
 use crate::confirm;
 use crate::contract::tokens::{Detokenize, Tokenize};
-use crate::types::{Address, BlockNumber, Bytes, CallRequest, TransactionCondition, TransactionRequest, H256, U256};
+use crate::error::Error as ApiError;
+#[cfg(feature = "signing")]
+use crate::signing::{LocalSigner, SecretKey, Signer};
+use crate::types::{
+    AccessList, AccessListWithGasUsed, Address, BlockNumber, Bytes, CallRequest, TransactionCondition,
+    TransactionRequest, H256, U256, U64,
+};
+#[cfg(feature = "signing")]
+use crate::types::{TransactionParameters, TransactionReceipt};
 use crate::Transport;
+use futures::{future, Future};
 use std::{collections::HashMap, hash::Hash, time};
 
 pub mod deploy;
@@ -16,6 +27,7 @@ pub mod tokens;
 
 pub use crate::contract::error::Error;
 pub use crate::contract::result::{CallFuture, QueryResult};
+pub use crate::types::{CustomError, Revert};
 
 /// Contract Call/Query Options
 #[derive(Default, Debug, Clone, PartialEq)]
@@ -30,6 +42,14 @@ pub struct Options {
     pub nonce: Option<U256>,
     /// A conditon to satisfy before including transaction.
     pub condition: Option<TransactionCondition>,
+    /// Transaction type (None for legacy transactions)
+    pub transaction_type: Option<U64>,
+    /// Access list
+    pub access_list: Option<AccessList>,
+    /// Max fee per gas (EIP-1559)
+    pub max_fee_per_gas: Option<U256>,
+    /// Max priority fee per gas (EIP-1559)
+    pub max_priority_fee_per_gas: Option<U256>,
 }
 
 impl Options {
@@ -42,6 +62,21 @@ impl Options {
         func(&mut options);
         options
     }
+
+    /// Replace the access list with one generated by `eth_createAccessList`.
+    ///
+    /// Fails if the node reported that the call would fail.
+    pub fn with_generated_access_list(mut self, generated: AccessListWithGasUsed) -> Result<Options, Error> {
+        if let Some(error) = generated.error {
+            return Err(Error::Api(ApiError::InvalidResponse(format!(
+                "Access list generation failed: {}",
+                error
+            ))));
+        }
+
+        self.access_list = Some(generated.access_list);
+        Ok(self)
+    }
 }
 
 /// Ethereum Contract Interface
@@ -50,15 +85,17 @@ pub struct Contract<T: Transport> {
     address: Address,
     eth: Eth<T>,
     abi: ethabi::Contract,
+    errors: Vec<CustomError>,
 }
 
 impl<T: Transport> Contract<T> {
     /// Creates deployment builder for a contract given it's ABI in JSON.
     pub fn deploy(eth: Eth<T>, json: &[u8]) -> Result<deploy::Builder<T>, ethabi::Error> {
-        let abi = ethabi::Contract::load(json)?;
+        let (abi, errors) = revert::load_abi(json)?;
         Ok(deploy::Builder {
             eth,
             abi,
+            errors,
             options: Options::default(),
             confirmations: 1,
             poll_interval: time::Duration::from_secs(7),
@@ -75,11 +112,12 @@ impl<T: Transport> Contract<T> {
     where
         S: AsRef<str> + Eq + Hash,
     {
-        let abi = ethabi::Contract::load(json)?;
+        let (abi, errors) = revert::load_abi(json)?;
         let linker: HashMap<String, Address> = linker.into_iter().map(|(s, a)| (s.as_ref().to_string(), a)).collect();
         Ok(deploy::Builder {
             eth,
             abi,
+            errors,
             options: Options::default(),
             confirmations: 1,
             poll_interval: time::Duration::from_secs(7),
@@ -91,13 +129,26 @@ impl<T: Transport> Contract<T> {
 impl<T: Transport> Contract<T> {
     /// Creates new Contract Interface given blockchain address and ABI
     pub fn new(eth: Eth<T>, address: Address, abi: ethabi::Contract) -> Self {
-        Contract { address, eth, abi }
+        Contract {
+            address,
+            eth,
+            abi,
+            errors: vec![],
+        }
     }
 
     /// Creates new Contract Interface given blockchain address and JSON containing ABI
+    ///
+    /// Custom errors declared in the ABI are used to decode reverted calls.
     pub fn from_json(eth: Eth<T>, address: Address, json: &[u8]) -> Result<Self, ethabi::Error> {
-        let abi = ethabi::Contract::load(json)?;
-        Ok(Self::new(eth, address, abi))
+        let (abi, errors) = revert::load_abi(json)?;
+        Ok(Self::new(eth, address, abi).with_errors(errors))
+    }
+
+    /// Sets custom errors used to decode reverted calls.
+    pub fn with_errors(mut self, errors: Vec<CustomError>) -> Self {
+        self.errors = errors;
+        self
     }
 
     /// Returns contract address
@@ -120,6 +171,10 @@ impl<T: Transport> Contract<T> {
                     value,
                     nonce,
                     condition,
+                    transaction_type,
+                    access_list,
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
                 } = options;
 
                 self.eth
@@ -132,12 +187,116 @@ impl<T: Transport> Contract<T> {
                         nonce,
                         data: Some(Bytes(data)),
                         condition,
+                        transaction_type,
+                        access_list,
+                        max_fee_per_gas,
+                        max_priority_fee_per_gas,
                     })
                     .into()
             })
             .unwrap_or_else(Into::into)
     }
 
+    /// Execute a contract function with an access list generated by the node.
+    ///
+    /// Calls `eth_createAccessList` with the same parameters first and replaces
+    /// the access list in `options` with the generated one before sending the transaction.
+    pub fn call_with_access_list<P>(
+        &self,
+        func: &str,
+        params: P,
+        from: Address,
+        options: Options,
+    ) -> impl Future<Item = H256, Error = Error>
+    where
+        P: Tokenize,
+    {
+        let eth = self.eth.clone();
+        let address = self.address;
+        let data = self
+            .abi
+            .function(func)
+            .and_then(|function| function.encode_input(&params.into_tokens()))
+            .map_err(Error::from);
+
+        future::result(data).and_then(move |data| {
+            let data = Bytes(data);
+            eth.create_access_list(
+                CallRequest {
+                    from: Some(from),
+                    to: address,
+                    gas: options.gas,
+                    gas_price: options.gas_price,
+                    value: options.value,
+                    data: Some(data.clone()),
+                    transaction_type: options.transaction_type,
+                    access_list: options.access_list.clone(),
+                    max_fee_per_gas: options.max_fee_per_gas,
+                    max_priority_fee_per_gas: options.max_priority_fee_per_gas,
+                },
+                None,
+            )
+            .map_err(Error::from)
+            .and_then(move |generated| {
+                let options = options.with_generated_access_list(generated)?;
+                Ok((data, options))
+            })
+            .and_then(move |(data, options)| {
+                eth.send_transaction(TransactionRequest {
+                    from,
+                    to: Some(address),
+                    gas: options.gas,
+                    gas_price: options.gas_price,
+                    value: options.value,
+                    nonce: options.nonce,
+                    data: Some(data),
+                    condition: options.condition,
+                    transaction_type: options.transaction_type,
+                    access_list: options.access_list,
+                    max_fee_per_gas: options.max_fee_per_gas,
+                    max_priority_fee_per_gas: options.max_priority_fee_per_gas,
+                })
+                .map_err(Error::from)
+            })
+        })
+    }
+
+    /// Generate an access list for this function call.
+    pub fn create_access_list<P>(
+        &self,
+        func: &str,
+        params: P,
+        from: Address,
+        options: Options,
+    ) -> CallFuture<AccessListWithGasUsed, T::Out>
+    where
+        P: Tokenize,
+    {
+        self.abi
+            .function(func)
+            .and_then(|function| function.encode_input(&params.into_tokens()))
+            .map(|data| {
+                self.eth
+                    .create_access_list(
+                        CallRequest {
+                            from: Some(from),
+                            to: self.address,
+                            gas: options.gas,
+                            gas_price: options.gas_price,
+                            value: options.value,
+                            data: Some(Bytes(data)),
+                            transaction_type: options.transaction_type,
+                            access_list: options.access_list,
+                            max_fee_per_gas: options.max_fee_per_gas,
+                            max_priority_fee_per_gas: options.max_priority_fee_per_gas,
+                        },
+                        None,
+                    )
+                    .into()
+            })
+            .unwrap_or_else(Into::into)
+    }
+
     /// Execute a contract function and wait for confirmations
     pub fn call_with_confirmations<P>(
         &self,
@@ -165,6 +324,10 @@ impl<T: Transport> Contract<T> {
                     nonce: options.nonce,
                     data: Some(Bytes(fn_data)),
                     condition: options.condition,
+                    transaction_type: options.transaction_type,
+                    access_list: options.access_list,
+                    max_fee_per_gas: options.max_fee_per_gas,
+                    max_priority_fee_per_gas: options.max_priority_fee_per_gas,
                 };
 
                 confirm::send_transaction_with_confirmation(
@@ -179,11 +342,112 @@ impl<T: Transport> Contract<T> {
                 // `contract::Error` instead of more generic `Error`.
                 confirm::SendTransactionWithConfirmation::from_err(
                     self.eth.transport().clone(),
-                    crate::error::Error::Decoder(format!("{:?}", e)),
+                    ApiError::Decoder(format!("{:?}", e)),
                 )
             })
     }
 
+    /// Execute a contract function with a transaction signed locally by the given key.
+    ///
+    /// Missing nonce, gas, gas price, chain id and EIP-1559 fees are fetched from the node
+    /// and the signed transaction is sent with `eth_sendRawTransaction`.
+    #[cfg(feature = "signing")]
+    pub fn signed_call<P>(
+        &self,
+        func: &str,
+        params: P,
+        options: Options,
+        key: &SecretKey,
+    ) -> impl Future<Item = H256, Error = Error>
+    where
+        P: Tokenize,
+    {
+        self.call_with_signer(func, params, options, LocalSigner::new(*key))
+    }
+
+    /// Execute a contract function with a transaction signed by given signer.
+    ///
+    /// Missing nonce, gas, gas price, chain id and EIP-1559 fees are fetched from the node
+    /// and the signed transaction is sent with `eth_sendRawTransaction`.
+    #[cfg(feature = "signing")]
+    pub fn call_with_signer<P, S>(
+        &self,
+        func: &str,
+        params: P,
+        options: Options,
+        signer: S,
+    ) -> impl Future<Item = H256, Error = Error>
+    where
+        P: Tokenize,
+        S: Signer,
+    {
+        let eth = self.eth.clone();
+        let accounts = Accounts::new(self.eth.transport().clone());
+        let tx = self
+            .abi
+            .function(func)
+            .and_then(|function| function.encode_input(&params.into_tokens()))
+            .map(|data| self.signed_transaction(signer.address(), data, options))
+            .map_err(Error::from);
+
+        future::result(tx).and_then(move |tx| {
+            accounts
+                .sign_transaction_with(tx, signer)
+                .and_then(move |signed| eth.send_raw_transaction(signed.raw_transaction))
+                .map_err(Error::from)
+        })
+    }
+
+    /// Execute a contract function with a transaction signed by given signer and wait for confirmations
+    #[cfg(feature = "signing")]
+    pub fn call_with_signer_and_confirmations<P, S>(
+        &self,
+        func: &str,
+        params: P,
+        options: Options,
+        signer: S,
+        confirmations: usize,
+    ) -> impl Future<Item = TransactionReceipt, Error = ApiError>
+    where
+        P: Tokenize,
+        S: Signer,
+    {
+        let poll_interval = time::Duration::from_secs(1);
+        let transport = self.eth.transport().clone();
+
+        let tx = self
+            .abi
+            .function(func)
+            .and_then(|function| function.encode_input(&params.into_tokens()))
+            .map(|data| self.signed_transaction(signer.address(), data, options))
+            // TODO [ToDr] SendTransactionWithConfirmation should support custom error type (so that we can return
+            // `contract::Error` instead of more generic `Error`.
+            .map_err(|e| ApiError::Decoder(format!("{:?}", e)));
+
+        future::result(tx).and_then(move |tx| {
+            confirm::send_transaction_with_signer(transport, tx, signer, poll_interval, confirmations)
+        })
+    }
+
+    #[cfg(feature = "signing")]
+    fn signed_transaction(&self, from: Address, data: Vec<u8>, options: Options) -> TransactionParameters {
+        TransactionRequest {
+            from,
+            to: Some(self.address),
+            gas: options.gas,
+            gas_price: options.gas_price,
+            value: options.value,
+            nonce: options.nonce,
+            data: Some(Bytes(data)),
+            condition: options.condition,
+            transaction_type: options.transaction_type,
+            access_list: options.access_list,
+            max_fee_per_gas: options.max_fee_per_gas,
+            max_priority_fee_per_gas: options.max_priority_fee_per_gas,
+        }
+        .into()
+    }
+
     /// Estimate gas required for this function call.
     pub fn estimate_gas<P>(&self, func: &str, params: P, from: Address, options: Options) -> CallFuture<U256, T::Out>
     where
@@ -202,12 +466,17 @@ impl<T: Transport> Contract<T> {
                             gas_price: options.gas_price,
                             value: options.value,
                             data: Some(Bytes(data)),
+                            transaction_type: options.transaction_type,
+                            access_list: options.access_list,
+                            max_fee_per_gas: options.max_fee_per_gas,
+                            max_priority_fee_per_gas: options.max_priority_fee_per_gas,
                         },
                         None,
                     )
                     .into()
             })
             .unwrap_or_else(Into::into)
+            .with_errors(self.errors.clone())
     }
 
     /// Call constant function
@@ -241,12 +510,17 @@ impl<T: Transport> Contract<T> {
                         gas_price: options.gas_price,
                         value: options.value,
                         data: Some(Bytes(call)),
+                        transaction_type: options.transaction_type,
+                        access_list: options.access_list,
+                        max_fee_per_gas: options.max_fee_per_gas,
+                        max_priority_fee_per_gas: options.max_priority_fee_per_gas,
                     },
                     block.into(),
                 );
                 QueryResult::new(result, function.clone())
             })
             .unwrap_or_else(Into::into)
+            .with_errors(self.errors.clone())
     }
 }
 
@@ -259,6 +533,7 @@ mod tests {
     use crate::types::{Address, BlockNumber, H256, U256};
     use crate::Transport;
     use futures::Future;
+    use serde_json::json;
 
     fn contract<T: Transport>(transport: &T) -> Contract<&T> {
         let eth = api::Eth::new(transport);
@@ -345,6 +620,223 @@ mod tests {
         assert_eq!(result, H256::from_low_u64_be(5));
     }
 
+    #[test]
+    fn should_call_a_contract_function_with_eip1559_fees() {
+        // given
+        let mut transport = TestTransport::default();
+        transport.set_response(rpc::Value::String(format!("{:?}", H256::from_low_u64_be(5))));
+
+        let result = {
+            let token = contract(&transport);
+
+            // when
+            token
+                .call(
+                    "name",
+                    (),
+                    Address::from_low_u64_be(5),
+                    Options::with(|options| {
+                        options.transaction_type = Some(2.into());
+                        options.max_fee_per_gas = Some(10.into());
+                        options.max_priority_fee_per_gas = Some(1.into());
+                    }),
+                )
+                .wait()
+                .unwrap()
+        };
+
+        // then
+        transport.assert_request("eth_sendTransaction", &["{\"data\":\"0x06fdde03\",\"from\":\"0x0000000000000000000000000000000000000005\",\"maxFeePerGas\":\"0xa\",\"maxPriorityFeePerGas\":\"0x1\",\"to\":\"0x0000000000000000000000000000000000000001\",\"type\":\"0x2\"}".into()]);
+        transport.assert_no_more_requests();
+        assert_eq!(result, H256::from_low_u64_be(5));
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn should_call_a_contract_function_with_signed_transaction() {
+        use crate::signing::SecretKey;
+        use std::str::FromStr;
+
+        // given
+        let mut transport = TestTransport::default();
+        transport.add_response(json!("0x1"));
+        transport.add_response(rpc::Value::String(format!("{:?}", H256::from_low_u64_be(5))));
+        let key = SecretKey::from_str("4646464646464646464646464646464646464646464646464646464646464646").unwrap();
+
+        let result = {
+            let token = contract(&transport);
+
+            // when
+            token
+                .signed_call(
+                    "name",
+                    (),
+                    Options::with(|options| {
+                        options.nonce = Some(0.into());
+                        options.gas = Some(100_000.into());
+                        options.gas_price = Some(1.into());
+                    }),
+                    &key,
+                )
+                .wait()
+                .unwrap()
+        };
+
+        // then
+        transport.assert_request("eth_chainId", &[]);
+        transport.assert_request("eth_sendRawTransaction", &["\"0xf8648001830186a0940000000000000000000000000000000000000001808406fdde0325a06eb8176f12bbc7857fd8827899a28fa33cb9a8062917c95bb0524e6e5f0a030da0421d194463fc573dad54ddeaceeae80b80c81717071f3d6689022aefd9ce8b0e\"".into()]);
+        transport.assert_no_more_requests();
+        assert_eq!(result, H256::from_low_u64_be(5));
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn should_estimate_gas_of_signed_transaction() {
+        use crate::signing::SecretKey;
+        use crate::transports::MockTransport;
+        use std::str::FromStr;
+
+        // given
+        let transport = MockTransport::new();
+        transport.respond("eth_chainId", "0x1");
+        transport.respond("eth_estimateGas", "0x186a0");
+        transport.respond("eth_sendRawTransaction", format!("{:?}", H256::from_low_u64_be(5)));
+        let key = SecretKey::from_str("4646464646464646464646464646464646464646464646464646464646464646").unwrap();
+
+        let result = {
+            let token = contract(&transport);
+
+            // when
+            token
+                .signed_call(
+                    "name",
+                    (),
+                    Options::with(|options| {
+                        options.nonce = Some(0.into());
+                        options.gas_price = Some(1.into());
+                    }),
+                    &key,
+                )
+                .wait()
+                .unwrap()
+        };
+
+        // then
+        transport.assert_request("eth_chainId", &[]);
+        transport.assert_request("eth_estimateGas", &["{\"data\":\"0x06fdde03\",\"from\":\"0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f\",\"to\":\"0x0000000000000000000000000000000000000001\",\"value\":\"0x0\"}".into(), "\"latest\"".into()]);
+        transport.assert_request("eth_sendRawTransaction", &["\"0xf8648001830186a0940000000000000000000000000000000000000001808406fdde0325a06eb8176f12bbc7857fd8827899a28fa33cb9a8062917c95bb0524e6e5f0a030da0421d194463fc573dad54ddeaceeae80b80c81717071f3d6689022aefd9ce8b0e\"".into()]);
+        transport.assert_no_more_requests();
+        assert_eq!(result, H256::from_low_u64_be(5));
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn should_call_a_contract_function_with_signer() {
+        use crate::signing::{MockSigner, SecretKey, SignerRequest};
+        use crate::types::{Bytes, TransactionParameters};
+        use std::str::FromStr;
+
+        // given
+        let mut transport = TestTransport::default();
+        transport.add_response(json!("0x1"));
+        transport.add_response(rpc::Value::String(format!("{:?}", H256::from_low_u64_be(5))));
+        let signer = MockSigner::new(
+            SecretKey::from_str("4646464646464646464646464646464646464646464646464646464646464646").unwrap(),
+        );
+
+        let result = {
+            let token = contract(&transport);
+
+            // when
+            token
+                .call_with_signer(
+                    "name",
+                    (),
+                    Options::with(|options| {
+                        options.nonce = Some(0.into());
+                        options.gas = Some(100_000.into());
+                        options.gas_price = Some(1.into());
+                    }),
+                    signer.clone(),
+                )
+                .wait()
+                .unwrap()
+        };
+
+        // then
+        transport.assert_request("eth_chainId", &[]);
+        transport.assert_request("eth_sendRawTransaction", &["\"0xf8648001830186a0940000000000000000000000000000000000000001808406fdde0325a06eb8176f12bbc7857fd8827899a28fa33cb9a8062917c95bb0524e6e5f0a030da0421d194463fc573dad54ddeaceeae80b80c81717071f3d6689022aefd9ce8b0e\"".into()]);
+        transport.assert_no_more_requests();
+        assert_eq!(
+            signer.requests(),
+            vec![SignerRequest::Transaction(TransactionParameters {
+                nonce: Some(0.into()),
+                to: Some(Address::from_low_u64_be(1)),
+                gas: Some(100_000.into()),
+                gas_price: Some(1.into()),
+                data: Bytes(vec![0x06, 0xfd, 0xde, 0x03]),
+                chain_id: Some(1),
+                ..Default::default()
+            })]
+        );
+        assert_eq!(result, H256::from_low_u64_be(5));
+    }
+
+    #[test]
+    fn should_call_a_contract_function_with_generated_access_list() {
+        // given
+        let mut transport = TestTransport::default();
+        transport.add_response(json!({
+            "accessList": [{
+                "address": "0x0000000000000000000000000000000000000001",
+                "storageKeys": ["0x0000000000000000000000000000000000000000000000000000000000000002"]
+            }],
+            "gasUsed": "0x5e0b"
+        }));
+        transport.add_response(rpc::Value::String(format!("{:?}", H256::from_low_u64_be(5))));
+
+        let result = {
+            let token = contract(&transport);
+
+            // when
+            token
+                .call_with_access_list("name", (), Address::from_low_u64_be(5), Options::default())
+                .wait()
+                .unwrap()
+        };
+
+        // then
+        transport.assert_request("eth_createAccessList", &["{\"data\":\"0x06fdde03\",\"from\":\"0x0000000000000000000000000000000000000005\",\"to\":\"0x0000000000000000000000000000000000000001\"}".into(), "\"latest\"".into()]);
+        transport.assert_request("eth_sendTransaction", &["{\"accessList\":[{\"address\":\"0x0000000000000000000000000000000000000001\",\"storageKeys\":[\"0x0000000000000000000000000000000000000000000000000000000000000002\"]}],\"data\":\"0x06fdde03\",\"from\":\"0x0000000000000000000000000000000000000005\",\"to\":\"0x0000000000000000000000000000000000000001\"}".into()]);
+        transport.assert_no_more_requests();
+        assert_eq!(result, H256::from_low_u64_be(5));
+    }
+
+    #[test]
+    fn should_not_send_when_access_list_generation_fails() {
+        // given
+        let mut transport = TestTransport::default();
+        transport.set_response(json!({
+            "accessList": [],
+            "gasUsed": "0x0",
+            "error": "execution reverted"
+        }));
+
+        let result = {
+            let token = contract(&transport);
+
+            // when
+            token
+                .call_with_access_list("name", (), Address::from_low_u64_be(5), Options::default())
+                .wait()
+        };
+
+        // then
+        transport.assert_request("eth_createAccessList", &["{\"data\":\"0x06fdde03\",\"from\":\"0x0000000000000000000000000000000000000005\",\"to\":\"0x0000000000000000000000000000000000000001\"}".into(), "\"latest\"".into()]);
+        transport.assert_no_more_requests();
+        assert!(result.is_err());
+    }
+
     #[test]
     fn should_estimate_gas_usage() {
         // given
@@ -390,4 +882,53 @@ mod tests {
         transport.assert_no_more_requests();
         assert_eq!(result, 0x20.into());
     }
+
+    fn reverted(data: &str) -> crate::Error {
+        crate::Error::Rpc(rpc::Error {
+            code: rpc::ErrorCode::ServerError(3),
+            message: "execution reverted".into(),
+            data: Some(json!(data)),
+        })
+    }
+
+    #[test]
+    fn should_decode_revert_reasons() {
+        use super::{Error, Revert};
+        use crate::transports::MockTransport;
+
+        // given
+        let transport = MockTransport::new();
+        transport.fail(
+            "eth_call",
+            reverted(concat!(
+                "0x08c379a0",
+                "0000000000000000000000000000000000000000000000000000000000000020",
+                "000000000000000000000000000000000000000000000000000000000000000b",
+                "4e6f7420616c6c6f776564000000000000000000000000000000000000000000"
+            )),
+        );
+        transport.fail(
+            "eth_estimateGas",
+            reverted("0x4e487b710000000000000000000000000000000000000000000000000000000000000001"),
+        );
+        let token = contract(&transport);
+
+        // when
+        let query: Result<U256, _> = token
+            .query("balanceOf", Address::from_low_u64_be(5), None, Options::default(), None)
+            .wait();
+        let estimate = token
+            .estimate_gas("name", (), Address::from_low_u64_be(5), Options::default())
+            .wait();
+
+        // then
+        match query {
+            Err(Error::Revert(revert)) => assert_eq!(revert, Revert::Reason("Not allowed".into())),
+            other => panic!("Expected revert, got: {:?}", other),
+        }
+        match estimate {
+            Err(Error::Revert(revert)) => assert_eq!(revert.panic_reason(), Some("assertion failed")),
+            other => panic!("Expected revert, got: {:?}", other),
+        }
+    }
 }
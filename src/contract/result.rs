@@ -5,6 +5,7 @@ use std::mem;
 
 use crate::contract;
 use crate::contract::tokens::Detokenize;
+use crate::contract::CustomError;
 use crate::helpers;
 use crate::rpc;
 use crate::types::Bytes;
@@ -24,12 +25,22 @@ enum ResultType<T, F> {
 #[derive(Debug)]
 pub struct CallFuture<T, F> {
     inner: ResultType<T, F>,
+    errors: Vec<CustomError>,
+}
+
+impl<T, F> CallFuture<T, F> {
+    /// Sets custom errors used to decode a reverted call.
+    pub(crate) fn with_errors(mut self, errors: Vec<CustomError>) -> Self {
+        self.errors = errors;
+        self
+    }
 }
 
 impl<T, F> From<crate::helpers::CallFuture<T, F>> for CallFuture<T, F> {
     fn from(inner: crate::helpers::CallFuture<T, F>) -> Self {
         CallFuture {
             inner: ResultType::Simple(inner),
+            errors: vec![],
         }
     }
 }
@@ -41,6 +52,7 @@ where
     fn from(e: E) -> Self {
         CallFuture {
             inner: ResultType::Constant(Err(e.into())),
+            errors: vec![],
         }
     }
 }
@@ -51,6 +63,7 @@ where
 #[derive(Debug)]
 pub struct QueryResult<T, F> {
     inner: ResultType<T, F>,
+    errors: Vec<CustomError>,
 }
 
 impl<T, F, E> From<E> for QueryResult<T, F>
@@ -60,6 +73,7 @@ where
     fn from(e: E) -> Self {
         QueryResult {
             inner: ResultType::Constant(Err(e.into())),
+            errors: vec![],
         }
     }
 }
@@ -69,8 +83,15 @@ impl<T, F> QueryResult<T, F> {
     pub fn new(inner: helpers::CallFuture<Bytes, F>, function: ethabi::Function) -> Self {
         QueryResult {
             inner: ResultType::Decodable(inner, function),
+            errors: vec![],
         }
     }
+
+    /// Sets custom errors used to decode a reverted call.
+    pub(crate) fn with_errors(mut self, errors: Vec<CustomError>) -> Self {
+        self.errors = errors;
+        self
+    }
 }
 
 impl<T: Detokenize, F> Future for QueryResult<T, F>
@@ -82,7 +103,8 @@ where
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         if let ResultType::Decodable(ref mut inner, ref function) = self.inner {
-            let bytes: Bytes = try_ready!(inner.poll());
+            let errors = &self.errors;
+            let bytes: Bytes = try_ready!(inner.poll().map_err(|err| contract::Error::from_api(err, errors)));
             return Ok(Async::Ready(T::from_tokens(function.decode_output(&bytes.0)?)?));
         }
 
@@ -102,7 +124,8 @@ where
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         if let ResultType::Simple(ref mut inner) = self.inner {
-            let hash: T = try_ready!(inner.poll());
+            let errors = &self.errors;
+            let hash: T = try_ready!(inner.poll().map_err(|err| contract::Error::from_api(err, errors)));
             return Ok(Async::Ready(hash));
         }
 
@@ -1,12 +1,14 @@
 //! Web3 Error
-use crate::rpc::error::Error as RPCError;
-use derive_more::{Display, From};
+use crate::rpc::error::{Error as RPCError, ErrorCode};
+use crate::rpc::Id as RPCId;
+use crate::types::{Bytes, Revert};
+use derive_more::Display;
 use serde_json::Error as SerdeError;
 use std::io::Error as IoError;
 use trie;
 
 /// Errors which can occur when attempting to generate resource uri.
-#[derive(Debug, Display, From)]
+#[derive(Debug, Display)]
 pub enum Error {
     /// server is unreachable
     #[display(fmt = "Server is unreachable")]
@@ -26,6 +28,27 @@ pub enum Error {
     /// io error
     #[display(fmt = "IO error: {}", _0)]
     Io(IoError),
+    /// signing error
+    #[display(fmt = "Signing error: {}", _0)]
+    Signing(String),
+    /// endpoints returned different results
+    #[display(fmt = "Endpoints disagree: {}", _0)]
+    Disagreement(String),
+    /// response body exceeds the configured size limit (in bytes)
+    #[display(fmt = "Response exceeds the size limit of {} bytes", _0)]
+    ResponseTooLarge(usize),
+    /// http transport got a non-success status code (and the response body)
+    #[display(fmt = "Unexpected response status code: {} {}", _0, _1)]
+    HttpStatus(u16, String),
+    /// server didn't respond to a request (of a batch)
+    #[display(fmt = "Missing response for request (id: {:?})", _0)]
+    MissingResponse(RPCId),
+    /// server sent more than one response to a request
+    #[display(fmt = "Duplicated response for request (id: {:?})", _0)]
+    DuplicatedResponse(RPCId),
+    /// GraphQL query failed
+    #[display(fmt = "GraphQL error: {}", _0)]
+    GraphQl(String),
     /// web3 internal error
     #[display(fmt = "Internal Web3 error")]
     Internal,
@@ -35,13 +58,27 @@ impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         use self::Error::*;
         match *self {
-            Unreachable | Decoder(_) | InvalidResponse(_) | Transport(_) | Internal => None,
+            Unreachable | Decoder(_) | InvalidResponse(_) | Transport(_) | Signing(_) | Disagreement(_)
+            | ResponseTooLarge(_) | HttpStatus(..) | MissingResponse(_) | DuplicatedResponse(_) | GraphQl(_)
+            | Internal => None,
             Rpc(ref e) => Some(e),
             Io(ref e) => Some(e),
     }
   }
 }
 
+impl From<RPCError> for Error {
+    fn from(err: RPCError) -> Self {
+        Error::Rpc(err)
+    }
+}
+
+impl From<IoError> for Error {
+    fn from(err: IoError) -> Self {
+        Error::Io(err)
+    }
+}
+
 impl From<SerdeError> for Error {
     fn from(err: SerdeError) -> Self {
         Error::Decoder(format!("{:?}", err))
@@ -64,6 +101,13 @@ impl Clone for Error {
             Transport(s) => Transport(s.clone()),
             Rpc(e) => Rpc(e.clone()),
             Io(e) => Io(IoError::from(e.kind())),
+            Signing(s) => Signing(s.clone()),
+            Disagreement(s) => Disagreement(s.clone()),
+            ResponseTooLarge(limit) => ResponseTooLarge(*limit),
+            HttpStatus(status, body) => HttpStatus(*status, body.clone()),
+            MissingResponse(id) => MissingResponse(id.clone()),
+            DuplicatedResponse(id) => DuplicatedResponse(id.clone()),
+            GraphQl(s) => GraphQl(s.clone()),
             Internal => Internal,
     }
     }
@@ -74,12 +118,165 @@ impl PartialEq for Error {
         use self::Error::*;
         match (self, other) {
             (Unreachable, Unreachable) | (Internal, Internal) => true,
-            (Decoder(a), Decoder(b)) | (InvalidResponse(a), InvalidResponse(b)) | (Transport(a), Transport(b)) => {
+            (Decoder(a), Decoder(b))
+            | (InvalidResponse(a), InvalidResponse(b))
+            | (Transport(a), Transport(b))
+            | (Signing(a), Signing(b))
+            | (Disagreement(a), Disagreement(b))
+            | (GraphQl(a), GraphQl(b)) => {
                 a == b
     }
             (Rpc(a), Rpc(b)) => a == b,
+            (ResponseTooLarge(a), ResponseTooLarge(b)) => a == b,
+            (HttpStatus(a, x), HttpStatus(b, y)) => a == b && x == y,
+            (MissingResponse(a), MissingResponse(b)) | (DuplicatedResponse(a), DuplicatedResponse(b)) => a == b,
             (Io(a), Io(b)) => a.kind() == b.kind(),
             _ => false,
         }
     }
 }
+
+/// Common node errors, see `Error::kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// transaction nonce is lower than the account nonce
+    NonceTooLow,
+    /// replacement transaction doesn't pay enough to replace a pending one
+    ReplacementUnderpriced,
+    /// account can't pay for gas and value of the transaction
+    InsufficientFunds,
+    /// call or gas estimation reverted, see `Error::revert_data`
+    ExecutionReverted,
+    /// requested block is not (yet) known to the node
+    HeaderNotFound,
+    /// filter doesn't exist (e.g. expired)
+    FilterNotFound,
+    /// provider limits the rate of requests
+    RateLimited,
+    /// method is not supported by the node
+    MethodNotFound,
+}
+
+impl Error {
+    /// Classifies common node errors.
+    ///
+    /// Nodes differ in error codes and messages, so both are checked (case-insensitively).
+    pub fn kind(&self) -> Option<ErrorKind> {
+        let error = match *self {
+            Error::HttpStatus(429, _) => return Some(ErrorKind::RateLimited),
+            Error::Rpc(ref error) => error,
+            _ => return None,
+        };
+
+        let message = error.message.to_lowercase();
+        let contains = |parts: &[&str]| parts.iter().any(|part| message.contains(part));
+        let kind = match error.code {
+            ErrorCode::MethodNotFound => ErrorKind::MethodNotFound,
+            ErrorCode::ServerError(3) => ErrorKind::ExecutionReverted,
+            ErrorCode::ServerError(-32005) | ErrorCode::ServerError(429) => ErrorKind::RateLimited,
+            _ if contains(&["nonce too low", "nonce is too low"]) => ErrorKind::NonceTooLow,
+            _ if contains(&["replacement transaction underpriced", "replacement underpriced"]) => {
+                ErrorKind::ReplacementUnderpriced
+            }
+            _ if contains(&["insufficient funds"]) => ErrorKind::InsufficientFunds,
+            _ if contains(&["execution reverted", "reverted"]) => ErrorKind::ExecutionReverted,
+            _ if contains(&["header not found", "unknown block"]) => ErrorKind::HeaderNotFound,
+            _ if contains(&["filter not found"]) => ErrorKind::FilterNotFound,
+            _ if contains(&["rate limit", "too many requests", "limit exceeded"]) => ErrorKind::RateLimited,
+            _ if contains(&["method not found", "does not exist/is not available"]) => ErrorKind::MethodNotFound,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// Returns the data returned by a reverted call (e.g. the encoded revert reason).
+    pub fn revert_data(&self) -> Option<Bytes> {
+        match *self {
+            Error::Rpc(ref error) if self.kind() == Some(ErrorKind::ExecutionReverted) => {
+                let data = match error.data {
+                    Some(serde_json::Value::Object(ref data)) => data.get("data")?,
+                    ref data => data.as_ref()?,
+                };
+                serde_json::from_value(data.clone()).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Decodes the reason of a reverted call (e.g. `eth_call` or `eth_estimateGas`).
+    ///
+    /// Custom errors are returned as `Revert::Unknown`, use `Revert::decode` with the contract's errors
+    /// (or query the contract through `Contract`) to decode them.
+    pub fn revert(&self) -> Option<Revert> {
+        self.revert_data().map(|data| Revert::decode(&data.0, &[]))
+    }
+
+    /// Returns the status code of a failed HTTP request.
+    pub fn http_status(&self) -> Option<u16> {
+        match *self {
+            Error::HttpStatus(status, _) => Some(status),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, ErrorKind};
+    use crate::rpc;
+    use crate::types::{Bytes, Revert};
+    use serde_json::json;
+
+    fn rpc_error(code: i64, message: &str, data: Option<serde_json::Value>) -> Error {
+        Error::Rpc(rpc::Error {
+            code: rpc::ErrorCode::ServerError(code),
+            message: message.into(),
+            data,
+        })
+    }
+
+    #[test]
+    fn should_classify_node_errors() {
+        assert_eq!(rpc_error(-32000, "nonce too low", None).kind(), Some(ErrorKind::NonceTooLow));
+        assert_eq!(
+            rpc_error(-32000, "replacement transaction underpriced", None).kind(),
+            Some(ErrorKind::ReplacementUnderpriced)
+        );
+        assert_eq!(
+            rpc_error(-32000, "insufficient funds for gas * price + value", None).kind(),
+            Some(ErrorKind::InsufficientFunds)
+        );
+        assert_eq!(rpc_error(-32000, "header not found", None).kind(), Some(ErrorKind::HeaderNotFound));
+        assert_eq!(rpc_error(-32000, "filter not found", None).kind(), Some(ErrorKind::FilterNotFound));
+        assert_eq!(rpc_error(-32005, "limit exceeded", None).kind(), Some(ErrorKind::RateLimited));
+        assert_eq!(
+            Error::Rpc(rpc::Error::method_not_found()).kind(),
+            Some(ErrorKind::MethodNotFound)
+        );
+        assert_eq!(
+            Error::HttpStatus(429, "Too Many Requests".into()).kind(),
+            Some(ErrorKind::RateLimited)
+        );
+        assert_eq!(rpc_error(-32000, "something else", None).kind(), None);
+        assert_eq!(Error::Transport("connection reset".into()).kind(), None);
+    }
+
+    #[test]
+    fn should_return_revert_data() {
+        let reverted = rpc_error(3, "execution reverted: nope", Some(json!("0x08c379a0")));
+        let nested = rpc_error(-32000, "execution reverted", Some(json!({ "data": "0x1234" })));
+
+        assert_eq!(reverted.kind(), Some(ErrorKind::ExecutionReverted));
+        assert_eq!(reverted.revert_data(), Some(Bytes(vec![0x08, 0xc3, 0x79, 0xa0])));
+        assert_eq!(nested.revert_data(), Some(Bytes(vec![0x12, 0x34])));
+        assert_eq!(rpc_error(-32000, "nonce too low", None).revert_data(), None);
+        assert_eq!(
+            reverted.revert(),
+            Some(Revert::Unknown(Bytes(vec![0x08, 0xc3, 0x79, 0xa0])))
+        );
+        assert_eq!(
+            rpc_error(3, "execution reverted", Some(json!("0x"))).revert(),
+            Some(Revert::Unknown(Bytes(vec![])))
+        );
+    }
+}
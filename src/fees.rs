@@ -0,0 +1,214 @@
+//! Easy to use utilities for EIP-1559 fee estimation.
+
+use crate::api::Eth;
+use crate::helpers::CallFuture;
+use crate::types::{BlockNumber, FeeHistory, U256};
+use crate::{Error, Transport};
+use futures::{Async, Future, Poll};
+
+/// Number of past blocks sampled by default.
+pub const DEFAULT_FEE_HISTORY_BLOCKS: u64 = 10;
+/// Priority fee percentile sampled from each block by default.
+pub const DEFAULT_REWARD_PERCENTILE: f64 = 50.0;
+/// Multiplier applied to the next block base fee by default.
+///
+/// Doubling the base fee keeps the transaction includable for 6 consecutive full blocks.
+pub const DEFAULT_BASE_FEE_MULTIPLIER: u64 = 2;
+/// Priority fee used when sampled blocks carry no priority fees (1 gwei).
+pub const DEFAULT_PRIORITY_FEE: u64 = 1_000_000_000;
+
+/// Suggested EIP-1559 fees.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Eip1559Fees {
+    /// Max fee per gas
+    pub max_fee_per_gas: U256,
+    /// Max priority fee per gas
+    pub max_priority_fee_per_gas: U256,
+}
+
+/// Suggests EIP-1559 fees from `eth_feeHistory` percentiles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeEstimator {
+    /// Number of past blocks to sample.
+    pub blocks: u64,
+    /// Priority fee percentile to sample from each block.
+    pub reward_percentile: f64,
+    /// Multiplier applied to the next block base fee.
+    pub base_fee_multiplier: u64,
+    /// Priority fee used when sampled blocks carry no priority fees.
+    pub default_priority_fee: U256,
+}
+
+impl Default for FeeEstimator {
+    fn default() -> Self {
+        FeeEstimator {
+            blocks: DEFAULT_FEE_HISTORY_BLOCKS,
+            reward_percentile: DEFAULT_REWARD_PERCENTILE,
+            base_fee_multiplier: DEFAULT_BASE_FEE_MULTIPLIER,
+            default_priority_fee: DEFAULT_PRIORITY_FEE.into(),
+        }
+    }
+}
+
+impl FeeEstimator {
+    /// Fetches the fee history of recent blocks and suggests fees from it.
+    pub fn estimate<T: Transport>(&self, eth: &Eth<T>) -> EstimateFees<T> {
+        EstimateFees {
+            estimator: *self,
+            future: eth.fee_history(
+                self.blocks.into(),
+                BlockNumber::Latest,
+                Some(vec![self.reward_percentile]),
+            ),
+        }
+    }
+
+    /// Suggests fees from an already fetched fee history.
+    ///
+    /// The priority fee is the median of the non-zero sampled rewards,
+    /// the max fee covers the multiplied base fee of the next block plus the priority fee.
+    pub fn suggest(&self, history: &FeeHistory) -> Result<Eip1559Fees, Error> {
+        // `baseFeePerGas` includes the base fee of the block following the range.
+        let next_base_fee = *history
+            .base_fee_per_gas
+            .last()
+            .ok_or_else(|| Error::InvalidResponse("Expected base fees in fee history".into()))?;
+
+        let mut rewards = history
+            .reward
+            .iter()
+            .flatten()
+            .filter_map(|block_rewards| block_rewards.first().cloned())
+            .filter(|reward| !reward.is_zero())
+            .collect::<Vec<_>>();
+        rewards.sort();
+
+        let max_priority_fee_per_gas = match rewards.len() {
+            0 => self.default_priority_fee,
+            len => rewards[len / 2],
+        };
+        let max_fee_per_gas = next_base_fee
+            .saturating_mul(self.base_fee_multiplier.into())
+            .saturating_add(max_priority_fee_per_gas);
+
+        Ok(Eip1559Fees {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        })
+    }
+}
+
+/// Future resolving to suggested EIP-1559 fees.
+pub struct EstimateFees<T: Transport> {
+    estimator: FeeEstimator,
+    future: CallFuture<FeeHistory, T::Out>,
+}
+
+impl<T: Transport> Future for EstimateFees<T> {
+    type Item = Eip1559Fees;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let history = try_ready!(self.future.poll());
+        self.estimator.suggest(&history).map(Async::Ready)
+    }
+}
+
+/// Suggests EIP-1559 fees using the default `FeeEstimator`.
+pub fn estimate_eip1559_fees<T: Transport>(eth: &Eth<T>) -> EstimateFees<T> {
+    FeeEstimator::default().estimate(eth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimate_eip1559_fees, Eip1559Fees, FeeEstimator};
+    use crate::api::{Eth, Namespace};
+    use crate::helpers::tests::TestTransport;
+    use crate::types::FeeHistory;
+    use crate::Error;
+    use futures::Future;
+    use serde_json::json;
+
+    fn history(base_fees: Vec<u64>, rewards: Option<Vec<u64>>) -> FeeHistory {
+        FeeHistory {
+            oldest_block: 1.into(),
+            gas_used_ratio: vec![0.5; base_fees.len().saturating_sub(1)],
+            base_fee_per_gas: base_fees.into_iter().map(Into::into).collect(),
+            reward: rewards.map(|rewards| rewards.into_iter().map(|r| vec![r.into()]).collect()),
+        }
+    }
+
+    #[test]
+    fn should_suggest_median_priority_fee() {
+        let estimator = FeeEstimator::default();
+
+        let fees = estimator.suggest(&history(vec![100, 110, 120, 130], Some(vec![3, 0, 1, 2])));
+
+        assert_eq!(
+            fees,
+            Ok(Eip1559Fees {
+                max_fee_per_gas: 262.into(),
+                max_priority_fee_per_gas: 2.into(),
+            })
+        );
+    }
+
+    #[test]
+    fn should_fall_back_to_default_priority_fee() {
+        let estimator = FeeEstimator {
+            default_priority_fee: 7.into(),
+            ..Default::default()
+        };
+
+        let fees = estimator.suggest(&history(vec![100, 110], None));
+
+        assert_eq!(
+            fees,
+            Ok(Eip1559Fees {
+                max_fee_per_gas: 227.into(),
+                max_priority_fee_per_gas: 7.into(),
+            })
+        );
+    }
+
+    #[test]
+    fn should_reject_empty_fee_history() {
+        let estimator = FeeEstimator::default();
+
+        let fees = estimator.suggest(&history(vec![], None));
+
+        assert_eq!(
+            fees,
+            Err(Error::InvalidResponse("Expected base fees in fee history".into()))
+        );
+    }
+
+    #[test]
+    fn should_estimate_fees_from_fee_history() {
+        // given
+        let mut transport = TestTransport::default();
+        transport.set_response(json!({
+            "oldestBlock": "0x1",
+            "baseFeePerGas": ["0x64", "0x6e"],
+            "gasUsedRatio": [0.5],
+            "reward": [["0x5"]]
+        }));
+
+        // when
+        let fees = estimate_eip1559_fees(&Eth::new(&transport)).wait();
+
+        // then
+        transport.assert_request(
+            "eth_feeHistory",
+            &[r#""0xa""#.into(), r#""latest""#.into(), "[50.0]".into()],
+        );
+        transport.assert_no_more_requests();
+        assert_eq!(
+            fees,
+            Ok(Eip1559Fees {
+                max_fee_per_gas: 225.into(),
+                max_priority_fee_per_gas: 5.into(),
+            })
+        );
+    }
+}
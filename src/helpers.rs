@@ -1,8 +1,10 @@
 //! Web3 helpers.
 
+use std::collections::BTreeMap;
 use std::marker::PhantomData;
 
 use crate::rpc;
+use crate::RequestId;
 use futures::{Async, Future, Poll};
 use serde;
 use serde_json;
@@ -133,6 +135,109 @@ pub fn to_result_from_output(output: rpc::Output) -> Result<rpc::Value, Error> {
     }
 }
 
+/// Returns the id of a call, `None` for notifications (that get no response).
+pub fn call_id(call: &rpc::Call) -> Option<rpc::Id> {
+    match *call {
+        rpc::Call::MethodCall(ref call) => Some(call.id.clone()),
+        rpc::Call::Notification(_) => None,
+        rpc::Call::Invalid { ref id } => Some(id.clone()),
+    }
+}
+
+/// Returns ids of the calls expecting a response.
+pub fn request_ids(request: &rpc::Request) -> Vec<rpc::Id> {
+    match *request {
+        rpc::Request::Single(ref call) => call_id(call).into_iter().collect(),
+        rpc::Request::Batch(ref calls) => calls.iter().filter_map(call_id).collect(),
+    }
+}
+
+/// Returns the id of a response.
+pub fn output_id(output: &rpc::Output) -> &rpc::Id {
+    match *output {
+        rpc::Output::Success(ref success) => &success.id,
+        rpc::Output::Failure(ref failure) => &failure.id,
+    }
+}
+
+/// Returns true if the response id matches the request id.
+///
+/// Numeric ids echoed back as strings are accepted.
+pub fn is_response_to(request: &rpc::Id, response: &rpc::Id) -> bool {
+    match (request, response) {
+        (&rpc::Id::Num(num), &rpc::Id::Str(ref s)) => s.parse() == Ok(num),
+        (request, response) => request == response,
+    }
+}
+
+/// Returns the key of the pending request (or batch) given responses answer.
+///
+/// Batch responses are not necessarily in order of the requests, so the batch is found by any of its ids.
+/// An error without an id (e.g. the server couldn't parse the request) is routed to the pending request
+/// only if there is exactly one, otherwise it can't be attributed.
+pub fn pending_request<P, F>(pending: &BTreeMap<RequestId, P>, ids: F, outputs: &[rpc::Output]) -> Option<RequestId>
+where
+    F: Fn(&P) -> &[rpc::Id],
+{
+    let answers = |request: &P| {
+        outputs
+            .iter()
+            .any(|output| ids(request).iter().any(|id| is_response_to(id, output_id(output))))
+    };
+
+    let first = outputs.first().and_then(|output| match *output_id(output) {
+        rpc::Id::Num(num) => Some(num as RequestId),
+        rpc::Id::Str(ref s) => s.parse().ok(),
+        rpc::Id::Null => None,
+    });
+    let unidentified = !outputs.is_empty()
+        && outputs.iter().all(|output| match *output {
+            rpc::Output::Failure(ref failure) => failure.id == rpc::Id::Null,
+            rpc::Output::Success(_) => false,
+        });
+    match first.and_then(|key| pending.get(&key).map(|request| (key, request))) {
+        Some((key, request)) if answers(request) => Some(key),
+        _ if unidentified && pending.len() == 1 => pending.keys().next().cloned(),
+        _ => pending
+            .iter()
+            .find(|&(_, request)| answers(request))
+            .map(|(key, _)| *key),
+    }
+}
+
+/// Matches responses with requests, returns results in order of the request ids.
+///
+/// Servers may return batch results in any order. Missing and duplicated responses are reported as
+/// `Error::MissingResponse` and `Error::DuplicatedResponse`. A request without a response fails with the
+/// error the server returned without an id (e.g. when it couldn't parse the request), if there is one.
+pub fn to_results_by_id(ids: &[rpc::Id], outputs: Vec<rpc::Output>) -> Vec<Result<rpc::Value, Error>> {
+    let mut results: Vec<Option<Result<rpc::Value, Error>>> = ids.iter().map(|_| None).collect();
+    let mut failure = None;
+
+    for output in outputs {
+        let position = ids.iter().position(|id| is_response_to(id, output_id(&output)));
+        match (position, output) {
+            (Some(idx), _) if results[idx].is_some() => {
+                log::warn!("Got duplicated response (id: {:?})", ids[idx]);
+                results[idx] = Some(Err(Error::DuplicatedResponse(ids[idx].clone())));
+            }
+            (Some(idx), output) => results[idx] = Some(to_result_from_output(output)),
+            (None, rpc::Output::Failure(ref output)) if output.id == rpc::Id::Null => {
+                failure = failure.or_else(|| Some(Error::Rpc(output.error.clone())));
+            }
+            (None, output) => log::warn!("Got response for unknown request: {:?}", output),
+        }
+    }
+
+    results
+        .into_iter()
+        .zip(ids)
+        .map(|(result, id)| {
+            result.unwrap_or_else(|| Err(failure.clone().unwrap_or_else(|| Error::MissingResponse(id.clone()))))
+        })
+        .collect()
+}
+
 #[macro_use]
 #[cfg(test)]
 pub mod tests {
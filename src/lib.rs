@@ -21,6 +21,9 @@ pub extern crate futures;
 pub mod helpers;
 
 pub mod api;
+pub mod blocking;
+#[cfg(feature = "std-future")]
+pub mod compat;
 pub mod contract;
 pub mod error;
 pub mod transports;
@@ -28,6 +31,9 @@ pub mod types;
 pub mod abos_types;
 
 pub mod confirm;
+pub mod fees;
+#[cfg(feature = "signing")]
+pub mod signing;
 
 pub use crate::api::Web3;
 pub use crate::error::Error;
@@ -0,0 +1,658 @@
+//! Local signing utilities and pluggable signers.
+
+use crate::types::{AccessList, Address, SignedData, SignedTransaction, TransactionParameters, H256, U256};
+use crate::Error;
+use futures::future;
+use hash::keccak;
+use parking_lot::Mutex;
+use rlp::RlpStream;
+use secp256k1::recovery::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, PublicKey, Secp256k1};
+use std::{fmt, sync::Arc};
+
+pub use secp256k1::SecretKey;
+
+/// A recoverable ECDSA signature split into its components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    /// Recovery id (`0` or `1`).
+    ///
+    /// Callers adjust it to the format they encode, e.g. EIP-155 for legacy transactions.
+    pub v: u64,
+    /// R component of the signature.
+    pub r: H256,
+    /// S component of the signature.
+    pub s: H256,
+}
+
+/// An account able to sign transactions and messages.
+///
+/// Implementations may keep the key in memory (`LocalSigner`), in an HSM
+/// or delegate to a remote signing service.
+pub trait Signer: fmt::Debug {
+    /// Address of the signing account.
+    fn address(&self) -> Address;
+
+    /// Signs a transaction.
+    ///
+    /// Parameters are expected to be complete: nonce, chain id, gas and gas price
+    /// (or EIP-1559 fees) are filled by `Accounts::fill_transaction` beforehand.
+    fn sign_transaction(&self, tx: TransactionParameters) -> crate::Result<SignedTransaction>;
+
+    /// Signs a message according to EIP-191 (`personal_sign`).
+    fn sign_message(&self, message: &[u8]) -> crate::Result<SignedData>;
+
+    /// Signs EIP-712 typed data given its domain separator and struct hash.
+    ///
+    /// See `TypedData::domain_separator` and `TypedData::struct_hash`.
+    fn sign_typed_data(&self, domain_separator: H256, struct_hash: H256) -> crate::Result<SignedData>;
+}
+
+impl<S: Signer + ?Sized> Signer for Arc<S> {
+    fn address(&self) -> Address {
+        (**self).address()
+    }
+
+    fn sign_transaction(&self, tx: TransactionParameters) -> crate::Result<SignedTransaction> {
+        (**self).sign_transaction(tx)
+    }
+
+    fn sign_message(&self, message: &[u8]) -> crate::Result<SignedData> {
+        (**self).sign_message(message)
+    }
+
+    fn sign_typed_data(&self, domain_separator: H256, struct_hash: H256) -> crate::Result<SignedData> {
+        (**self).sign_typed_data(domain_separator, struct_hash)
+    }
+}
+
+/// Signer keeping the secret key in memory.
+#[derive(Clone, Copy)]
+pub struct LocalSigner {
+    key: SecretKey,
+    address: Address,
+}
+
+impl LocalSigner {
+    /// Creates a new signer for given secret key.
+    pub fn new(key: SecretKey) -> Self {
+        LocalSigner {
+            address: secret_key_address(&key),
+            key,
+        }
+    }
+}
+
+impl fmt::Debug for LocalSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Never print the secret key.
+        f.debug_struct("LocalSigner").field("address", &self.address).finish()
+    }
+}
+
+impl From<SecretKey> for LocalSigner {
+    fn from(key: SecretKey) -> Self {
+        LocalSigner::new(key)
+    }
+}
+
+impl Signer for LocalSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_transaction(&self, tx: TransactionParameters) -> crate::Result<SignedTransaction> {
+        Box::new(future::result(sign_transaction(tx, &self.key)))
+    }
+
+    fn sign_message(&self, message: &[u8]) -> crate::Result<SignedData> {
+        Box::new(future::ok(sign_message(message, &self.key)))
+    }
+
+    fn sign_typed_data(&self, domain_separator: H256, struct_hash: H256) -> crate::Result<SignedData> {
+        Box::new(future::ok(sign_typed_data(domain_separator, struct_hash, &self.key)))
+    }
+}
+
+/// Request received by a `MockSigner`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignerRequest {
+    /// `Signer::sign_transaction`
+    Transaction(TransactionParameters),
+    /// `Signer::sign_message`
+    Message(Vec<u8>),
+    /// `Signer::sign_typed_data`
+    TypedData {
+        /// EIP-712 domain separator
+        domain_separator: H256,
+        /// EIP-712 struct hash
+        struct_hash: H256,
+    },
+}
+
+/// Signer for tests.
+///
+/// Records all requests and signs them with the given key unless set to fail.
+#[derive(Debug, Clone)]
+pub struct MockSigner {
+    signer: LocalSigner,
+    requests: Arc<Mutex<Vec<SignerRequest>>>,
+    error: Arc<Mutex<Option<Error>>>,
+}
+
+impl MockSigner {
+    /// Creates a new mock signer signing with given secret key.
+    pub fn new(key: SecretKey) -> Self {
+        MockSigner {
+            signer: LocalSigner::new(key),
+            requests: Default::default(),
+            error: Default::default(),
+        }
+    }
+
+    /// Makes all subsequent requests fail with given error.
+    pub fn fail_with(&self, error: Error) {
+        *self.error.lock() = Some(error);
+    }
+
+    /// Returns all requests received so far.
+    pub fn requests(&self) -> Vec<SignerRequest> {
+        self.requests.lock().clone()
+    }
+
+    fn respond<T: Send + 'static>(&self, request: SignerRequest, sign: crate::Result<T>) -> crate::Result<T> {
+        self.requests.lock().push(request);
+        match self.error.lock().clone() {
+            Some(error) => Box::new(future::err(error)),
+            None => sign,
+        }
+    }
+}
+
+impl Signer for MockSigner {
+    fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    fn sign_transaction(&self, tx: TransactionParameters) -> crate::Result<SignedTransaction> {
+        let sign = self.signer.sign_transaction(tx.clone());
+        self.respond(SignerRequest::Transaction(tx), sign)
+    }
+
+    fn sign_message(&self, message: &[u8]) -> crate::Result<SignedData> {
+        let sign = self.signer.sign_message(message);
+        self.respond(SignerRequest::Message(message.to_vec()), sign)
+    }
+
+    fn sign_typed_data(&self, domain_separator: H256, struct_hash: H256) -> crate::Result<SignedData> {
+        let sign = self.signer.sign_typed_data(domain_separator, struct_hash);
+        self.respond(
+            SignerRequest::TypedData {
+                domain_separator,
+                struct_hash,
+            },
+            sign,
+        )
+    }
+}
+
+/// Compute the Keccak-256 hash of input bytes.
+pub fn keccak256(bytes: &[u8]) -> H256 {
+    keccak(bytes)
+}
+
+/// Hash a message according to EIP-191 (`eth_sign` / `personal_sign`).
+///
+/// The data is prefixed with `"\x19Ethereum Signed Message:\n"` and its length before hashing.
+pub fn hash_message<S: AsRef<[u8]>>(message: S) -> H256 {
+    let message = message.as_ref();
+    let mut eth_message = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    eth_message.extend_from_slice(message);
+    keccak256(&eth_message)
+}
+
+/// Hash EIP-712 typed data given its domain separator and struct hash.
+///
+/// Computes `keccak256("\x19\x01" || domain_separator || struct_hash)`.
+pub fn hash_typed_data(domain_separator: H256, struct_hash: H256) -> H256 {
+    keccak256(&typed_data_message(domain_separator, struct_hash))
+}
+
+fn typed_data_message(domain_separator: H256, struct_hash: H256) -> Vec<u8> {
+    let mut message = Vec::with_capacity(66);
+    message.extend_from_slice(b"\x19\x01");
+    message.extend_from_slice(domain_separator.as_bytes());
+    message.extend_from_slice(struct_hash.as_bytes());
+    message
+}
+
+/// Sign a 32-byte hash with the given secret key.
+pub fn sign(hash: &H256, key: &SecretKey) -> Signature {
+    let secp = Secp256k1::signing_only();
+    let message = Message::from_slice(hash.as_bytes()).expect("hash is 32 bytes long; qed");
+    let (recovery_id, signature) = secp.sign_recoverable(&message, key).serialize_compact();
+
+    Signature {
+        v: recovery_id.to_i32() as u64,
+        r: H256::from_slice(&signature[..32]),
+        s: H256::from_slice(&signature[32..]),
+    }
+}
+
+/// Sign arbitrary data according to EIP-191.
+///
+/// The returned signature is in 'Electrum' notation, that is the recovery value `v` is
+/// either `27` or `28` (as opposed to the standard notation where `v` is either `0` or `1`).
+pub fn sign_message(message: &[u8], key: &SecretKey) -> SignedData {
+    signed_data(message.to_vec(), hash_message(message), key)
+}
+
+/// Sign EIP-712 typed data given its domain separator and struct hash.
+///
+/// The signature is in 'Electrum' notation, see `sign_message`.
+pub fn sign_typed_data(domain_separator: H256, struct_hash: H256, key: &SecretKey) -> SignedData {
+    let message = typed_data_message(domain_separator, struct_hash);
+    let message_hash = keccak256(&message);
+    signed_data(message, message_hash, key)
+}
+
+fn signed_data(message: Vec<u8>, message_hash: H256, key: &SecretKey) -> SignedData {
+    let Signature { v, r, s } = sign(&message_hash, key);
+    let v = v as u8 + 27;
+
+    let mut signature = Vec::with_capacity(65);
+    signature.extend_from_slice(r.as_bytes());
+    signature.extend_from_slice(s.as_bytes());
+    signature.push(v);
+
+    SignedData {
+        message,
+        message_hash,
+        v,
+        r,
+        s,
+        signature: signature.into(),
+    }
+}
+
+/// Sign a transaction with the given secret key.
+///
+/// Nonce, chain id, gas and gas price (or both EIP-1559 fees for type `2` transactions)
+/// have to be set.
+pub fn sign_transaction(tx: TransactionParameters, key: &SecretKey) -> Result<SignedTransaction, Error> {
+    Ok(Transaction::from_parameters(tx)?.sign(key))
+}
+
+/// Recover the address which signed given hash.
+///
+/// The signature is 65 bytes long (`r || s || v`), `v` being either the recovery id (`0` or `1`)
+/// or in 'Electrum' notation (`27` or `28`).
+pub fn recover(hash: &H256, signature: &[u8]) -> Result<Address, Error> {
+    if signature.len() != 65 {
+        return Err(Error::Signing(format!(
+            "Invalid signature length: {}, expected 65",
+            signature.len()
+        )));
+    }
+
+    let v = match signature[64] {
+        v @ 27..=28 => v - 27,
+        v => v,
+    };
+    let recovery_id =
+        RecoveryId::from_i32(i32::from(v)).map_err(|_| Error::Signing(format!("Invalid recovery id: {}", v)))?;
+    let signature = RecoverableSignature::from_compact(&signature[..64], recovery_id)
+        .map_err(|e| Error::Signing(format!("Invalid signature: {}", e)))?;
+    let message = Message::from_slice(hash.as_bytes()).expect("hash is 32 bytes long; qed");
+    let public_key = Secp256k1::verification_only()
+        .recover(&message, &signature)
+        .map_err(|e| Error::Signing(format!("Unable to recover public key: {}", e)))?;
+
+    Ok(public_key_address(&public_key.serialize_uncompressed()))
+}
+
+/// Check that given hash was signed by `address`.
+///
+/// Fails only for malformed signatures, see `recover`.
+pub fn verify(hash: &H256, signature: &[u8], address: Address) -> Result<bool, Error> {
+    Ok(recover(hash, signature)? == address)
+}
+
+/// Get the address controlled by the given secret key.
+pub fn secret_key_address(key: &SecretKey) -> Address {
+    let secp = Secp256k1::signing_only();
+    let public_key = PublicKey::from_secret_key(&secp, key).serialize_uncompressed();
+    public_key_address(&public_key)
+}
+
+/// Get the address of an uncompressed (65 bytes, `0x04`-prefixed) public key.
+pub(crate) fn public_key_address(public_key: &[u8; 65]) -> Address {
+    debug_assert_eq!(public_key[0], 0x04);
+    let hash = keccak256(&public_key[1..]);
+    Address::from_slice(&hash[12..])
+}
+
+/// A transaction with all the fields required for signing.
+#[derive(Debug)]
+struct Transaction {
+    chain_id: u64,
+    to: Option<Address>,
+    nonce: U256,
+    gas: U256,
+    gas_price: U256,
+    value: U256,
+    data: Vec<u8>,
+    transaction_type: u64,
+    access_list: AccessList,
+    max_fee_per_gas: U256,
+    max_priority_fee_per_gas: U256,
+}
+
+impl Transaction {
+    fn from_parameters(tx: TransactionParameters) -> Result<Self, Error> {
+        let missing = |field| Error::Signing(format!("Missing {} in transaction parameters", field));
+        let transaction_type = tx.resolved_type();
+        if transaction_type > 2 {
+            return Err(Error::Signing(format!(
+                "Unsupported transaction type: {}",
+                transaction_type
+            )));
+        }
+
+        // EIP-1559 transactions don't carry a gas price.
+        let (gas_price, max_fee_per_gas, max_priority_fee_per_gas) = if transaction_type == 2 {
+            (
+                U256::zero(),
+                tx.max_fee_per_gas.ok_or_else(|| missing("max fee per gas"))?,
+                tx.max_priority_fee_per_gas
+                    .ok_or_else(|| missing("max priority fee per gas"))?,
+            )
+        } else {
+            (
+                tx.gas_price.ok_or_else(|| missing("gas price"))?,
+                U256::zero(),
+                U256::zero(),
+            )
+        };
+
+        Ok(Transaction {
+            chain_id: tx.chain_id.ok_or_else(|| missing("chain id"))?,
+            to: tx.to,
+            nonce: tx.nonce.ok_or_else(|| missing("nonce"))?,
+            gas: tx.gas.ok_or_else(|| missing("gas"))?,
+            gas_price,
+            value: tx.value,
+            data: tx.data.0,
+            transaction_type,
+            access_list: tx.access_list.unwrap_or_default(),
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        })
+    }
+
+    fn rlp_append_to(&self, stream: &mut RlpStream) {
+        match self.to {
+            Some(ref to) => stream.append(to),
+            None => stream.append_empty_data(),
+        };
+    }
+
+    fn rlp_append_access_list(&self, stream: &mut RlpStream) {
+        stream.begin_list(self.access_list.len());
+        for item in &self.access_list {
+            stream.begin_list(2);
+            stream.append(&item.address);
+            stream.append_list(&item.storage_keys);
+        }
+    }
+
+    fn rlp_append_signature(stream: &mut RlpStream, signature: &Signature) {
+        stream.append(&signature.v);
+        stream.append(&U256::from_big_endian(signature.r.as_bytes()));
+        stream.append(&U256::from_big_endian(signature.s.as_bytes()));
+    }
+
+    /// Encodes the transaction, either signed or as the payload to sign.
+    ///
+    /// Legacy transactions are encoded according to EIP-155, typed transactions according
+    /// to EIP-2718 (`type || rlp(payload)`).
+    fn encode(&self, signature: Option<&Signature>) -> Vec<u8> {
+        let mut stream = RlpStream::new();
+        stream.begin_unbounded_list();
+
+        if self.transaction_type == 0 {
+            stream.append(&self.nonce);
+            stream.append(&self.gas_price);
+            stream.append(&self.gas);
+            self.rlp_append_to(&mut stream);
+            stream.append(&self.value);
+            stream.append(&self.data);
+            match signature {
+                Some(signature) => Self::rlp_append_signature(&mut stream, signature),
+                None => {
+                    stream.append(&self.chain_id);
+                    stream.append(&0u8);
+                    stream.append(&0u8);
+                }
+            }
+            stream.finalize_unbounded_list();
+            return stream.out();
+        }
+
+        stream.append(&self.chain_id);
+        stream.append(&self.nonce);
+        if self.transaction_type == 2 {
+            stream.append(&self.max_priority_fee_per_gas);
+            stream.append(&self.max_fee_per_gas);
+        } else {
+            stream.append(&self.gas_price);
+        }
+        stream.append(&self.gas);
+        self.rlp_append_to(&mut stream);
+        stream.append(&self.value);
+        stream.append(&self.data);
+        self.rlp_append_access_list(&mut stream);
+        if let Some(signature) = signature {
+            Self::rlp_append_signature(&mut stream, signature);
+        }
+        stream.finalize_unbounded_list();
+
+        let mut encoded = vec![self.transaction_type as u8];
+        encoded.extend_from_slice(&stream.out());
+        encoded
+    }
+
+    /// Sign and return a raw signed transaction.
+    fn sign(self, key: &SecretKey) -> SignedTransaction {
+        let message_hash = keccak256(&self.encode(None));
+        let mut signature = sign(&message_hash, key);
+        // Typed transactions carry the y-parity, legacy ones the EIP-155 `v`.
+        if self.transaction_type == 0 {
+            signature.v += 35 + self.chain_id * 2;
+        }
+
+        let raw_transaction = self.encode(Some(&signature));
+        let transaction_hash = keccak256(&raw_transaction);
+
+        SignedTransaction {
+            message_hash,
+            v: signature.v,
+            r: signature.r,
+            s: signature.s,
+            raw_transaction: raw_transaction.into(),
+            transaction_hash,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::Future;
+    use std::str::FromStr;
+
+    fn key() -> SecretKey {
+        SecretKey::from_str("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318").unwrap()
+    }
+
+    #[test]
+    fn should_derive_address_from_secret_key() {
+        assert_eq!(
+            secret_key_address(&key()),
+            Address::from_str("2c7536e3605d9c16a7a3d7b1898e529396a65c23").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_hash_message() {
+        assert_eq!(
+            hash_message("Some data"),
+            H256::from_str("1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_sign_hash() {
+        let signature = sign(&hash_message("Some data"), &key());
+
+        assert_eq!(
+            signature,
+            Signature {
+                v: 1,
+                r: H256::from_str("b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd").unwrap(),
+                s: H256::from_str("6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a029").unwrap(),
+            }
+        );
+    }
+
+    #[test]
+    fn should_recover_signer_address() {
+        let signed = sign_message(b"Some data", &key());
+
+        assert_eq!(
+            recover(&signed.message_hash, &signed.signature.0),
+            Ok(secret_key_address(&key()))
+        );
+        assert_eq!(
+            verify(&signed.message_hash, &signed.signature.0, secret_key_address(&key())),
+            Ok(true)
+        );
+        assert_eq!(
+            verify(
+                &hash_message("Other data"),
+                &signed.signature.0,
+                secret_key_address(&key())
+            ),
+            Ok(false)
+        );
+        assert_eq!(
+            recover(&signed.message_hash, &signed.signature.0[..64]),
+            Err(Error::Signing("Invalid signature length: 64, expected 65".into()))
+        );
+    }
+
+    #[test]
+    fn should_sign_and_recover_typed_data() {
+        use crate::types::TypedData;
+        use serde_json::json;
+
+        // key of the "Cow" account from the EIP-712 example
+        let key = SecretKey::from_slice(keccak256(b"cow").as_bytes()).unwrap();
+        let typed_data: TypedData = serde_json::from_value(json!({
+            "types": {
+                "Person": [{ "name": "name", "type": "string" }, { "name": "wallet", "type": "address" }],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "string" }
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+            },
+            "message": {
+                "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                "contents": "Hello, Bob!"
+            }
+        }))
+        .unwrap();
+
+        let signed = sign_typed_data(
+            typed_data.domain_separator().unwrap(),
+            typed_data.struct_hash().unwrap(),
+            &key,
+        );
+
+        assert_eq!(signed.message_hash, typed_data.signing_hash().unwrap());
+        assert_eq!(signed.v, 28);
+        assert_eq!(
+            signed.r,
+            H256::from_str("4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d").unwrap()
+        );
+        assert_eq!(
+            signed.s,
+            H256::from_str("07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562").unwrap()
+        );
+        assert_eq!(
+            recover(&signed.message_hash, &signed.signature.0),
+            Ok(Address::from_str("cd2a3d9f938e13cd947ec05abc7fe734df8dd826").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_reject_incomplete_transaction() {
+        let tx = TransactionParameters {
+            nonce: Some(0.into()),
+            gas_price: Some(1.into()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            sign_transaction(tx, &key()),
+            Err(Error::Signing("Missing chain id in transaction parameters".into()))
+        );
+    }
+
+    #[test]
+    fn local_signer_should_not_leak_secret_key() {
+        let signer = LocalSigner::new(key());
+
+        assert_eq!(
+            format!("{:?}", signer),
+            "LocalSigner { address: 0x2c7536e3605d9c16a7a3d7b1898e529396a65c23 }"
+        );
+    }
+
+    #[test]
+    fn mock_signer_should_record_requests() {
+        // given
+        let signer = MockSigner::new(key());
+
+        // when
+        let signed = signer.sign_message(b"Some data").wait().unwrap();
+        signer.fail_with(Error::Signing("Rejected by user".into()));
+        let rejected = signer
+            .sign_typed_data(H256::from_low_u64_be(1), H256::from_low_u64_be(2))
+            .wait();
+
+        // then
+        assert_eq!(signer.address(), secret_key_address(&key()));
+        assert_eq!(signed, sign_message(b"Some data", &key()));
+        assert_eq!(rejected, Err(Error::Signing("Rejected by user".into())));
+        assert_eq!(
+            signer.requests(),
+            vec![
+                SignerRequest::Message(b"Some data".to_vec()),
+                SignerRequest::TypedData {
+                    domain_separator: H256::from_low_u64_be(1),
+                    struct_hash: H256::from_low_u64_be(2),
+                },
+            ]
+        );
+    }
+}
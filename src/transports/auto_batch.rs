@@ -0,0 +1,486 @@
+//! Auto-batching Transport
+
+use crate::rpc;
+use crate::transports::Result;
+use crate::{BatchTransport, Error, RequestId, Transport};
+use futures::task::{self, Task};
+use futures::{Async, Future, Poll};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{fmt, mem};
+use tokio_timer::{Sleep, Timer};
+
+/// Default time window collecting calls into a single batch.
+const DEFAULT_WINDOW: Duration = Duration::from_millis(10);
+/// Default number of calls flushing the batch before the window ends.
+const DEFAULT_FLUSH_SIZE: usize = 100;
+
+/// Transport collecting single calls into batches, Dataloader-style.
+///
+/// Calls sent within a time window (`with_window`) are sent together as one batch, which is
+/// flushed earlier once it reaches the flush size (`with_flush_size`). Batches larger than
+/// the provider's limit (`with_max_batch_size`), including those sent explicitly with
+/// `send_batch`, are split into several requests.
+///
+/// The batch is sent by whichever of its calls is polled first after the window ends,
+/// so there is no need to flush it manually. Works with any `BatchTransport`,
+/// e.g. `Http`, `Ipc` or `WebSocket`.
+#[derive(Clone)]
+pub struct AutoBatch<T: BatchTransport> {
+    transport: T,
+    current: Arc<Mutex<Option<Arc<Mutex<Window<T::Batch>>>>>>,
+    window: Duration,
+    flush_size: usize,
+    max_batch_size: Option<usize>,
+    timer: Timer,
+}
+
+impl<T: BatchTransport> fmt::Debug for AutoBatch<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AutoBatch")
+            .field("transport", &self.transport)
+            .field("window", &self.window)
+            .field("flush_size", &self.flush_size)
+            .field("max_batch_size", &self.max_batch_size)
+            .finish()
+    }
+}
+
+impl<T: BatchTransport> AutoBatch<T> {
+    /// Creates new AutoBatch transport given existing transport supporting batch requests.
+    pub fn new(transport: T) -> Self {
+        AutoBatch {
+            transport,
+            current: Default::default(),
+            window: DEFAULT_WINDOW,
+            flush_size: DEFAULT_FLUSH_SIZE,
+            max_batch_size: None,
+            timer: super::timer::shared(),
+        }
+    }
+
+    /// Sets the timer used to wait for the window to end, a timer shared by all transports is used by default.
+    pub fn with_timer(mut self, timer: Timer) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Sets how long calls are collected before the batch is sent, defaults to 10ms.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the number of calls which flushes the batch immediately, defaults to `100`.
+    pub fn with_flush_size(mut self, flush_size: usize) -> Self {
+        assert!(flush_size > 0, "Flush size has to be positive.");
+        self.flush_size = flush_size;
+        self
+    }
+
+    /// Sets the maximal number of calls in a single batch request accepted by the provider.
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        assert!(max_batch_size > 0, "Max batch size has to be positive.");
+        self.max_batch_size = Some(max_batch_size);
+        self
+    }
+
+    /// Sends the calls collected so far without waiting for the window to end.
+    pub fn flush(&self) {
+        if let Some(window) = self.current.lock().take() {
+            window.lock().flush(&self.transport, self.max_batch_size);
+        }
+    }
+
+    /// Returns the underlying transport.
+    pub fn inner(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: BatchTransport> Transport for AutoBatch<T> {
+    type Out = AutoBatched<T>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        let mut current = self.current.lock();
+        let window = match *current {
+            Some(ref window) if window.lock().is_collecting() => window.clone(),
+            _ => {
+                let window = Arc::new(Mutex::new(Window::new(Instant::now() + self.window)));
+                *current = Some(window.clone());
+                window
+            }
+        };
+
+        let index = {
+            let mut locked = window.lock();
+            locked.calls.push((id, request));
+            let index = locked.calls.len() - 1;
+            if locked.calls.len() >= self.flush_size {
+                locked.flush(&self.transport, self.max_batch_size);
+                *current = None;
+            }
+            index
+        };
+
+        AutoBatched {
+            transport: self.transport.clone(),
+            window,
+            index,
+            max_batch_size: self.max_batch_size,
+            timer: self.timer.clone(),
+            sleep: None,
+        }
+    }
+}
+
+impl<T: BatchTransport> BatchTransport for AutoBatch<T> {
+    type Batch = SplitBatch<T::Batch>;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        SplitBatch::new(&self.transport, requests, self.max_batch_size)
+    }
+}
+
+enum WindowState<F> {
+    Collecting,
+    Sending(SplitBatch<F>),
+    Done(Vec<Option<Result<rpc::Value>>>),
+}
+
+/// Calls collected into a single batch.
+struct Window<F> {
+    calls: Vec<(RequestId, rpc::Call)>,
+    deadline: Instant,
+    state: WindowState<F>,
+    /// Calls waiting for the batch to be sent (or answered) through another call.
+    waiting: Vec<Task>,
+}
+
+impl<F> Window<F> {
+    fn new(deadline: Instant) -> Self {
+        Window {
+            calls: vec![],
+            deadline,
+            state: WindowState::Collecting,
+            waiting: vec![],
+        }
+    }
+
+    /// Registers current task to be notified once the batch is sent (or answered).
+    fn wait(&mut self) {
+        if !self.waiting.iter().any(Task::will_notify_current) {
+            self.waiting.push(task::current());
+        }
+    }
+
+    fn is_collecting(&self) -> bool {
+        match self.state {
+            WindowState::Collecting => true,
+            _ => false,
+        }
+    }
+
+    fn flush<T: BatchTransport<Batch = F>>(&mut self, transport: &T, max_batch_size: Option<usize>) {
+        if self.is_collecting() {
+            let calls = mem::replace(&mut self.calls, vec![]);
+            log::debug!("Sending batch of {} calls", calls.len());
+            self.state = WindowState::Sending(SplitBatch::new(transport, calls, max_batch_size));
+            for task in self.waiting.drain(..) {
+                task.notify();
+            }
+        }
+    }
+}
+
+/// A future representing a call sent as part of an automatic batch.
+pub struct AutoBatched<T: BatchTransport> {
+    transport: T,
+    window: Arc<Mutex<Window<T::Batch>>>,
+    index: usize,
+    max_batch_size: Option<usize>,
+    timer: Timer,
+    sleep: Option<Sleep>,
+}
+
+impl<T: BatchTransport> fmt::Debug for AutoBatched<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AutoBatched")
+            .field("transport", &self.transport)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+impl<T: BatchTransport> Future for AutoBatched<T> {
+    type Item = rpc::Value;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let mut window = self.window.lock();
+        loop {
+            let result = match window.state {
+                WindowState::Collecting => {
+                    let now = Instant::now();
+                    if self.sleep.is_none() && now < window.deadline {
+                        self.sleep = Some(self.timer.sleep(window.deadline - now));
+                    }
+                    if let Some(ref mut sleep) = self.sleep {
+                        match sleep.poll() {
+                            Ok(Async::NotReady) => {
+                                window.wait();
+                                return Ok(Async::NotReady);
+                            }
+                            Ok(Async::Ready(())) => {}
+                            Err(err) => log::warn!("Batch window timer failed: {:?}", err),
+                        }
+                    }
+                    window.flush(&self.transport, self.max_batch_size);
+                    continue;
+                }
+                WindowState::Sending(ref mut batch) => match batch.poll() {
+                    Ok(Async::NotReady) => {
+                        window.wait();
+                        return Ok(Async::NotReady);
+                    }
+                    Ok(Async::Ready(results)) => results,
+                    Err(err) => vec![Err(err); batch.len()],
+                },
+                WindowState::Done(ref mut results) => {
+                    return results
+                        .get_mut(self.index)
+                        .and_then(Option::take)
+                        .unwrap_or(Err(Error::Internal))
+                        .map(Async::Ready);
+                }
+            };
+
+            window.state = WindowState::Done(result.into_iter().map(Some).collect());
+            for task in window.waiting.drain(..) {
+                task.notify();
+            }
+        }
+    }
+}
+
+impl<T: BatchTransport> Drop for AutoBatched<T> {
+    fn drop(&mut self) {
+        // Let another call drive the batch.
+        let mut window = self.window.lock();
+        if let WindowState::Sending(_) = window.state {
+            for task in window.waiting.drain(..) {
+                task.notify();
+            }
+        }
+    }
+}
+
+/// A batch sent in chunks of limited size.
+///
+/// Resolves to the results of all calls in order, a failed chunk fails all of its calls.
+#[derive(Debug)]
+pub struct SplitBatch<F> {
+    chunks: Vec<(F, usize, Option<Vec<Result<rpc::Value>>>)>,
+}
+
+impl<F> SplitBatch<F> {
+    fn new<T, I>(transport: &T, requests: I, max_batch_size: Option<usize>) -> Self
+    where
+        T: BatchTransport<Batch = F>,
+        I: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let mut requests = requests.into_iter().peekable();
+        let mut chunks = vec![];
+        while requests.peek().is_some() {
+            let chunk = requests
+                .by_ref()
+                .take(max_batch_size.unwrap_or(usize::max_value()))
+                .collect::<Vec<_>>();
+            let len = chunk.len();
+            chunks.push((transport.send_batch(chunk), len, None));
+        }
+        SplitBatch { chunks }
+    }
+
+    /// Returns the number of calls in the batch.
+    fn len(&self) -> usize {
+        self.chunks.iter().map(|&(_, len, _)| len).sum()
+    }
+}
+
+impl<F: Future<Item = Vec<Result<rpc::Value>>, Error = Error>> Future for SplitBatch<F> {
+    type Item = Vec<Result<rpc::Value>>;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let mut ready = true;
+        for &mut (ref mut future, len, ref mut results) in &mut self.chunks {
+            if results.is_none() {
+                match future.poll() {
+                    Ok(Async::NotReady) => ready = false,
+                    Ok(Async::Ready(chunk)) => *results = Some(chunk),
+                    Err(err) => *results = Some(vec![Err(err); len]),
+                }
+            }
+        }
+
+        if !ready {
+            return Ok(Async::NotReady);
+        }
+
+        Ok(Async::Ready(
+            self.chunks
+                .iter_mut()
+                .flat_map(|&mut (_, _, ref mut results)| results.take().expect("all chunks are ready; qed"))
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AutoBatch;
+    use crate::transports::MockTransport;
+    use crate::{rpc, BatchTransport, Error, RequestId, Transport};
+    use futures::{future, Async, Future};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Records the sizes of sent batches.
+    #[derive(Debug, Clone)]
+    struct Batches(MockTransport, Arc<Mutex<Vec<usize>>>);
+
+    impl Transport for Batches {
+        type Out = <MockTransport as Transport>::Out;
+
+        fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+            self.0.prepare(method, params)
+        }
+
+        fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+            self.0.send(id, request)
+        }
+    }
+
+    impl BatchTransport for Batches {
+        type Batch = <MockTransport as BatchTransport>::Batch;
+
+        fn send_batch<I>(&self, requests: I) -> Self::Batch
+        where
+            I: IntoIterator<Item = (RequestId, rpc::Call)>,
+        {
+            let requests = requests.into_iter().collect::<Vec<_>>();
+            self.1.lock().push(requests.len());
+            self.0.send_batch(requests)
+        }
+    }
+
+    fn transport(responses: &[&str]) -> (Batches, Arc<Mutex<Vec<usize>>>) {
+        let mock = MockTransport::new();
+        for response in responses {
+            mock.respond("eth_blockNumber", *response);
+        }
+        let batches = Arc::new(Mutex::new(vec![]));
+        (Batches(mock, batches.clone()), batches)
+    }
+
+    #[test]
+    fn should_batch_calls_within_window() {
+        // given
+        let (inner, batches) = transport(&["0x1", "0x2", "0x3"]);
+        let transport = AutoBatch::new(inner).with_window(Duration::from_millis(20));
+
+        // when
+        let calls = (0..3)
+            .map(|_| transport.execute("eth_blockNumber", vec![]))
+            .collect::<Vec<_>>();
+        let results = future::join_all(calls).wait();
+
+        // then
+        assert_eq!(
+            results,
+            Ok(vec![
+                rpc::Value::String("0x1".into()),
+                rpc::Value::String("0x2".into()),
+                rpc::Value::String("0x3".into()),
+            ])
+        );
+        assert_eq!(*batches.lock(), vec![3]);
+    }
+
+    #[test]
+    fn should_flush_on_size() {
+        // given
+        let (inner, batches) = transport(&["0x1", "0x2", "0x3"]);
+        let transport = AutoBatch::new(inner)
+            .with_window(Duration::from_secs(3600))
+            .with_flush_size(2);
+
+        // when
+        let first = transport.execute("eth_blockNumber", vec![]);
+        let second = transport.execute("eth_blockNumber", vec![]);
+        let sent = batches.lock().clone();
+        let third = transport.execute("eth_blockNumber", vec![]);
+        transport.flush();
+
+        // then
+        assert_eq!(sent, vec![2]);
+        assert_eq!(first.wait(), Ok(rpc::Value::String("0x1".into())));
+        assert_eq!(second.wait(), Ok(rpc::Value::String("0x2".into())));
+        assert_eq!(third.wait(), Ok(rpc::Value::String("0x3".into())));
+        assert_eq!(*batches.lock(), vec![2, 1]);
+    }
+
+    #[test]
+    fn should_split_oversized_batches() {
+        // given
+        let (inner, batches) = transport(&["0x1", "0x2", "0x3", "0x4", "0x5"]);
+        inner.0.fail("eth_gasPrice", Error::Unreachable);
+        let transport = AutoBatch::new(inner).with_max_batch_size(2);
+
+        // when
+        let mut requests = (0..5)
+            .map(|_| transport.prepare("eth_blockNumber", vec![]))
+            .collect::<Vec<_>>();
+        requests.insert(1, transport.prepare("eth_gasPrice", vec![]));
+        let results = transport.send_batch(requests).wait().unwrap();
+
+        // then
+        assert_eq!(*batches.lock(), vec![2, 2, 2]);
+        assert_eq!(results.len(), 6);
+        assert_eq!(results[1], Err(Error::Unreachable));
+        assert_eq!(results[5], Ok(rpc::Value::String("0x5".into())));
+    }
+
+    #[test]
+    fn should_register_each_waiting_task_once() {
+        // given
+        let (inner, _) = transport(&["0x1"]);
+        let transport = AutoBatch::new(inner).with_window(Duration::from_secs(3600));
+        let mut call = transport.execute("eth_blockNumber", vec![]);
+
+        // when
+        future::lazy(|| {
+            for _ in 0..3 {
+                assert_eq!(call.poll(), Ok(Async::NotReady));
+            }
+            Ok::<_, ()>(())
+        })
+        .wait()
+        .unwrap();
+
+        // then
+        assert_eq!(call.window.lock().waiting.len(), 1);
+        transport.flush();
+        assert_eq!(call.wait(), Ok(rpc::Value::String("0x1".into())));
+    }
+}
@@ -0,0 +1,22 @@
+//! Exponential backoff shared by the transports retrying or reconnecting.
+
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Delay before given attempt (starting from 0), doubling `initial` up to `max`.
+///
+/// With `jitter` the delay is randomized between half and the full delay, so that many
+/// clients failing at once (e.g. after a node restart) don't retry in lockstep.
+pub fn delay(initial: Duration, max: Duration, jitter: bool, attempt: usize) -> Duration {
+    let factor = 1u32 << cmp::min(attempt, 16);
+    let delay = cmp::min(initial.checked_mul(factor).unwrap_or(max), max);
+    if !jitter {
+        return delay;
+    }
+
+    let half = delay.as_nanos() as u64 / 2;
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_nanos(half + random % (half + 1))
+}
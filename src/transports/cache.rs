@@ -0,0 +1,604 @@
+//! Caching Transport
+
+use crate::rpc;
+use crate::transports::Result;
+use crate::{BatchTransport, Error, RequestId, Transport};
+use futures::{Async, Future, Poll};
+use hash::keccak;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{fs, io};
+
+/// Default number of blocks below the latest one, after which results are considered final.
+pub const DEFAULT_FINALITY_DEPTH: u64 = 64;
+
+/// Returns the cache key of given call if its result can never change.
+///
+/// Calls are cacheable only if they target a block by hash or by a number not above `finalized`,
+/// block tags (`latest`, `pending`, `safe`, ...) are never cached.
+/// Transactions and receipts are looked up by their key, but only stored once included in a finalized block.
+pub fn cache_key(request: &rpc::Call, finalized: Option<u64>) -> Option<String> {
+    match entry(request, finalized)? {
+        Entry::Fixed(key) | Entry::Mined(key) => Some(key),
+        Entry::Head => None,
+    }
+}
+
+/// Describes how the result of a call is used by the cache.
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    /// Result is the latest block number.
+    Head,
+    /// Result can be cached under given key.
+    Fixed(String),
+    /// Result is a transaction or receipt that can be cached under given key once finalized.
+    Mined(String),
+}
+
+fn entry(request: &rpc::Call, finalized: Option<u64>) -> Option<Entry> {
+    let call = match *request {
+        rpc::Call::MethodCall(ref call) => call,
+        _ => return None,
+    };
+    let params = match call.params {
+        rpc::Params::Array(ref params) => &params[..],
+        rpc::Params::None => &[],
+        rpc::Params::Map(_) => return None,
+    };
+    let fixed = |block: Option<&rpc::Value>| block.map_or(false, |block| is_fixed_block(block, finalized));
+    let key = || format!("{}:{}", call.method, rpc::Value::Array(params.to_vec()));
+
+    let cacheable = match call.method.as_str() {
+        "eth_blockNumber" => return Some(Entry::Head),
+        "eth_getTransactionByHash" | "eth_getTransactionReceipt" if !params.is_empty() => {
+            return Some(Entry::Mined(key()));
+        }
+        "eth_chainId" | "net_version" => true,
+        "eth_getBlockByHash"
+        | "eth_getBlockTransactionCountByHash"
+        | "eth_getUncleCountByBlockHash"
+        | "eth_getTransactionByBlockHashAndIndex"
+        | "eth_getUncleByBlockHashAndIndex" => !params.is_empty(),
+        "eth_getBlockByNumber"
+        | "eth_getBlockTransactionCountByNumber"
+        | "eth_getUncleCountByBlockNumber"
+        | "eth_getTransactionByBlockNumberAndIndex"
+        | "eth_getUncleByBlockNumberAndIndex" => fixed(params.get(0)),
+        "eth_getBalance" | "eth_getCode" | "eth_getTransactionCount" | "eth_call" => fixed(params.get(1)),
+        "eth_getStorageAt" => fixed(params.get(2)),
+        "eth_getLogs" => params.get(0).map_or(false, |filter| is_fixed_range(filter, finalized)),
+        _ => false,
+    };
+
+    if cacheable {
+        Some(Entry::Fixed(key()))
+    } else {
+        None
+    }
+}
+
+/// Block hash, block number not above `finalized` or EIP-1898 block object.
+fn is_fixed_block(block: &rpc::Value, finalized: Option<u64>) -> bool {
+    match *block {
+        rpc::Value::String(ref hash) if hash.len() == 66 && hash.starts_with("0x") => true,
+        rpc::Value::String(_) => is_finalized(block, finalized),
+        rpc::Value::Object(ref block) => {
+            block.contains_key("blockHash")
+                || block
+                    .get("blockNumber")
+                    .map_or(false, |block| is_fixed_block(block, finalized))
+        }
+        _ => false,
+    }
+}
+
+/// Log filter by block hash or with both bounds fixed.
+fn is_fixed_range(filter: &rpc::Value, finalized: Option<u64>) -> bool {
+    match *filter {
+        rpc::Value::Object(ref filter) => {
+            let fixed = |block: Option<&rpc::Value>| block.map_or(false, |block| is_fixed_block(block, finalized));
+            filter.contains_key("blockHash") || (fixed(filter.get("fromBlock")) && fixed(filter.get("toBlock")))
+        }
+        _ => false,
+    }
+}
+
+/// Hex-encoded block number not above `finalized`.
+fn is_finalized(number: &rpc::Value, finalized: Option<u64>) -> bool {
+    match (parse_number(number), finalized) {
+        (Some(number), Some(finalized)) => number <= finalized,
+        _ => false,
+    }
+}
+
+/// Transaction or receipt included in a block not above `finalized`.
+fn is_finalized_transaction(value: &rpc::Value, finalized: Option<u64>) -> bool {
+    value.get("blockHash").map_or(false, |hash| !hash.is_null())
+        && value
+            .get("blockNumber")
+            .map_or(false, |number| is_finalized(number, finalized))
+}
+
+fn parse_number(number: &rpc::Value) -> Option<u64> {
+    let number = number.as_str()?;
+    if !number.starts_with("0x") {
+        return None;
+    }
+    u64::from_str_radix(&number[2..], 16).ok()
+}
+
+/// In-memory store evicting least recently used entries.
+#[derive(Debug)]
+struct Lru {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (rpc::Value, u64)>,
+    usage: BTreeMap<u64, String>,
+}
+
+impl Lru {
+    fn get(&mut self, key: &str) -> Option<rpc::Value> {
+        self.tick += 1;
+        let tick = self.tick;
+        let entry = self.entries.get_mut(key)?;
+        self.usage.remove(&entry.1);
+        self.usage.insert(tick, key.into());
+        entry.1 = tick;
+        Some(entry.0.clone())
+    }
+
+    fn insert(&mut self, key: String, value: rpc::Value) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((_, tick)) = self.entries.insert(key.clone(), (value, self.tick)) {
+            self.usage.remove(&tick);
+        }
+        self.usage.insert(self.tick, key);
+
+        while self.entries.len() > self.capacity {
+            let oldest = *self.usage.keys().next().expect("usage tracks all entries; qed");
+            if let Some(key) = self.usage.remove(&oldest) {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+/// On-disk store keeping each result in a separate JSON file.
+#[derive(Debug, Clone)]
+pub struct DiskStore {
+    path: PathBuf,
+}
+
+impl DiskStore {
+    /// Creates new store in given directory, creating it if needed.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        fs::create_dir_all(path.as_ref())?;
+        Ok(DiskStore {
+            path: path.as_ref().into(),
+        })
+    }
+
+    fn file(&self, key: &str) -> PathBuf {
+        self.path.join(format!("{:x}.json", keccak(key.as_bytes())))
+    }
+
+    /// Reads a cached result.
+    pub fn get(&self, key: &str) -> Option<rpc::Value> {
+        let file = fs::File::open(self.file(key)).ok()?;
+        serde_json::from_reader(io::BufReader::new(file)).ok()
+    }
+
+    /// Stores a result.
+    pub fn insert(&self, key: &str, value: &rpc::Value) -> io::Result<()> {
+        // Write to a temporary file first, so that readers never see partial results.
+        let file = self.file(key);
+        let tmp = file.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(value)?)?;
+        fs::rename(tmp, file)
+    }
+}
+
+#[derive(Debug)]
+struct Store {
+    memory: Mutex<Lru>,
+    disk: Option<DiskStore>,
+    head: Mutex<Option<u64>>,
+}
+
+impl Store {
+    fn get(&self, key: &str) -> Option<rpc::Value> {
+        if let Some(value) = self.memory.lock().get(key) {
+            return Some(value);
+        }
+
+        let value = self.disk.as_ref()?.get(key)?;
+        self.memory.lock().insert(key.into(), value.clone());
+        Some(value)
+    }
+
+    fn insert(&self, key: String, value: &rpc::Value) {
+        // Missing blocks, transactions or receipts might still appear.
+        if value.is_null() {
+            return;
+        }
+
+        if let Some(ref disk) = self.disk {
+            if let Err(err) = disk.insert(&key, value) {
+                log::warn!("Unable to store cached result: {:?}", err);
+            }
+        }
+        self.memory.lock().insert(key, value.clone());
+    }
+
+    /// Returns the latest block number considered final.
+    fn finalized(&self, finality: u64) -> Option<u64> {
+        self.head.lock().and_then(|head| head.checked_sub(finality))
+    }
+
+    /// Records the result of given call.
+    fn complete(&self, entry: Entry, value: &rpc::Value, finality: u64) {
+        match entry {
+            Entry::Head => {
+                if let Some(number) = parse_number(value) {
+                    let mut head = self.head.lock();
+                    if head.map_or(true, |head| head < number) {
+                        *head = Some(number);
+                    }
+                }
+            }
+            Entry::Fixed(key) => self.insert(key, value),
+            Entry::Mined(key) => {
+                if is_finalized_transaction(value, self.finalized(finality)) {
+                    self.insert(key, value)
+                }
+            }
+        }
+    }
+}
+
+/// Transport caching results of calls that can never change.
+///
+/// See `cache_key` for the calls being cached. Errors and `null` results are never cached.
+/// Batch requests are passed through, populating the cache with their results.
+///
+/// Results for block numbers are cached only once the block is at least `finality` blocks below
+/// the latest block number returned by `eth_blockNumber` calls made through this transport.
+#[derive(Debug, Clone)]
+pub struct Cache<T> {
+    transport: T,
+    store: Arc<Store>,
+    finality: u64,
+}
+
+impl<T: Transport> Cache<T> {
+    /// Creates new Cache transport keeping at most `capacity` results in memory.
+    pub fn new(transport: T, capacity: usize) -> Self {
+        Self::with_store(transport, capacity, None)
+    }
+
+    /// Creates new Cache transport keeping at most `capacity` results in memory,
+    /// backed by given on-disk store.
+    pub fn with_disk_store(transport: T, capacity: usize, disk: DiskStore) -> Self {
+        Self::with_store(transport, capacity, Some(disk))
+    }
+
+    fn with_store(transport: T, capacity: usize, disk: Option<DiskStore>) -> Self {
+        Cache {
+            transport,
+            store: Arc::new(Store {
+                memory: Mutex::new(Lru {
+                    capacity,
+                    tick: 0,
+                    entries: Default::default(),
+                    usage: Default::default(),
+                }),
+                disk,
+                head: Mutex::new(None),
+            }),
+            finality: DEFAULT_FINALITY_DEPTH,
+        }
+    }
+
+    /// Sets the number of blocks below the latest one, after which results are considered final.
+    pub fn with_finality_depth(mut self, finality: u64) -> Self {
+        self.finality = finality;
+        self
+    }
+
+    /// Returns the underlying transport.
+    pub fn inner(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> Transport for Cache<T> {
+    type Out = CacheFuture<T::Out>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        let entry = entry(&request, self.store.finalized(self.finality));
+        let cached = match entry {
+            Some(Entry::Fixed(ref key)) | Some(Entry::Mined(ref key)) => self.store.get(key),
+            _ => None,
+        };
+        let future = match cached {
+            Some(_) => {
+                log::trace!("[{}] Cached: {:?}", id, entry);
+                None
+            }
+            None => Some(self.transport.send(id, request)),
+        };
+
+        CacheFuture {
+            cached,
+            future,
+            entry,
+            store: self.store.clone(),
+            finality: self.finality,
+        }
+    }
+}
+
+impl<T: BatchTransport> BatchTransport for Cache<T> {
+    type Batch = CacheBatch<T::Batch>;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let requests = requests.into_iter().collect::<Vec<_>>();
+        let finalized = self.store.finalized(self.finality);
+        let entries = requests
+            .iter()
+            .map(|&(_, ref request)| entry(request, finalized))
+            .collect();
+
+        CacheBatch {
+            future: self.transport.send_batch(requests),
+            entries,
+            store: self.store.clone(),
+            finality: self.finality,
+        }
+    }
+}
+
+/// A future representing a cached or sent request.
+#[derive(Debug)]
+pub struct CacheFuture<F> {
+    cached: Option<rpc::Value>,
+    future: Option<F>,
+    entry: Option<Entry>,
+    store: Arc<Store>,
+    finality: u64,
+}
+
+impl<F: Future<Item = rpc::Value, Error = Error>> Future for CacheFuture<F> {
+    type Item = rpc::Value;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if let Some(value) = self.cached.take() {
+            return Ok(Async::Ready(value));
+        }
+
+        let value = try_ready!(self.future.as_mut().expect("Polled a completed future.").poll());
+        if let Some(entry) = self.entry.take() {
+            self.store.complete(entry, &value, self.finality);
+        }
+        Ok(Async::Ready(value))
+    }
+}
+
+/// A future representing a batch request populating the cache.
+#[derive(Debug)]
+pub struct CacheBatch<F> {
+    future: F,
+    entries: Vec<Option<Entry>>,
+    store: Arc<Store>,
+    finality: u64,
+}
+
+impl<F: Future<Item = Vec<Result<rpc::Value>>, Error = Error>> Future for CacheBatch<F> {
+    type Item = Vec<Result<rpc::Value>>;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let results = try_ready!(self.future.poll());
+        for (entry, result) in self.entries.drain(..).zip(&results) {
+            if let (Some(entry), &Ok(ref value)) = (entry, result) {
+                self.store.complete(entry, value, self.finality);
+            }
+        }
+        Ok(Async::Ready(results))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cache_key, Cache, DiskStore, Lru};
+    use crate::helpers::tests::TestTransport;
+    use crate::rpc;
+    use crate::{Error, Transport};
+    use futures::Future;
+    use std::fs;
+
+    fn key(method: &str, params: Vec<rpc::Value>) -> Option<String> {
+        cache_key(&crate::helpers::build_request(1, method, params), Some(0x10))
+    }
+
+    #[test]
+    fn should_classify_cacheable_calls() {
+        let hash = rpc::Value::String("0x1234".into());
+        let call = serde_json::json!({"to": "0x0000000000000000000000000000000000000001"});
+
+        assert_eq!(
+            key("eth_getBlockByHash", vec![hash.clone(), false.into()]),
+            Some(r#"eth_getBlockByHash:["0x1234",false]"#.into())
+        );
+        assert!(key("eth_getBlockByNumber", vec!["0x10".into(), false.into()]).is_some());
+        assert!(key("eth_getBlockByNumber", vec!["0x11".into(), false.into()]).is_none());
+        assert!(key("eth_getBlockByNumber", vec!["latest".into(), false.into()]).is_none());
+        assert!(key("eth_call", vec![call.clone(), "0x10".into()]).is_some());
+        assert!(key(
+            "eth_call",
+            vec![call.clone(), serde_json::json!({ "blockHash": "0x1234" })]
+        )
+        .is_some());
+        assert!(key("eth_call", vec![call, "pending".into()]).is_none());
+        assert!(key("eth_getStorageAt", vec![hash.clone(), "0x0".into(), "safe".into()]).is_none());
+        assert!(key(
+            "eth_getLogs",
+            vec![serde_json::json!({"fromBlock": "0x1", "toBlock": "0x2"})]
+        )
+        .is_some());
+        assert!(key(
+            "eth_getLogs",
+            vec![serde_json::json!({"fromBlock": "0x1", "toBlock": "latest"})]
+        )
+        .is_none());
+        assert!(key("eth_getTransactionReceipt", vec![hash.clone()]).is_some());
+        assert!(key("eth_blockNumber", vec![]).is_none());
+        assert!(key("eth_sendRawTransaction", vec![hash]).is_none());
+    }
+
+    #[test]
+    fn should_serve_cached_results() {
+        // given
+        let mut transport = TestTransport::default();
+        transport.add_response(rpc::Value::String("0x100".into()));
+        transport.add_response(rpc::Value::String("0x1".into()));
+        let cache = Cache::new(transport, 16);
+        let params = vec![
+            rpc::Value::String("0x0000000000000000000000000000000000000001".into()),
+            "0x10".into(),
+        ];
+
+        // when
+        cache.execute("eth_blockNumber", vec![]).wait().unwrap();
+        let first = cache.execute("eth_getBalance", params.clone()).wait();
+        let second = cache.execute("eth_getBalance", params).wait();
+
+        // then
+        assert_eq!(first, Ok(rpc::Value::String("0x1".into())));
+        assert_eq!(second, Ok(rpc::Value::String("0x1".into())));
+    }
+
+    #[test]
+    fn should_not_cache_null_results() {
+        // given
+        let mut transport = TestTransport::default();
+        transport.add_response(rpc::Value::Null);
+        let cache = Cache::new(transport, 16);
+        let params = vec![rpc::Value::String("0x1234".into())];
+
+        // when
+        let first = cache.execute("eth_getTransactionReceipt", params.clone()).wait();
+        let second = cache.execute("eth_getTransactionReceipt", params).wait();
+
+        // then
+        assert_eq!(first, Ok(rpc::Value::Null));
+        assert_eq!(second, Err(Error::Unreachable));
+    }
+
+    #[test]
+    fn should_cache_blocks_only_below_finality_depth() {
+        // given
+        let mut transport = TestTransport::default();
+        transport.add_response(rpc::Value::String("0x12".into()));
+        transport.add_response(rpc::Value::String("0x1".into()));
+        transport.add_response(rpc::Value::String("0x2".into()));
+        transport.add_response(rpc::Value::String("0x3".into()));
+        let cache = Cache::new(transport, 16).with_finality_depth(2);
+        let recent = vec![rpc::Value::String("0x11".into()), false.into()];
+        let finalized = vec![rpc::Value::String("0x10".into()), false.into()];
+
+        // when
+        cache.execute("eth_blockNumber", vec![]).wait().unwrap();
+        let recent1 = cache
+            .execute("eth_getBlockTransactionCountByNumber", recent.clone())
+            .wait();
+        let recent2 = cache.execute("eth_getBlockTransactionCountByNumber", recent).wait();
+        let finalized1 = cache
+            .execute("eth_getBlockTransactionCountByNumber", finalized.clone())
+            .wait();
+        let finalized2 = cache.execute("eth_getBlockTransactionCountByNumber", finalized).wait();
+
+        // then
+        assert_eq!(recent1, Ok(rpc::Value::String("0x1".into())));
+        assert_eq!(recent2, Ok(rpc::Value::String("0x2".into())));
+        assert_eq!(finalized1, Ok(rpc::Value::String("0x3".into())));
+        assert_eq!(finalized2, Ok(rpc::Value::String("0x3".into())));
+    }
+
+    #[test]
+    fn should_cache_only_finalized_transactions() {
+        // given
+        let pending = serde_json::json!({"hash": "0x1234", "blockHash": null, "blockNumber": null});
+        let recent = serde_json::json!({"hash": "0x1234", "blockHash": "0x5678", "blockNumber": "0x11"});
+        let finalized = serde_json::json!({"hash": "0x1234", "blockHash": "0x5678", "blockNumber": "0x10"});
+        let mut transport = TestTransport::default();
+        transport.add_response(rpc::Value::String("0x12".into()));
+        transport.add_response(pending.clone());
+        transport.add_response(recent.clone());
+        transport.add_response(finalized.clone());
+        let cache = Cache::new(transport, 16).with_finality_depth(2);
+        let params = vec![rpc::Value::String("0x1234".into())];
+
+        // when
+        cache.execute("eth_blockNumber", vec![]).wait().unwrap();
+        let results = (0..4)
+            .map(|_| cache.execute("eth_getTransactionByHash", params.clone()).wait())
+            .collect::<Vec<_>>();
+
+        // then
+        assert_eq!(
+            results,
+            vec![Ok(pending), Ok(recent), Ok(finalized.clone()), Ok(finalized)]
+        );
+    }
+
+    #[test]
+    fn should_evict_least_recently_used() {
+        // given
+        let mut lru = Lru {
+            capacity: 2,
+            tick: 0,
+            entries: Default::default(),
+            usage: Default::default(),
+        };
+        lru.insert("a".into(), 1.into());
+        lru.insert("b".into(), 2.into());
+
+        // when
+        lru.get("a");
+        lru.insert("c".into(), 3.into());
+
+        // then
+        assert_eq!(lru.get("a"), Some(1.into()));
+        assert_eq!(lru.get("b"), None);
+        assert_eq!(lru.get("c"), Some(3.into()));
+    }
+
+    #[test]
+    fn should_persist_results_on_disk() {
+        // given
+        let path = std::env::temp_dir().join(format!("web3-cache-{}", std::process::id()));
+        let store = DiskStore::new(&path).unwrap();
+
+        // when
+        store.insert("eth_chainId:[]", &"0x1".into()).unwrap();
+
+        // then
+        assert_eq!(DiskStore::new(&path).unwrap().get("eth_chainId:[]"), Some("0x1".into()));
+        assert_eq!(store.get("net_version:[]"), None);
+        fs::remove_dir_all(path).unwrap();
+    }
+}
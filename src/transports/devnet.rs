@@ -0,0 +1,993 @@
+//! In-process development chain Transport
+
+use crate::rpc;
+use crate::signing::{self, SecretKey};
+use crate::transports::Result;
+use crate::types::{
+    AccessList, AccessListItem, Address, Block, Bytes, Index, Log, Transaction, TransactionReceipt, H2048, H256, U256,
+    U64,
+};
+use crate::{BatchTransport, Error, RequestId, Transport};
+use ethereum_types::BloomInput;
+use evm::backend::{self, ApplyBackend, MemoryAccount, MemoryBackend, MemoryVicinity};
+use evm::executor::StackExecutor;
+use evm::{Config, CreateScheme, ExitReason};
+use futures;
+use parking_lot::Mutex;
+use rlp::{DecoderError, Rlp, RlpStream};
+use rustc_hex::ToHex;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::cmp;
+use std::collections::BTreeMap;
+use std::sync::{atomic, Arc};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Chain id of the devnet unless configured otherwise.
+pub const DEFAULT_CHAIN_ID: u64 = 1337;
+/// Number of funded accounts of the devnet.
+const DEFAULT_ACCOUNTS: usize = 10;
+/// Block gas limit, also used for requests without gas.
+const GAS_LIMIT: u64 = 12_000_000;
+/// Reported gas price, gas is not charged.
+const GAS_PRICE: u64 = 1_000_000_000;
+/// Number of recent block hashes available to the `BLOCKHASH` opcode.
+const BLOCK_HASHES: usize = 256;
+
+/// Transport answering `eth_*` requests from an in-memory chain, executing transactions with an EVM.
+///
+/// The chain starts with funded accounts which can send transactions (`eth_sendTransaction`)
+/// and whose secret keys are available to sign them (`eth_sendRawTransaction`). Every transaction
+/// is mined in a block of its own right away. Gas is not charged and only the latest state is kept,
+/// block parameters of state queries are ignored.
+///
+/// Polling a block filter without new blocks mines an empty block, so waiting for confirmations
+/// (`Contract::deploy`, `confirm::send_transaction_with_confirmation`) completes without any
+/// further transactions.
+#[derive(Debug, Clone)]
+pub struct Devnet {
+    id: Arc<atomic::AtomicUsize>,
+    chain: Arc<Mutex<Chain>>,
+}
+
+impl Default for Devnet {
+    fn default() -> Self {
+        Devnet::with_accounts(DEFAULT_ACCOUNTS, U256::exp10(20))
+    }
+}
+
+impl Devnet {
+    /// Creates new devnet with 10 accounts holding 100 ether each.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates new devnet with given number of accounts holding `balance` each.
+    ///
+    /// Secret keys of the accounts are deterministic: `1`, `2`, ... as 32-byte big-endian numbers.
+    pub fn with_accounts(accounts: usize, balance: U256) -> Self {
+        let keys = (1..=accounts as u64)
+            .map(|i| {
+                SecretKey::from_slice(H256::from_low_u64_be(i).as_bytes()).expect("Small numbers are valid keys; qed")
+            })
+            .collect::<Vec<_>>();
+        let state = keys
+            .iter()
+            .map(|key| {
+                let account = MemoryAccount {
+                    nonce: U256::zero(),
+                    balance,
+                    storage: Default::default(),
+                    code: vec![],
+                };
+                (signing::secret_key_address(key), account)
+            })
+            .collect();
+
+        let mut chain = Chain {
+            chain_id: DEFAULT_CHAIN_ID,
+            gas_limit: GAS_LIMIT.into(),
+            mine_on_poll: true,
+            keys,
+            state,
+            blocks: vec![],
+            transactions: Default::default(),
+            filters: Default::default(),
+            next_filter: 0,
+        };
+        chain.mine_block(vec![]);
+
+        Devnet {
+            id: Arc::new(atomic::AtomicUsize::new(1)),
+            chain: Arc::new(Mutex::new(chain)),
+        }
+    }
+
+    /// Sets the chain id, defaults to `1337`.
+    pub fn with_chain_id(self, chain_id: u64) -> Self {
+        self.chain.lock().chain_id = chain_id;
+        self
+    }
+
+    /// Sets whether polling a block filter without new blocks mines an empty block, defaults to `true`.
+    pub fn with_mine_on_poll(self, mine_on_poll: bool) -> Self {
+        self.chain.lock().mine_on_poll = mine_on_poll;
+        self
+    }
+
+    /// Returns the funded accounts.
+    pub fn accounts(&self) -> Vec<Address> {
+        self.chain.lock().accounts()
+    }
+
+    /// Returns secret keys of the funded accounts.
+    pub fn secret_keys(&self) -> Vec<SecretKey> {
+        self.chain.lock().keys.clone()
+    }
+
+    /// Sets the balance of given account.
+    pub fn set_balance(&self, address: Address, balance: U256) {
+        let mut chain = self.chain.lock();
+        chain.state.entry(address).or_insert_with(empty_account).balance = balance;
+    }
+
+    /// Mines given number of empty blocks.
+    pub fn mine(&self, blocks: usize) {
+        let mut chain = self.chain.lock();
+        for _ in 0..blocks {
+            chain.mine_block(vec![]);
+        }
+    }
+
+    /// Returns the number of the latest block.
+    pub fn block_number(&self) -> u64 {
+        self.chain.lock().latest().number
+    }
+
+    fn respond(&self, request: rpc::Call) -> Result<rpc::Value> {
+        let (method, params) = match request {
+            rpc::Call::MethodCall(call) => match call.params {
+                rpc::Params::Array(params) => (call.method, params),
+                rpc::Params::None => (call.method, vec![]),
+                rpc::Params::Map(params) => (call.method, vec![rpc::Value::Object(params)]),
+            },
+            request => return Err(Error::Transport(format!("Unsupported request: {:?}", request))),
+        };
+
+        log::trace!("Devnet request: {} {:?}", method, params);
+        self.chain.lock().respond(&method, &params)
+    }
+}
+
+impl Transport for Devnet {
+    type Out = crate::Result<rpc::Value>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        let id = self.id.fetch_add(1, atomic::Ordering::AcqRel);
+        let request = crate::helpers::build_request(id, method, params);
+
+        (id, request)
+    }
+
+    fn send(&self, _id: RequestId, request: rpc::Call) -> Self::Out {
+        Box::new(futures::done(self.respond(request)))
+    }
+}
+
+impl BatchTransport for Devnet {
+    type Batch = crate::Result<Vec<Result<rpc::Value>>>;
+
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let responses = requests.into_iter().map(|(_, request)| self.respond(request)).collect();
+        Box::new(futures::finished(responses))
+    }
+}
+
+#[derive(Debug)]
+struct MinedBlock {
+    number: u64,
+    hash: H256,
+    parent_hash: H256,
+    timestamp: u64,
+    gas_used: U256,
+    logs_bloom: H2048,
+    transactions: Vec<H256>,
+}
+
+/// Result of an EVM execution.
+struct Execution {
+    reason: ExitReason,
+    output: Vec<u8>,
+    gas_used: U256,
+    contract_address: Option<Address>,
+    logs: Vec<backend::Log>,
+}
+
+#[derive(Debug)]
+struct Chain {
+    chain_id: u64,
+    gas_limit: U256,
+    mine_on_poll: bool,
+    keys: Vec<SecretKey>,
+    state: BTreeMap<Address, MemoryAccount>,
+    blocks: Vec<MinedBlock>,
+    transactions: BTreeMap<H256, (Transaction, TransactionReceipt)>,
+    /// Block filters with the number of the next block to report.
+    filters: BTreeMap<u64, u64>,
+    next_filter: u64,
+}
+
+impl Chain {
+    fn respond(&mut self, method: &str, params: &[rpc::Value]) -> Result<rpc::Value> {
+        match method {
+            "web3_clientVersion" => to_value(concat!("web3-devnet/v", env!("CARGO_PKG_VERSION"))),
+            "net_version" => to_value(self.chain_id.to_string()),
+            "eth_chainId" => to_value(U64::from(self.chain_id)),
+            "eth_accounts" => to_value(self.accounts()),
+            "eth_blockNumber" => to_value(U64::from(self.latest().number)),
+            "eth_gasPrice" => to_value(U256::from(GAS_PRICE)),
+            "eth_getBalance" => to_value(self.account(&param(params, 0)?).balance),
+            "eth_getTransactionCount" => to_value(self.account(&param(params, 0)?).nonce),
+            "eth_getCode" => to_value(Bytes(self.account(&param(params, 0)?).code)),
+            "eth_getStorageAt" => {
+                let position: U256 = param(params, 1)?;
+                let mut key = H256::zero();
+                position.to_big_endian(key.as_bytes_mut());
+                let account = self.account(&param(params, 0)?);
+                to_value(account.storage.get(&key).cloned().unwrap_or_default())
+            }
+            "eth_call" => {
+                let request: Request = param(params, 0)?;
+                let execution = self.execute(&request, false);
+                if !execution.reason.is_succeed() {
+                    return Err(execution_error(execution));
+                }
+                to_value(Bytes(execution.output))
+            }
+            "eth_estimateGas" => {
+                let request: Request = param(params, 0)?;
+                let execution = self.execute(&request, false);
+                if !execution.reason.is_succeed() {
+                    return Err(execution_error(execution));
+                }
+                to_value(execution.gas_used)
+            }
+            "eth_sendTransaction" => {
+                let request: Request = param(params, 0)?;
+                let transaction = self.transaction(request)?;
+                to_value(self.transact(transaction)?)
+            }
+            "eth_sendRawTransaction" => {
+                let raw: Bytes = param(params, 0)?;
+                let transaction = self.raw_transaction(&raw.0)?;
+                to_value(self.transact(transaction)?)
+            }
+            "eth_getTransactionByHash" => {
+                let hash: H256 = param(params, 0)?;
+                to_value(self.transactions.get(&hash).map(|&(ref tx, _)| tx))
+            }
+            "eth_getTransactionReceipt" => {
+                let hash: H256 = param(params, 0)?;
+                to_value(self.transactions.get(&hash).map(|&(_, ref receipt)| receipt))
+            }
+            "eth_getBlockByNumber" => {
+                let number = self.block_number(params.get(0))?;
+                self.block(self.blocks.get(number as usize), param(params, 1)?)
+            }
+            "eth_getBlockByHash" => {
+                let hash: H256 = param(params, 0)?;
+                self.block(self.blocks.iter().find(|block| block.hash == hash), param(params, 1)?)
+            }
+            "eth_getLogs" => {
+                let filter: LogFilter = param(params, 0)?;
+                to_value(self.logs(&filter)?)
+            }
+            "eth_newBlockFilter" => {
+                let id = self.next_filter;
+                self.next_filter += 1;
+                self.filters.insert(id, self.latest().number + 1);
+                to_value(U256::from(id))
+            }
+            "eth_getFilterChanges" => {
+                let id: U256 = param(params, 0)?;
+                let next = *self
+                    .filters
+                    .get(&id.low_u64())
+                    .ok_or_else(|| server_error("Filter not found".into()))?;
+                if self.mine_on_poll && next > self.latest().number {
+                    self.mine_block(vec![]);
+                }
+                let hashes = self.blocks[next as usize..]
+                    .iter()
+                    .map(|block| block.hash)
+                    .collect::<Vec<_>>();
+                self.filters.insert(id.low_u64(), self.latest().number + 1);
+                to_value(hashes)
+            }
+            "eth_uninstallFilter" => {
+                let id: U256 = param(params, 0)?;
+                to_value(self.filters.remove(&id.low_u64()).is_some())
+            }
+            "evm_mine" => {
+                self.mine_block(vec![]);
+                to_value("0x0")
+            }
+            _ => Err(Error::Rpc(rpc::Error::method_not_found())),
+        }
+    }
+
+    fn accounts(&self) -> Vec<Address> {
+        self.keys.iter().map(signing::secret_key_address).collect()
+    }
+
+    fn account(&self, address: &Address) -> MemoryAccount {
+        self.state.get(address).cloned().unwrap_or_else(empty_account)
+    }
+
+    fn latest(&self) -> &MinedBlock {
+        self.blocks.last().expect("Genesis block is mined on creation; qed")
+    }
+
+    /// Timestamp of the next block, strictly increasing.
+    fn next_timestamp(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        match self.blocks.last() {
+            Some(block) => cmp::max(now, block.timestamp + 1),
+            None => now,
+        }
+    }
+
+    /// Resolves a block parameter (tag, number or `blockHash` object) to a block number.
+    fn block_number(&self, block: Option<&rpc::Value>) -> Result<u64> {
+        match block {
+            None | Some(rpc::Value::Null) => Ok(self.latest().number),
+            Some(rpc::Value::String(tag)) if tag == "latest" || tag == "pending" => Ok(self.latest().number),
+            Some(rpc::Value::String(tag)) if tag == "earliest" => Ok(0),
+            Some(rpc::Value::Object(object)) if object.contains_key("blockHash") => {
+                self.block_by_hash(&param(&[object["blockHash"].clone()], 0)?)
+            }
+            Some(rpc::Value::Object(object)) => self.block_number(object.get("blockNumber")),
+            Some(value) => Ok(param::<U64>(&[value.clone()], 0)?.low_u64()),
+        }
+    }
+
+    fn block_by_hash(&self, hash: &H256) -> Result<u64> {
+        self.blocks
+            .iter()
+            .find(|block| block.hash == *hash)
+            .map(|block| block.number)
+            .ok_or_else(|| server_error(format!("Unknown block: {:?}", hash)))
+    }
+
+    fn block(&self, block: Option<&MinedBlock>, full: Option<bool>) -> Result<rpc::Value> {
+        let block = match block {
+            Some(block) => block,
+            None => return Ok(rpc::Value::Null),
+        };
+
+        if full.unwrap_or(false) {
+            let transactions = block
+                .transactions
+                .iter()
+                .map(|hash| self.transactions[hash].0.clone())
+                .collect();
+            to_value(self.rpc_block(block, transactions))
+        } else {
+            to_value(self.rpc_block(block, block.transactions.clone()))
+        }
+    }
+
+    fn rpc_block<TX: Default>(&self, block: &MinedBlock, transactions: Vec<TX>) -> Block<TX> {
+        Block {
+            hash: Some(block.hash),
+            parent_hash: block.parent_hash,
+            number: Some(block.number.into()),
+            gas_used: block.gas_used,
+            gas_limit: self.gas_limit,
+            logs_bloom: block.logs_bloom,
+            timestamp: block.timestamp.into(),
+            transactions,
+            ..Default::default()
+        }
+    }
+
+    fn logs(&self, filter: &LogFilter) -> Result<Vec<Log>> {
+        let (from, to) = match filter.block_hash {
+            Some(ref hash) => {
+                let number = self.block_by_hash(hash)?;
+                (number, number)
+            }
+            None => (
+                self.block_number(filter.from_block.as_ref())?,
+                self.block_number(filter.to_block.as_ref())?,
+            ),
+        };
+
+        let logs = self
+            .blocks
+            .iter()
+            .skip(from as usize)
+            .take_while(|block| block.number <= to)
+            .flat_map(|block| block.transactions.iter())
+            .flat_map(|hash| self.transactions[hash].1.logs.iter())
+            .filter(|log| filter.matches(log))
+            .cloned()
+            .collect();
+        Ok(logs)
+    }
+
+    /// Executes a call or contract creation on top of the latest state.
+    fn execute(&mut self, request: &Request, commit: bool) -> Execution {
+        let from = request.from.unwrap_or_default();
+        let vicinity = MemoryVicinity {
+            gas_price: request.gas_price.unwrap_or_else(|| GAS_PRICE.into()),
+            origin: from,
+            chain_id: self.chain_id.into(),
+            block_hashes: self
+                .blocks
+                .iter()
+                .rev()
+                .take(BLOCK_HASHES)
+                .map(|block| block.hash)
+                .collect(),
+            block_number: (self.latest().number + 1).into(),
+            block_coinbase: Address::zero(),
+            block_timestamp: self.next_timestamp().into(),
+            block_difficulty: U256::zero(),
+            block_gas_limit: self.gas_limit,
+        };
+        let mut backend = MemoryBackend::new(&vicinity, self.state.clone());
+        let config = Config::istanbul();
+        let gas = cmp::min(request.gas.unwrap_or(self.gas_limit), self.gas_limit).low_u64() as usize;
+        let value = request.value.unwrap_or_default();
+        let data = request.data().to_vec();
+
+        let mut executor = StackExecutor::new(&backend, gas, &config);
+        let (reason, output, contract_address) = match request.to {
+            Some(to) => {
+                let (reason, output) = executor.transact_call(from, to, value, data, gas);
+                (reason, output, None)
+            }
+            None => {
+                let address = executor.create_address(CreateScheme::Legacy { caller: from });
+                (executor.transact_create(from, value, data, gas), vec![], Some(address))
+            }
+        };
+        let gas_used = U256::from(executor.used_gas() as u64);
+        let (values, logs) = executor.deconstruct();
+        let logs = logs.into_iter().collect();
+
+        if commit {
+            backend.apply(values, Vec::new(), false);
+            self.state = backend.state().clone();
+        }
+
+        Execution {
+            reason,
+            output,
+            gas_used,
+            contract_address,
+            logs,
+        }
+    }
+
+    /// Fills in defaults of a transaction sent by one of the devnet accounts.
+    ///
+    /// Such transactions are not signed, their hash is derived from the sender and nonce.
+    fn transaction(&self, request: Request) -> Result<Transaction> {
+        let from = request
+            .from
+            .ok_or_else(|| Error::Rpc(rpc::Error::invalid_params("Missing sender")))?;
+        if !self.accounts().contains(&from) {
+            return Err(server_error(format!("Unknown account: {:?}", from)));
+        }
+        let nonce = request.nonce.unwrap_or_else(|| self.account(&from).nonce);
+
+        let mut hash = RlpStream::new_list(2);
+        hash.append(&from).append(&nonce);
+
+        Ok(Transaction {
+            hash: signing::keccak256(&hash.out()),
+            nonce,
+            from,
+            to: request.to,
+            value: request.value.unwrap_or_default(),
+            gas_price: request
+                .gas_price
+                .or(request.max_fee_per_gas)
+                .unwrap_or_else(|| GAS_PRICE.into()),
+            gas: request.gas.unwrap_or(self.gas_limit),
+            input: Bytes(request.data().to_vec()),
+            transaction_type: request.transaction_type,
+            access_list: request.access_list,
+            max_fee_per_gas: request.max_fee_per_gas,
+            max_priority_fee_per_gas: request.max_priority_fee_per_gas,
+            ..Default::default()
+        })
+    }
+
+    /// Decodes a signed transaction and recovers its sender.
+    fn raw_transaction(&self, raw: &[u8]) -> Result<Transaction> {
+        let invalid = |err| Error::Rpc(rpc::Error::invalid_params(format!("Invalid transaction: {}", err)));
+        let decoded = decode_raw_transaction(raw).map_err(|err| invalid(format!("{:?}", err)))?;
+
+        if let Some(chain_id) = decoded.chain_id {
+            if chain_id != self.chain_id {
+                return Err(invalid(format!("chain id {}, expected {}", chain_id, self.chain_id)));
+            }
+        }
+        let mut transaction = decoded.transaction;
+        transaction.from = signing::recover(&signing::keccak256(&decoded.message), &decoded.signature)
+            .map_err(|err| invalid(format!("{}", err)))?;
+        transaction.hash = signing::keccak256(raw);
+        Ok(transaction)
+    }
+
+    /// Executes a transaction and mines it in a new block.
+    fn transact(&mut self, transaction: Transaction) -> Result<H256> {
+        let expected = self.account(&transaction.from).nonce;
+        if transaction.nonce != expected {
+            return Err(server_error(format!(
+                "Invalid nonce: {}, expected {}",
+                transaction.nonce, expected
+            )));
+        }
+        if self.transactions.contains_key(&transaction.hash) {
+            return Err(server_error("Transaction already imported".into()));
+        }
+
+        let request = Request {
+            from: Some(transaction.from),
+            to: transaction.to,
+            gas: Some(transaction.gas),
+            gas_price: Some(transaction.gas_price),
+            value: Some(transaction.value),
+            input: Some(transaction.input.clone()),
+            ..Default::default()
+        };
+        // Failed executions still increase the nonce of the sender.
+        let execution = self.execute(&request, true);
+        let success = execution.reason.is_succeed();
+        if !success {
+            log::debug!("Transaction {:?} failed: {:?}", transaction.hash, execution.reason);
+        }
+
+        let hash = transaction.hash;
+        let logs = if success {
+            execution
+                .logs
+                .into_iter()
+                .enumerate()
+                .map(|(index, log)| Log {
+                    address: log.address,
+                    topics: log.topics,
+                    data: Bytes(log.data),
+                    block_hash: None,
+                    block_number: None,
+                    transaction_hash: Some(hash),
+                    transaction_index: None,
+                    log_index: None,
+                    transaction_log_index: Some((index as u64).into()),
+                    log_type: None,
+                    removed: Some(false),
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        let receipt = TransactionReceipt {
+            transaction_hash: hash,
+            gas_used: Some(execution.gas_used),
+            contract_address: execution.contract_address.filter(|_| success),
+            logs_bloom: logs_bloom(&logs),
+            logs,
+            status: Some((success as u64).into()),
+            transaction_type: transaction.transaction_type,
+            effective_gas_price: Some(transaction.gas_price),
+            ..Default::default()
+        };
+
+        self.mine_block(vec![(transaction, receipt)]);
+        Ok(hash)
+    }
+
+    fn mine_block(&mut self, mut transactions: Vec<(Transaction, TransactionReceipt)>) {
+        let (number, parent_hash) = match self.blocks.last() {
+            Some(parent) => (parent.number + 1, parent.hash),
+            None => (0, H256::zero()),
+        };
+        let timestamp = self.next_timestamp();
+        let hashes = transactions.iter().map(|&(ref tx, _)| tx.hash).collect::<Vec<_>>();
+
+        let mut header = RlpStream::new_list(4);
+        header
+            .append(&parent_hash)
+            .append(&number)
+            .append(&timestamp)
+            .append_list(&hashes);
+        let hash = signing::keccak256(&header.out());
+
+        let mut gas_used = U256::zero();
+        let mut logs_bloom = H2048::zero();
+        let mut log_index = 0u64;
+        for (index, &mut (ref mut tx, ref mut receipt)) in transactions.iter_mut().enumerate() {
+            let index = Index::from(index as u64);
+            gas_used += receipt.gas_used.unwrap_or_default();
+            logs_bloom.accrue_bloom(&receipt.logs_bloom);
+
+            tx.block_hash = Some(hash);
+            tx.block_number = Some(number.into());
+            tx.transaction_index = Some(index);
+            receipt.block_hash = Some(hash);
+            receipt.block_number = Some(number.into());
+            receipt.transaction_index = index;
+            receipt.cumulative_gas_used = gas_used;
+            for log in &mut receipt.logs {
+                log.block_hash = Some(hash);
+                log.block_number = Some(number.into());
+                log.transaction_index = Some(index.low_u64().into());
+                log.log_index = Some(log_index.into());
+                log_index += 1;
+            }
+        }
+
+        self.transactions
+            .extend(transactions.into_iter().map(|(tx, receipt)| (tx.hash, (tx, receipt))));
+        self.blocks.push(MinedBlock {
+            number,
+            hash,
+            parent_hash,
+            timestamp,
+            gas_used,
+            logs_bloom,
+            transactions: hashes,
+        });
+    }
+}
+
+fn empty_account() -> MemoryAccount {
+    MemoryAccount {
+        nonce: U256::zero(),
+        balance: U256::zero(),
+        storage: Default::default(),
+        code: vec![],
+    }
+}
+
+fn logs_bloom(logs: &[Log]) -> H2048 {
+    let mut bloom = H2048::zero();
+    for log in logs {
+        bloom.accrue(BloomInput::Raw(log.address.as_bytes()));
+        for topic in &log.topics {
+            bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+        }
+    }
+    bloom
+}
+
+fn to_value<T: serde::Serialize>(value: T) -> Result<rpc::Value> {
+    Ok(serde_json::to_value(value)?)
+}
+
+fn param<T: DeserializeOwned>(params: &[rpc::Value], index: usize) -> Result<T> {
+    let value = params.get(index).cloned().unwrap_or(rpc::Value::Null);
+    serde_json::from_value(value).map_err(|err| {
+        Error::Rpc(rpc::Error::invalid_params(format!(
+            "Invalid parameter {}: {}",
+            index, err
+        )))
+    })
+}
+
+fn server_error(message: String) -> Error {
+    Error::Rpc(rpc::Error {
+        code: rpc::ErrorCode::ServerError(-32000),
+        message,
+        data: None,
+    })
+}
+
+/// Reverts are reported like geth does: code `3` with the revert data.
+fn execution_error(execution: Execution) -> Error {
+    match execution.reason {
+        ExitReason::Revert(_) => Error::Rpc(rpc::Error {
+            code: rpc::ErrorCode::ServerError(3),
+            message: "execution reverted".into(),
+            data: Some(rpc::Value::String(format!("0x{}", execution.output.to_hex::<String>()))),
+        }),
+        reason => server_error(format!("Execution failed: {:?}", reason)),
+    }
+}
+
+/// Call and transaction parameters.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Request {
+    from: Option<Address>,
+    to: Option<Address>,
+    gas: Option<U256>,
+    #[serde(rename = "gasPrice")]
+    gas_price: Option<U256>,
+    value: Option<U256>,
+    data: Option<Bytes>,
+    input: Option<Bytes>,
+    nonce: Option<U256>,
+    #[serde(rename = "type")]
+    transaction_type: Option<U64>,
+    #[serde(rename = "accessList")]
+    access_list: Option<AccessList>,
+    #[serde(rename = "maxFeePerGas")]
+    max_fee_per_gas: Option<U256>,
+    #[serde(rename = "maxPriorityFeePerGas")]
+    max_priority_fee_per_gas: Option<U256>,
+}
+
+impl Request {
+    fn data(&self) -> &[u8] {
+        match self.input.as_ref().or_else(|| self.data.as_ref()) {
+            Some(data) => &data.0,
+            None => &[],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T: PartialEq> OneOrMany<T> {
+    fn contains(&self, value: &T) -> bool {
+        match *self {
+            OneOrMany::One(ref one) => one == value,
+            OneOrMany::Many(ref many) => many.contains(value),
+        }
+    }
+}
+
+/// `eth_getLogs` parameters.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LogFilter {
+    #[serde(rename = "fromBlock")]
+    from_block: Option<rpc::Value>,
+    #[serde(rename = "toBlock")]
+    to_block: Option<rpc::Value>,
+    #[serde(rename = "blockHash")]
+    block_hash: Option<H256>,
+    address: Option<OneOrMany<Address>>,
+    topics: Vec<Option<OneOrMany<H256>>>,
+}
+
+impl LogFilter {
+    fn matches(&self, log: &Log) -> bool {
+        let address = self
+            .address
+            .as_ref()
+            .map_or(true, |address| address.contains(&log.address));
+        let topics = self.topics.iter().enumerate().all(|(index, topic)| match *topic {
+            Some(ref topic) => log.topics.get(index).map_or(false, |t| topic.contains(t)),
+            None => true,
+        });
+        address && topics
+    }
+}
+
+/// A decoded signed transaction, without the sender.
+struct DecodedTransaction {
+    transaction: Transaction,
+    chain_id: Option<u64>,
+    /// The signed payload.
+    message: Vec<u8>,
+    /// `r || s || y-parity`
+    signature: [u8; 65],
+}
+
+/// Decodes a legacy (EIP-155 or not) or typed (EIP-2930, EIP-1559) signed transaction.
+fn decode_raw_transaction(raw: &[u8]) -> ::std::result::Result<DecodedTransaction, DecoderError> {
+    let transaction_type = match raw.first() {
+        None => return Err(DecoderError::RlpIsTooShort),
+        Some(&byte) if byte >= 0xc0 => 0,
+        Some(&byte) if byte == 1 || byte == 2 => byte,
+        Some(_) => return Err(DecoderError::Custom("Unsupported transaction type")),
+    };
+    let rlp = if transaction_type == 0 {
+        Rlp::new(raw)
+    } else {
+        Rlp::new(&raw[1..])
+    };
+    let fields = match transaction_type {
+        0 => 9,
+        1 => 11,
+        _ => 12,
+    };
+    if rlp.item_count()? != fields {
+        return Err(DecoderError::RlpIncorrectListLen);
+    }
+
+    // Offset of the fields common to all types (gas, to, value, data).
+    let (offset, chain_id) = if transaction_type == 0 {
+        (2, None)
+    } else {
+        (transaction_type as usize + 2, Some(rlp.val_at::<u64>(0)?))
+    };
+    let to = rlp.at(offset + 1)?;
+    let mut transaction = Transaction {
+        nonce: rlp.val_at(if transaction_type == 0 { 0 } else { 1 })?,
+        gas: rlp.val_at(offset)?,
+        to: if to.is_empty() { None } else { Some(to.as_val()?) },
+        value: rlp.val_at(offset + 2)?,
+        input: Bytes(rlp.val_at(offset + 3)?),
+        ..Default::default()
+    };
+    if transaction_type == 2 {
+        transaction.max_priority_fee_per_gas = Some(rlp.val_at(2)?);
+        transaction.max_fee_per_gas = Some(rlp.val_at(3)?);
+        transaction.gas_price = rlp.val_at(3)?;
+    } else {
+        transaction.gas_price = rlp.val_at(offset - 1)?;
+    }
+    if transaction_type != 0 {
+        transaction.transaction_type = Some(u64::from(transaction_type).into());
+        transaction.access_list = Some(
+            rlp.at(offset + 4)?
+                .iter()
+                .map(|item| {
+                    Ok(AccessListItem {
+                        address: item.val_at(0)?,
+                        storage_keys: item.list_at(1)?,
+                    })
+                })
+                .collect::<::std::result::Result<_, DecoderError>>()?,
+        );
+    }
+
+    let v: u64 = rlp.val_at(fields - 3)?;
+    let r: U256 = rlp.val_at(fields - 2)?;
+    let s: U256 = rlp.val_at(fields - 1)?;
+    let mut signature = [0u8; 65];
+    r.to_big_endian(&mut signature[..32]);
+    s.to_big_endian(&mut signature[32..64]);
+
+    let (parity, chain_id) = match (transaction_type, v) {
+        (0, 27) | (0, 28) => (v - 27, None),
+        (0, v) if v >= 35 => ((v - 35) % 2, Some((v - 35) / 2)),
+        (0, _) => return Err(DecoderError::Custom("Invalid signature")),
+        (_, v) if v <= 1 => (v, chain_id),
+        _ => return Err(DecoderError::Custom("Invalid signature")),
+    };
+    signature[64] = parity as u8;
+
+    // The signed payload consists of the fields without the signature, legacy EIP-155
+    // transactions replace it with `chain_id, 0, 0`.
+    let unsigned = if transaction_type == 0 { 6 } else { fields - 3 };
+    let eip155 = chain_id.filter(|_| transaction_type == 0);
+    let mut message = RlpStream::new_list(if eip155.is_some() { unsigned + 3 } else { unsigned });
+    for index in 0..unsigned {
+        message.append_raw(rlp.at(index)?.as_raw(), 1);
+    }
+    if let Some(chain_id) = eip155 {
+        message.append(&chain_id).append(&0u8).append(&0u8);
+    }
+
+    let mut payload = if transaction_type == 0 {
+        vec![]
+    } else {
+        vec![transaction_type]
+    };
+    payload.extend_from_slice(&message.out());
+
+    Ok(DecodedTransaction {
+        transaction,
+        chain_id,
+        message: payload,
+        signature,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Devnet;
+    use crate::api::Web3;
+    use crate::contract::{Contract, Options};
+    use crate::signing;
+    use crate::types::{Address, TransactionParameters, U256};
+    use crate::{rpc, Error, Transport};
+    use futures::Future;
+    use std::time::Duration;
+
+    // Stores the value passed to `set(uint256)` (emitting it as a log) and returns it from `get()`.
+    const CODE: &str =
+        "6025600c60003960256000f33660241460125760005460005260206000f35b60043580600055600052600160206000a100";
+    const ABI: &[u8] = br#"[
+        {"type":"function","name":"get","inputs":[],"outputs":[{"name":"","type":"uint256"}],"constant":true,"payable":false,"stateMutability":"view"},
+        {"type":"function","name":"set","inputs":[{"name":"value","type":"uint256"}],"outputs":[],"constant":false,"payable":false,"stateMutability":"nonpayable"}
+    ]"#;
+
+    #[test]
+    fn should_deploy_and_call_a_contract() {
+        // given
+        let devnet = Devnet::new();
+        let web3 = Web3::new(devnet.clone());
+        let from = devnet.accounts()[0];
+
+        // when
+        let contract = Contract::deploy(web3.eth(), ABI)
+            .unwrap()
+            .poll_interval(Duration::from_millis(10))
+            .execute(CODE, (), from)
+            .unwrap()
+            .wait()
+            .unwrap();
+        let hash = contract
+            .call("set", U256::from(42), from, Options::default())
+            .wait()
+            .unwrap();
+        let receipt = web3.eth().transaction_receipt(hash).wait().unwrap().unwrap();
+        let value: U256 = contract
+            .query("get", (), None, Options::default(), None)
+            .wait()
+            .unwrap();
+
+        // then
+        assert_eq!(value, U256::from(42));
+        assert_eq!(receipt.status, Some(1.into()));
+        assert_eq!(receipt.logs.len(), 1);
+        assert_eq!(receipt.logs[0].address, contract.address());
+        assert_eq!(web3.eth().transaction_count(from, None).wait(), Ok(U256::from(2)));
+    }
+
+    #[test]
+    fn should_accept_signed_transactions() {
+        // given
+        let devnet = Devnet::new();
+        let web3 = Web3::new(devnet.clone());
+        let key = devnet.secret_keys()[0];
+        let to = Address::from_low_u64_be(0x1234);
+        let tx = TransactionParameters {
+            nonce: Some(0.into()),
+            to: Some(to),
+            value: 1_000.into(),
+            gas: Some(100_000.into()),
+            gas_price: Some(1.into()),
+            chain_id: Some(super::DEFAULT_CHAIN_ID),
+            ..Default::default()
+        };
+        let signed = signing::sign_transaction(tx, &key).unwrap();
+
+        // when
+        let hash = web3.eth().send_raw_transaction(signed.raw_transaction).wait();
+
+        // then
+        assert_eq!(hash, Ok(signed.transaction_hash));
+        assert_eq!(web3.eth().balance(to, None).wait(), Ok(U256::from(1_000)));
+        assert_eq!(devnet.block_number(), 1);
+        let transaction = web3
+            .eth()
+            .transaction(signed.transaction_hash.into())
+            .wait()
+            .unwrap()
+            .unwrap();
+        assert_eq!(transaction.from, signing::secret_key_address(&key));
+    }
+
+    #[test]
+    fn should_report_unknown_methods() {
+        // given
+        let devnet = Devnet::new();
+
+        // when
+        let result = devnet.execute("eth_mining", vec![]).wait();
+
+        // then
+        assert_eq!(result, Err(Error::Rpc(rpc::Error::method_not_found())));
+    }
+}
@@ -0,0 +1,158 @@
+//! Streaming JSON framer
+
+/// Splits a byte stream into top-level JSON objects and arrays.
+///
+/// Frames are found by tracking the nesting depth (ignoring brackets within strings),
+/// so values may be split across reads or concatenated without any delimiter.
+/// Bytes outside of objects and arrays (e.g. whitespace or newlines) are skipped.
+#[derive(Debug, Default)]
+pub struct JsonFramer {
+    buffer: Vec<u8>,
+    /// Number of bytes of `buffer` already scanned
+    scanned: usize,
+    /// Start of the current frame in `buffer`
+    start: Option<usize>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl JsonFramer {
+    /// Creates new empty framer.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Appends received bytes.
+    pub fn extend(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Returns true if there is no partially received frame.
+    pub fn is_empty(&self) -> bool {
+        self.buffer[self.scanned..].iter().all(u8::is_ascii_whitespace) && self.start.is_none()
+    }
+
+    /// Discards all buffered bytes, e.g. after the connection was reset.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Returns the next complete frame, if any.
+    pub fn next_frame(&mut self) -> Option<Vec<u8>> {
+        while self.scanned < self.buffer.len() {
+            let pos = self.scanned;
+            let byte = self.buffer[pos];
+            self.scanned += 1;
+
+            if self.start.is_none() {
+                match byte {
+                    b'{' | b'[' => {
+                        self.start = Some(pos);
+                        self.depth = 1;
+                    }
+                    byte if byte.is_ascii_whitespace() => {}
+                    byte => log::warn!("Skipping unexpected byte outside of JSON value: {:?}", byte as char),
+                }
+                continue;
+            }
+
+            if self.in_string {
+                match byte {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        let start = self.start.take().expect("frame is started; qed");
+                        let frame = self.buffer[start..self.scanned].to_vec();
+                        self.buffer.drain(..self.scanned);
+                        self.scanned = 0;
+                        return Some(frame);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Drop skipped bytes, keep only the partial frame.
+        let start = self.start.unwrap_or(self.scanned);
+        if start > 0 {
+            self.buffer.drain(..start);
+            self.scanned -= start;
+            self.start = self.start.map(|_| 0);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonFramer;
+
+    fn frames(framer: &mut JsonFramer) -> Vec<String> {
+        std::iter::from_fn(|| framer.next_frame())
+            .map(|frame| String::from_utf8(frame).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn should_split_concatenated_values() {
+        // given
+        let mut framer = JsonFramer::new();
+
+        // when
+        framer.extend(br#"{"id":1,"result":"x"}[{"id":2}]  {"id":3}"#);
+
+        // then
+        assert_eq!(
+            frames(&mut framer),
+            vec![r#"{"id":1,"result":"x"}"#, r#"[{"id":2}]"#, r#"{"id":3}"#]
+        );
+        assert!(framer.is_empty());
+    }
+
+    #[test]
+    fn should_join_split_values() {
+        // given
+        let message = br#"{"id":1,"result":{"data":"\"}]{[\\"}}"#;
+        let mut framer = JsonFramer::new();
+        let mut result = vec![];
+
+        // when
+        for chunk in message.chunks(3) {
+            framer.extend(chunk);
+            result.extend(frames(&mut framer));
+        }
+
+        // then
+        assert_eq!(result, vec![String::from_utf8(message.to_vec()).unwrap()]);
+        assert!(framer.is_empty());
+    }
+
+    #[test]
+    fn should_skip_delimiters() {
+        // given
+        let mut framer = JsonFramer::new();
+
+        // when
+        framer.extend(b"\n{\"id\":1}\n\n{\"id\"");
+        let first = frames(&mut framer);
+        framer.extend(b":2}\n");
+        let second = frames(&mut framer);
+
+        // then
+        assert_eq!(first, vec![r#"{"id":1}"#]);
+        assert_eq!(second, vec![r#"{"id":2}"#]);
+        assert!(framer.is_empty());
+    }
+}
@@ -0,0 +1,516 @@
+//! GraphQL (EIP-1767) client
+
+extern crate hyper;
+
+use crate::helpers;
+use crate::rpc;
+use crate::transports::http::{FetchTask, Http};
+use crate::transports::shared::EventLoopHandle;
+use crate::transports::Result;
+use crate::types::{Block, BlockId, BlockNumber, Filter, Log, Transaction, TransactionReceipt, H2048, H256};
+use crate::Error;
+use ethereum_types::BloomInput;
+use futures::{Async, Future, Poll};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{self, json};
+use std::fmt;
+use std::ops::Deref;
+
+/// Fields of `Log`, aliased to their JSON-RPC names.
+const LOG_FIELDS: &str = "logIndex: index address: account { address } topics data \
+                          transactionHash: transaction { hash } transactionIndex: transaction { index } \
+                          blockHash: transaction { block { hash } } blockNumber: transaction { block { number } }";
+
+/// Fields of `Transaction`, aliased to their JSON-RPC names.
+const TRANSACTION_FIELDS: &str = "hash nonce blockHash: block { hash } blockNumber: block { number } \
+                                  transactionIndex: index from { address } to { address } value gasPrice gas \
+                                  input: inputData type accessList { address storageKeys } \
+                                  maxFeePerGas maxPriorityFeePerGas";
+
+/// Fields of `TransactionReceipt` (except for `logsBloom`), aliased to their JSON-RPC names.
+const RECEIPT_FIELDS: &str = "transactionHash: hash transactionIndex: index \
+                              blockHash: block { hash } blockNumber: block { number } \
+                              cumulativeGasUsed gasUsed contractAddress: createdContract { address } \
+                              status type effectiveGasPrice";
+
+/// Fields of `Block` (except for `transactions`), aliased to their JSON-RPC names.
+const BLOCK_FIELDS: &str = "hash parentHash: parent { hash } sha3Uncles: ommerHash miner { address } \
+                            stateRoot transactionsRoot receiptsRoot number gasUsed gasLimit extraData logsBloom \
+                            timestamp difficulty totalDifficulty uncles: ommers { hash } mixHash nonce baseFeePerGas";
+
+/// GraphQL client of the EIP-1767 endpoint (e.g. `geth --graphql`).
+///
+/// Fetches blocks along with their transactions, receipts and logs in a single round trip,
+/// returning the same types as the JSON-RPC API.
+#[derive(Debug, Clone)]
+pub struct GraphQl {
+    http: Http,
+    url: hyper::Uri,
+}
+
+impl GraphQl {
+    /// Create new GraphQL client with given URL (e.g. `http://localhost:8545/graphql`)
+    /// and spawn an event loop in a separate thread.
+    /// NOTE: Dropping event loop handle will stop the transport layer!
+    pub fn new(url: &str) -> Result<(EventLoopHandle, Self)> {
+        let (eloop, http) = Http::new(url)?;
+        Ok((eloop, Self::with_http(http, url)?))
+    }
+
+    /// Create new GraphQL client sending queries to given URL through an existing `Http` transport.
+    pub fn with_http(http: Http, url: &str) -> Result<Self> {
+        Ok(GraphQl {
+            http,
+            url: url.parse()?,
+        })
+    }
+
+    /// Sends a raw GraphQL query, returns its `data`.
+    pub fn query<T>(&self, query: &str, variables: rpc::Value) -> GraphQlFuture<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.send(query, variables, |data| {
+            serde_json::from_value(data).map_err(Into::into)
+        })
+    }
+
+    /// Get block details with transaction hashes.
+    pub fn block(&self, block: BlockId) -> GraphQlFuture<Option<Block<H256>>> {
+        let query = block_selector(block)
+            .map(|block| format!("{{ {} {{ {} transactions {{ hash }} }} }}", block, BLOCK_FIELDS));
+        self.send_typed(query, |data| decode_block(field(data, "block")))
+    }
+
+    /// Get block details with full transaction objects.
+    pub fn block_with_txs(&self, block: BlockId) -> GraphQlFuture<Option<Block<Transaction>>> {
+        let query = block_selector(block).map(|block| {
+            format!(
+                "{{ {} {{ {} transactions {{ {} }} }} }}",
+                block, BLOCK_FIELDS, TRANSACTION_FIELDS
+            )
+        });
+        self.send_typed(query, |data| decode_block(field(data, "block")))
+    }
+
+    /// Get receipts of all transactions of a block.
+    pub fn block_receipts(&self, block: BlockId) -> GraphQlFuture<Option<Vec<TransactionReceipt>>> {
+        let query = block_selector(block).map(|block| {
+            format!(
+                "{{ {} {{ transactions {{ {} logs {{ {} }} }} }} }}",
+                block, RECEIPT_FIELDS, LOG_FIELDS
+            )
+        });
+        self.send_typed(query, |data| match field(field(data, "block"), "transactions") {
+            rpc::Value::Array(receipts) => receipts
+                .into_iter()
+                .map(|receipt| decode_receipt(receipt)?.ok_or_else(|| Error::GraphQl("Missing receipt".into())))
+                .collect::<Result<Vec<_>>>()
+                .map(Some),
+            _ => Ok(None),
+        })
+    }
+
+    /// Get transaction by hash.
+    pub fn transaction(&self, hash: H256) -> GraphQlFuture<Option<Transaction>> {
+        let query = format!("{{ transaction(hash: \"{:?}\") {{ {} }} }}", hash, TRANSACTION_FIELDS);
+        self.send_typed(Ok(query), |data| decode(field(data, "transaction")))
+    }
+
+    /// Get transaction receipt by transaction hash.
+    pub fn transaction_receipt(&self, hash: H256) -> GraphQlFuture<Option<TransactionReceipt>> {
+        let query = format!(
+            "{{ transaction(hash: \"{:?}\") {{ {} logs {{ {} }} }} }}",
+            hash, RECEIPT_FIELDS, LOG_FIELDS
+        );
+        self.send_typed(Ok(query), |data| decode_receipt(field(data, "transaction")))
+    }
+
+    /// Get receipts of given transactions in a single query.
+    pub fn receipts(&self, hashes: Vec<H256>) -> GraphQlFuture<Vec<Option<TransactionReceipt>>> {
+        let len = hashes.len();
+        let transactions = hashes
+            .into_iter()
+            .enumerate()
+            .map(|(idx, hash)| {
+                format!(
+                    "r{}: transaction(hash: \"{:?}\") {{ {} logs {{ {} }} }}",
+                    idx, hash, RECEIPT_FIELDS, LOG_FIELDS
+                )
+            })
+            .collect::<Vec<_>>();
+        let query = format!("{{ {} }}", transactions.join(" "));
+        self.send_typed(Ok(query), move |mut data| {
+            (0..len)
+                .map(|idx| decode_receipt(data[format!("r{}", idx)].take()))
+                .collect()
+        })
+    }
+
+    /// Get logs matching given filter.
+    ///
+    /// Pending blocks are not supported (`latest` is used instead) and the limit is ignored.
+    pub fn logs(&self, filter: Filter) -> GraphQlFuture<Vec<Log>> {
+        let query = format!("{{ logs(filter: {}) {{ {} }} }}", filter_criteria(&filter), LOG_FIELDS);
+        self.send_typed(Ok(query), |data| decode(field(data, "logs")))
+    }
+
+    fn send_typed<T, F>(&self, query: Result<String>, decode: F) -> GraphQlFuture<T>
+    where
+        F: Fn(rpc::Value) -> Result<T> + Send + 'static,
+    {
+        match query {
+            Ok(query) => self.send(&query, rpc::Value::Null, decode),
+            Err(err) => GraphQlFuture {
+                inner: Err(Some(err)),
+                decode: Box::new(decode),
+            },
+        }
+    }
+
+    fn send<T, F>(&self, query: &str, variables: rpc::Value, decode: F) -> GraphQlFuture<T>
+    where
+        F: Fn(rpc::Value) -> Result<T> + Send + 'static,
+    {
+        let body = helpers::to_string(&json!({ "query": query, "variables": variables }));
+        GraphQlFuture {
+            inner: Ok(self.http.post(self.url.clone(), body, response)),
+            decode: Box::new(decode),
+        }
+    }
+}
+
+/// A future resolving to the result of a GraphQL query.
+pub struct GraphQlFuture<T> {
+    inner: ::std::result::Result<FetchTask<fn((Vec<rpc::Id>, hyper::Chunk)) -> Result<rpc::Value>>, Option<Error>>,
+    decode: Box<dyn Fn(rpc::Value) -> Result<T> + Send>,
+}
+
+impl<T> fmt::Debug for GraphQlFuture<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GraphQlFuture").finish()
+    }
+}
+
+impl<T> Future for GraphQlFuture<T> {
+    type Item = T;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let data = match self.inner {
+            Ok(ref mut inner) => try_ready!(inner.poll().map_err(|err| match err {
+                // The endpoint reports invalid queries with `400 Bad Request`.
+                Error::HttpStatus(_, ref body) => match response((vec![], body.as_bytes())) {
+                    Err(err @ Error::GraphQl(_)) => err,
+                    _ => err.clone(),
+                },
+                err => err,
+            })),
+            Err(ref mut err) => return Err(err.take().expect("future polled after error; qed")),
+        };
+        (self.decode)(data).map(Async::Ready)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlResponse {
+    #[serde(default)]
+    data: rpc::Value,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+/// Parse GraphQL response into its `data`.
+fn response<T: Deref<Target = [u8]>>((_, response): (Vec<rpc::Id>, T)) -> Result<rpc::Value> {
+    let response: GraphQlResponse =
+        serde_json::from_slice(&*response).map_err(|e| Error::InvalidResponse(format!("{:?}", e)))?;
+
+    if response.errors.is_empty() {
+        Ok(response.data)
+    } else {
+        let messages = response
+            .errors
+            .into_iter()
+            .map(|error| error.message)
+            .collect::<Vec<_>>();
+        Err(Error::GraphQl(messages.join("; ")))
+    }
+}
+
+fn field(mut value: rpc::Value, name: &str) -> rpc::Value {
+    value[name].take()
+}
+
+fn block_selector(block: BlockId) -> Result<String> {
+    match block {
+        BlockId::Hash(hash) => Ok(format!("block(hash: \"{:?}\")", hash)),
+        BlockId::Number(BlockNumber::Number(number)) => Ok(format!("block(number: {})", number.as_u64())),
+        BlockId::Number(BlockNumber::Latest) => Ok("block".into()),
+        BlockId::Number(BlockNumber::Earliest) => Ok("block(number: 0)".into()),
+        BlockId::Number(BlockNumber::Pending) => Err(Error::GraphQl("Pending block is not supported".into())),
+    }
+}
+
+/// Converts the JSON-RPC filter into GraphQL `FilterCriteria`.
+fn filter_criteria(filter: &Filter) -> String {
+    let filter = helpers::serialize(filter);
+    let mut criteria = vec![];
+
+    for &name in &["fromBlock", "toBlock"] {
+        match filter[name].as_str() {
+            Some("earliest") => criteria.push(format!("{}: 0", name)),
+            Some(number) if number.starts_with("0x") => {
+                if let Ok(number) = u64::from_str_radix(&number[2..], 16) {
+                    criteria.push(format!("{}: {}", name, number));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let list = |value: &rpc::Value| match *value {
+        rpc::Value::Array(_) => value.clone(),
+        rpc::Value::Null => json!([]),
+        _ => json!([value]),
+    };
+    if !filter["address"].is_null() {
+        criteria.push(format!("addresses: {}", list(&filter["address"])));
+    }
+    if let Some(topics) = filter["topics"].as_array() {
+        let topics = topics.iter().map(list).collect::<Vec<_>>();
+        criteria.push(format!("topics: {}", rpc::Value::Array(topics)));
+    }
+
+    format!("{{ {} }}", criteria.join(", "))
+}
+
+/// Converts GraphQL values into their JSON-RPC representation.
+///
+/// Objects with a single field (e.g. `miner { address }`) are replaced with the value of the field
+/// and numbers (`Long` and `Int` scalars) with hex quantities.
+fn normalize(value: rpc::Value) -> rpc::Value {
+    match value {
+        rpc::Value::Object(object) => {
+            if object.len() == 1 {
+                return normalize(object.into_iter().next().map(|(_, value)| value).unwrap_or_default());
+            }
+            rpc::Value::Object(object.into_iter().map(|(key, value)| (key, normalize(value))).collect())
+        }
+        rpc::Value::Array(values) => rpc::Value::Array(values.into_iter().map(normalize).collect()),
+        rpc::Value::Number(number) => match number.as_u64() {
+            Some(number) => rpc::Value::String(format!("0x{:x}", number)),
+            None => rpc::Value::Number(number),
+        },
+        value => value,
+    }
+}
+
+fn decode<T: DeserializeOwned>(value: rpc::Value) -> Result<T> {
+    serde_json::from_value(normalize(value)).map_err(Into::into)
+}
+
+fn decode_block<T: DeserializeOwned>(value: rpc::Value) -> Result<Option<Block<T>>> {
+    let mut value = normalize(value);
+    if let Some(block) = value.as_object_mut() {
+        // The genesis block has no parent.
+        if block.get("parentHash").map_or(false, rpc::Value::is_null) {
+            block.insert("parentHash".into(), helpers::serialize(&H256::zero()));
+        }
+    }
+    serde_json::from_value(value).map_err(Into::into)
+}
+
+fn decode_receipt(value: rpc::Value) -> Result<Option<TransactionReceipt>> {
+    let mut value = normalize(value);
+    if let Some(receipt) = value.as_object_mut() {
+        // Pending transactions don't have a receipt yet.
+        if receipt.get("blockHash").map_or(false, rpc::Value::is_null) {
+            return Ok(None);
+        }
+        // Not part of the schema, computed from the logs below.
+        receipt.insert("logsBloom".into(), helpers::serialize(&H2048::zero()));
+    }
+
+    let mut receipt: Option<TransactionReceipt> = serde_json::from_value(value)?;
+    if let Some(ref mut receipt) = receipt {
+        receipt.logs_bloom = logs_bloom(&receipt.logs);
+    }
+    Ok(receipt)
+}
+
+fn logs_bloom(logs: &[Log]) -> H2048 {
+    logs.iter().fold(H2048::zero(), |mut bloom, log| {
+        bloom.accrue(BloomInput::Raw(log.address.as_bytes()));
+        for topic in &log.topics {
+            bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+        }
+        bloom
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphQl;
+    use crate::transports::http::tests::serve_once;
+    use crate::types::{BlockId, FilterBuilder, Log, H160, H256};
+    use crate::Error;
+    use futures::Future;
+    use serde_json::json;
+
+    #[test]
+    fn should_fetch_block() {
+        // given
+        let response = json!({ "data": { "block": {
+            "hash": format!("{:?}", H256::from_low_u64_be(2)),
+            "parentHash": { "hash": format!("{:?}", H256::from_low_u64_be(1)) },
+            "sha3Uncles": format!("{:?}", H256::zero()),
+            "miner": { "address": format!("{:?}", H160::from_low_u64_be(5)) },
+            "stateRoot": format!("{:?}", H256::zero()),
+            "transactionsRoot": format!("{:?}", H256::zero()),
+            "receiptsRoot": format!("{:?}", H256::zero()),
+            "number": 2,
+            "gasUsed": "0x5208",
+            "gasLimit": 30000000,
+            "extraData": "0x",
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "timestamp": "0x60",
+            "difficulty": "0x0",
+            "totalDifficulty": "0x0",
+            "uncles": [],
+            "mixHash": format!("{:?}", H256::zero()),
+            "nonce": "0x0000000000000000",
+            "baseFeePerGas": "0x7",
+            "transactions": [{ "hash": format!("{:?}", H256::from_low_u64_be(3)) }]
+        }}});
+        let (url, request) = serve_once("200 OK", "", response.to_string().into_bytes());
+        let (_eloop, graphql) = GraphQl::new(&url).unwrap();
+
+        // when
+        let block = graphql.block(BlockId::Number(2.into())).wait().unwrap().unwrap();
+
+        // then
+        assert!(request.recv().unwrap().contains("block(number: 2)"));
+        assert_eq!(block.number, Some(2.into()));
+        assert_eq!(block.parent_hash, H256::from_low_u64_be(1));
+        assert_eq!(block.author, H160::from_low_u64_be(5));
+        assert_eq!(block.gas_limit, 30_000_000.into());
+        assert_eq!(block.base_fee_per_gas, Some(7.into()));
+        assert_eq!(block.transactions, vec![H256::from_low_u64_be(3)]);
+    }
+
+    #[test]
+    fn should_fetch_receipts_in_single_query() {
+        // given
+        let address = H160::from_low_u64_be(9);
+        let topic = H256::from_low_u64_be(10);
+        let response = json!({ "data": {
+            "r0": {
+                "transactionHash": format!("{:?}", H256::from_low_u64_be(3)),
+                "transactionIndex": 0,
+                "blockHash": { "hash": format!("{:?}", H256::from_low_u64_be(2)) },
+                "blockNumber": { "number": 2 },
+                "cumulativeGasUsed": 21000,
+                "gasUsed": 21000,
+                "contractAddress": null,
+                "status": 1,
+                "type": 2,
+                "effectiveGasPrice": "0x8",
+                "logs": [{
+                    "logIndex": 0,
+                    "address": { "address": format!("{:?}", address) },
+                    "topics": [format!("{:?}", topic)],
+                    "data": "0x",
+                    "transactionHash": { "hash": format!("{:?}", H256::from_low_u64_be(3)) },
+                    "transactionIndex": { "index": 0 },
+                    "blockHash": { "block": { "hash": format!("{:?}", H256::from_low_u64_be(2)) } },
+                    "blockNumber": { "block": { "number": 2 } }
+                }]
+            },
+            "r1": null
+        }});
+        let (url, request) = serve_once("200 OK", "", response.to_string().into_bytes());
+        let (_eloop, graphql) = GraphQl::new(&url).unwrap();
+
+        // when
+        let receipts = graphql
+            .receipts(vec![H256::from_low_u64_be(3), H256::from_low_u64_be(4)])
+            .wait()
+            .unwrap();
+
+        // then
+        let request = request.recv().unwrap();
+        assert!(request.contains(&format!(
+            "r1: transaction(hash: \\\"{:?}\\\")",
+            H256::from_low_u64_be(4)
+        )));
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[1], None);
+        let receipt = receipts[0].clone().unwrap();
+        assert_eq!(receipt.status, Some(1.into()));
+        assert_eq!(receipt.block_number, Some(2.into()));
+        assert_eq!(receipt.effective_gas_price, Some(8.into()));
+        let log: &Log = &receipt.logs[0];
+        assert_eq!((log.address, log.topics.clone()), (address, vec![topic]));
+        assert!(receipt.logs_bloom.as_bytes().iter().any(|byte| *byte != 0));
+    }
+
+    #[test]
+    fn should_not_return_receipts_of_pending_transactions() {
+        // given
+        let response = json!({ "data": { "transaction": {
+            "transactionHash": format!("{:?}", H256::from_low_u64_be(3)),
+            "transactionIndex": null,
+            "blockHash": null,
+            "blockNumber": null,
+            "cumulativeGasUsed": null,
+            "gasUsed": null,
+            "contractAddress": null,
+            "status": null,
+            "type": 2,
+            "effectiveGasPrice": null,
+            "logs": null
+        }}});
+        let (url, _request) = serve_once("200 OK", "", response.to_string().into_bytes());
+        let (_eloop, graphql) = GraphQl::new(&url).unwrap();
+
+        // when
+        let receipt = graphql.transaction_receipt(H256::from_low_u64_be(3)).wait();
+
+        // then
+        assert_eq!(receipt, Ok(None));
+    }
+
+    #[test]
+    fn should_report_query_errors() {
+        // given
+        let response = json!({ "errors": [{ "message": "Cannot query field \"type\" on type \"Transaction\"." }] });
+        let (url, request) = serve_once("400 Bad Request", "", response.to_string().into_bytes());
+        let (_eloop, graphql) = GraphQl::new(&url).unwrap();
+        let filter = FilterBuilder::default()
+            .from_block(1.into())
+            .address(vec![H160::from_low_u64_be(9)])
+            .topics(None, Some(vec![H256::from_low_u64_be(10)]), None, None)
+            .build();
+
+        // when
+        let result = graphql.logs(filter).wait();
+
+        // then
+        assert!(request
+            .recv()
+            .unwrap()
+            .contains("logs(filter: { fromBlock: 1, addresses: [\\\"0x0000000000000000000000000000000000000009\\\"], topics: [[],[\\\"0x000000000000000000000000000000000000000000000000000000000000000a\\\"]] })"));
+        assert_eq!(
+            result,
+            Err(Error::GraphQl(
+                "Cannot query field \"type\" on type \"Transaction\".".into()
+            ))
+        );
+    }
+}
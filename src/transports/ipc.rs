@@ -1,5 +1,9 @@
-//! IPC Transport for *nix
+//! IPC Transport for Unix Domain Sockets and Windows Named Pipes
 
+#[cfg(windows)]
+extern crate mio_named_pipes;
+#[cfg(windows)]
+extern crate tokio_named_pipes;
 #[cfg(unix)]
 extern crate tokio_uds;
 
@@ -7,44 +11,48 @@ use std::collections::BTreeMap;
 use std::io::{self, Read, Write};
 use std::path::Path;
 use std::sync::{atomic, Arc};
+use std::{mem, time};
 
+#[cfg(windows)]
+use self::tokio_named_pipes::NamedPipe;
 #[cfg(unix)]
 use self::tokio_uds::UnixStream;
 
 use crate::api::SubscriptionId;
 use crate::helpers;
 use crate::rpc;
+use crate::transports::framer::JsonFramer;
 use crate::transports::shared::{EventLoopHandle, Response};
 use crate::transports::tokio_core::reactor;
-use crate::transports::tokio_io::io::{ReadHalf, WriteHalf};
-use crate::transports::tokio_io::AsyncRead;
 use crate::transports::Result;
 use crate::{BatchTransport, DuplexTransport, Error, RequestId, Transport};
 use futures::sync::{mpsc, oneshot};
-use futures::{self, Future, Stream};
+use futures::{self, Async, Future, Stream};
 use parking_lot::Mutex;
 
-macro_rules! try_nb {
-    ($e:expr) => {
-        match $e {
-            Ok(t) => t,
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(futures::Async::NotReady),
-            Err(e) => {
-                log::warn!("Unexpected IO error: {:?}", e);
-                return Err(());
-            }
-        }
-    };
-}
+/// Delay between attempts to re-establish a closed connection.
+const RECONNECT_DELAY: time::Duration = time::Duration::from_secs(1);
+/// Number of failed reconnection attempts after which queued requests fail.
+const MAX_RECONNECT_ATTEMPTS: usize = 5;
+const READ_BUFFER_SIZE: usize = 4096;
 
-type Pending = oneshot::Sender<Result<Vec<Result<rpc::Value>>>>;
+/// Ids of the requests and the response channel.
+type Pending = (Vec<rpc::Id>, oneshot::Sender<Result<Vec<Result<rpc::Value>>>>);
 
-type Subscription = mpsc::UnboundedSender<rpc::Value>;
+type Subscription = mpsc::UnboundedSender<Result<rpc::Value>>;
+
+/// Opens a new connection to the socket.
+type Connect<S> = Box<dyn Fn(&reactor::Handle) -> io::Result<S>>;
 
 /// A future representing pending IPC request, resolves to a response.
 pub type IpcTask<F> = Response<F, Vec<Result<rpc::Value>>>;
 
-/// Unix Domain Sockets (IPC) transport
+/// IPC transport (Unix Domain Sockets or Windows Named Pipes)
+///
+/// The connection is re-established when the socket gets closed. Requests in flight fail
+/// and subscription streams end with an `Error::Transport` (the node forgets them with the connection).
+/// Requests sent in the meantime are written once connected again, or fail if the connection
+/// can't be re-established within a few attempts.
 #[derive(Debug, Clone)]
 pub struct Ipc {
     id: Arc<atomic::AtomicUsize>,
@@ -57,7 +65,7 @@ impl Ipc {
     /// Create new IPC transport with separate event loop.
     /// NOTE: Dropping event loop handle will stop the transport layer!
     ///
-    /// IPC is only available on Unix. On other systems, this always returns an error.
+    /// IPC is only available on Unix and Windows. On other systems, this always returns an error.
     pub fn new<P>(path: P) -> Result<(EventLoopHandle, Self)>
     where
         P: AsRef<Path>,
@@ -68,63 +76,70 @@ impl Ipc {
 
     /// Create new IPC transport within existing Event Loop.
     ///
-    /// IPC is only available on Unix. On other systems, this always returns an error.
-    #[cfg(unix)]
+    /// `path` is a Unix Domain Socket path on Unix and a Named Pipe path (e.g. `\\.\pipe\geth.ipc`) on Windows.
+    #[cfg(any(unix, windows))]
     pub fn with_event_loop<P>(path: P, handle: &reactor::Handle) -> Result<Self>
     where
         P: AsRef<Path>,
     {
-        log::trace!("Connecting to: {:?}", path.as_ref());
-        let stream = UnixStream::connect(path, handle)?;
-        Self::with_stream(stream, handle)
+        let path = path.as_ref().to_owned();
+        log::trace!("Connecting to: {:?}", path);
+        let stream = connect(&path, handle)?;
+        Ok(Self::with_stream(
+            stream,
+            Some(Box::new(move |handle: &reactor::Handle| connect(&path, handle))),
+            handle,
+        ))
+    }
+
+    /// Create new IPC transport within existing Event Loop.
+    ///
+    /// IPC is only available on Unix and Windows. On other systems, this always returns an error.
+    #[cfg(not(any(unix, windows)))]
+    pub fn with_event_loop<P>(_path: P, _handle: &reactor::Handle) -> Result<Self> {
+        return Err(Error::Transport("IPC transport is only supported on Unix and Windows".into()).into());
     }
 
-    /// Creates new IPC transport from existing `UnixStream` and `Handle`
-    #[cfg(unix)]
-    fn with_stream(stream: UnixStream, handle: &reactor::Handle) -> Result<Self> {
-        let (read, write) = stream.split();
+    /// Creates new IPC transport from existing stream, `connect` is used to re-establish the connection.
+    fn with_stream<S>(stream: S, connect: Option<Connect<S>>, handle: &reactor::Handle) -> Self
+    where
+        S: Read + Write + 'static,
+    {
         let (write_sender, write_receiver) = mpsc::unbounded();
         let pending: Arc<Mutex<BTreeMap<RequestId, Pending>>> = Default::default();
         let subscriptions: Arc<Mutex<BTreeMap<SubscriptionId, Subscription>>> = Default::default();
 
-        let r = ReadStream {
-            read,
+        handle.spawn(Connection {
+            stream: Some(stream),
+            reconnect: None,
+            connect,
+            handle: handle.clone(),
+            incoming: write_receiver,
+            incoming_closed: false,
+            attempts: 0,
+            write_buffer: vec![],
+            framer: JsonFramer::new(),
             pending: pending.clone(),
             subscriptions: subscriptions.clone(),
-            buffer: vec![],
-            current_pos: 0,
-        };
-
-        let w = WriteStream {
-            write,
-            incoming: write_receiver,
-            state: WriteState::WaitingForRequest,
-        };
-
-        handle.spawn(r);
-        handle.spawn(w);
+        });
 
-        Ok(Ipc {
+        Ipc {
             id: Arc::new(atomic::AtomicUsize::new(1)),
             write_sender,
             pending,
             subscriptions,
-        })
-    }
-
-    #[cfg(not(unix))]
-    pub fn with_event_loop<P>(_path: P, _handle: &reactor::Handle) -> Result<Self> {
-        return Err(Error::Transport("IPC transport is only supported on Unix".into()).into());
+        }
     }
 
     fn send_request<F, O>(&self, id: RequestId, request: rpc::Request, extract: F) -> IpcTask<F>
     where
         F: Fn(Vec<Result<rpc::Value>>) -> O,
     {
+        let ids = helpers::request_ids(&request);
         let request = helpers::to_string(&request);
         log::debug!("[{}] Calling: {}", id, request);
         let (tx, rx) = futures::oneshot();
-        self.pending.lock().insert(id, tx);
+        self.pending.lock().insert(id, (ids, tx));
 
         let result = self
             .write_sender
@@ -179,7 +194,10 @@ impl DuplexTransport for Ipc {
         if self.subscriptions.lock().insert(id.clone(), tx).is_some() {
             log::warn!("Replacing already-registered subscription with id {:?}", id)
         }
-        Box::new(rx.map_err(|()| Error::Transport("No data available".into())))
+        Box::new(rx.then(|notification| match notification {
+            Ok(notification) => notification,
+            Err(()) => Err(Error::Transport("No data available".into())),
+        }))
     }
 
     fn unsubscribe(&self, id: &SubscriptionId) {
@@ -187,188 +205,242 @@ impl DuplexTransport for Ipc {
     }
 }
 
-enum WriteState {
-    WaitingForRequest,
-    Writing { buffer: Vec<u8>, current_pos: usize },
+#[cfg(unix)]
+fn connect(path: &Path, handle: &reactor::Handle) -> io::Result<UnixStream> {
+    UnixStream::connect(path, handle)
 }
 
-/// Writing part of the IPC transport
-/// Awaits new requests using `mpsc::UnboundedReceiver` and writes them to the socket.
-#[cfg(unix)]
-struct WriteStream {
-    write: WriteHalf<UnixStream>,
+#[cfg(windows)]
+fn connect(path: &Path, handle: &reactor::Handle) -> io::Result<NamedPipe> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::{FromRawHandle, IntoRawHandle};
+
+    const FILE_FLAG_OVERLAPPED: u32 = 0x4000_0000;
+
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(FILE_FLAG_OVERLAPPED)
+        .open(path)?;
+    // Safe, since the handle is owned by the pipe from now on.
+    let pipe = unsafe { mio_named_pipes::NamedPipe::from_raw_handle(file.into_raw_handle()) };
+    NamedPipe::from_pipe(pipe, handle.new_tokio_handle())
+}
+
+/// Connection task of the IPC transport.
+/// Writes requests to the socket, reads and dispatches responses and notifications
+/// and re-establishes the connection when it gets closed.
+struct Connection<S> {
+    stream: Option<S>,
+    reconnect: Option<reactor::Timeout>,
+    connect: Option<Connect<S>>,
+    handle: reactor::Handle,
     incoming: mpsc::UnboundedReceiver<Vec<u8>>,
-    state: WriteState,
+    incoming_closed: bool,
+    /// Failed reconnection attempts since the requests were last failed.
+    attempts: usize,
+    write_buffer: Vec<u8>,
+    framer: JsonFramer,
+    pending: Arc<Mutex<BTreeMap<RequestId, Pending>>>,
+    subscriptions: Arc<Mutex<BTreeMap<SubscriptionId, Subscription>>>,
 }
 
-#[cfg(unix)]
-impl Future for WriteStream {
+impl<S: Read + Write> Future for Connection<S> {
     type Item = ();
     type Error = ();
 
     fn poll(&mut self) -> futures::Poll<Self::Item, Self::Error> {
         loop {
-            self.state = match self.state {
-                WriteState::WaitingForRequest => {
-                    // Ask for more to write
-                    let to_send = try_ready!(self.incoming.poll());
-                    if let Some(to_send) = to_send {
-                        log::trace!("Got new message to write: {:?}", String::from_utf8_lossy(&to_send));
-                        WriteState::Writing {
-                            buffer: to_send,
-                            current_pos: 0,
-                        }
-                    } else {
-                        return Ok(futures::Async::NotReady);
-                    }
+            if let Some(mut timeout) = self.reconnect.take() {
+                // Keep queueing requests, so that the task ends once the transport is dropped.
+                self.poll_incoming();
+                if self.incoming_closed {
+                    self.fail_pending("IPC connection closed");
+                    return Ok(Async::Ready(()));
                 }
-                WriteState::Writing {
-                    ref buffer,
-                    ref mut current_pos,
-                } => {
-                    // Write everything in the buffer
-                    while *current_pos < buffer.len() {
-                        let n = try_nb!(self.write.write(&buffer[*current_pos..]));
-                        *current_pos += n;
-                        if n == 0 {
-                            log::warn!("IO Error: Zero write.");
-                            return Err(()); // zero write?
-                        }
+                match timeout.poll() {
+                    Ok(Async::NotReady) => {
+                        self.reconnect = Some(timeout);
+                        return Ok(Async::NotReady);
+                    }
+                    Ok(Async::Ready(())) => self.connect()?,
+                    Err(err) => {
+                        log::error!("IPC reconnection timer failed: {:?}", err);
+                        return Err(());
                     }
+                }
+                continue;
+            }
 
-                    WriteState::WaitingForRequest
+            let mut stream = self
+                .stream
+                .take()
+                .expect("Either stream or reconnect timeout is set; qed");
+            match self.poll_stream(&mut stream) {
+                Ok(Async::NotReady) => {
+                    self.stream = Some(stream);
+                    return Ok(Async::NotReady);
                 }
-            };
+                Ok(Async::Ready(())) => return Ok(Async::Ready(())),
+                Err(err) => self.disconnected(err)?,
+            }
         }
     }
 }
 
-/// Reading part of the IPC transport.
-/// Reads data on the socket and tries to dispatch it to awaiting requests.
-#[cfg(unix)]
-struct ReadStream {
-    read: ReadHalf<UnixStream>,
-    pending: Arc<Mutex<BTreeMap<RequestId, Pending>>>,
-    subscriptions: Arc<Mutex<BTreeMap<SubscriptionId, Subscription>>>,
-    buffer: Vec<u8>,
-    current_pos: usize,
-}
-
-#[cfg(unix)]
-impl Future for ReadStream {
-    type Item = ();
-    type Error = ();
+impl<S: Read + Write> Connection<S> {
+    /// Writes queued requests and dispatches received messages.
+    /// Resolves when the transport was dropped and there are no pending requests.
+    fn poll_stream(&mut self, stream: &mut S) -> futures::Poll<(), io::Error> {
+        self.poll_incoming();
+
+        while !self.write_buffer.is_empty() {
+            match stream.write(&self.write_buffer) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.write_buffer.drain(..written);
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            }
+        }
 
-    fn poll(&mut self) -> futures::Poll<Self::Item, Self::Error> {
-        const DEFAULT_BUF_SIZE: usize = 4096;
-        let mut new_write_size = 128;
+        let mut buffer = [0; READ_BUFFER_SIZE];
         loop {
-            if self.current_pos == self.buffer.len() {
-                if new_write_size < DEFAULT_BUF_SIZE {
-                    new_write_size *= 2;
+            match stream.read(&mut buffer) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(read) => {
+                    self.framer.extend(&buffer[..read]);
+                    while let Some(frame) = self.framer.next_frame() {
+                        self.dispatch(&frame);
+                    }
                 }
-                self.buffer.resize(self.current_pos + new_write_size, 0);
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
             }
+        }
+
+        if self.incoming_closed && self.pending.lock().is_empty() {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
 
-            let read = try_nb!(self.read.read(&mut self.buffer[self.current_pos..]));
-            if read == 0 {
-                return Ok(futures::Async::NotReady);
+    /// Moves requests sent through the transport to the write buffer.
+    fn poll_incoming(&mut self) {
+        while !self.incoming_closed {
+            match self.incoming.poll() {
+                Ok(Async::Ready(Some(request))) => {
+                    log::trace!("Got new message to write: {:?}", String::from_utf8_lossy(&request));
+                    self.write_buffer.extend(request);
+                }
+                Ok(Async::Ready(None)) => self.incoming_closed = true,
+                Ok(Async::NotReady) | Err(()) => break,
             }
+        }
+    }
 
-            let mut min = self.current_pos;
-            self.current_pos += read;
-            while let Some((response, len)) = Self::extract_response(&self.buffer[0..self.current_pos], min) {
-                // Respond
-                self.respond(response);
+    /// Fails all pending requests with given reason.
+    fn fail_pending(&mut self, reason: &str) {
+        self.write_buffer.clear();
+        for (_, (_, request)) in mem::replace(&mut *self.pending.lock(), Default::default()) {
+            let _ = request.send(Err(Error::Transport(reason.into())));
+        }
+    }
 
-                // copy rest of buffer to the beginning
-                for i in len..self.current_pos {
-                    self.buffer.swap(i, i - len);
-                }
+    /// Fails pending requests, ends subscriptions and schedules reconnection.
+    fn disconnected(&mut self, err: io::Error) -> std::result::Result<(), ()> {
+        log::warn!("IPC connection closed: {:?}", err);
+        if !self.framer.is_empty() {
+            log::warn!("Discarding partially received message.");
+        }
+        self.framer.clear();
+        self.fail_pending("IPC connection closed");
 
-                // truncate the buffer
-                let new_len = self.current_pos - len;
-                self.buffer.truncate(new_len + new_write_size);
+        for (_, subscription) in mem::replace(&mut *self.subscriptions.lock(), Default::default()) {
+            let _ = subscription.unbounded_send(Err(Error::Transport("IPC connection closed".into())));
+        }
 
-                // Set new positions
-                self.current_pos = new_len;
-                min = 0;
-            }
+        if self.connect.is_none() || self.incoming_closed {
+            return Err(());
         }
+        self.schedule_reconnect()
     }
-}
 
-enum Message {
-    Rpc(Vec<rpc::Output>),
-    Notification(rpc::Notification),
-}
+    fn schedule_reconnect(&mut self) -> std::result::Result<(), ()> {
+        let timeout = reactor::Timeout::new(RECONNECT_DELAY, &self.handle)
+            .map_err(|err| log::error!("Unable to schedule IPC reconnection: {:?}", err))?;
+        self.reconnect = Some(timeout);
+        Ok(())
+    }
 
-#[cfg(unix)]
-impl ReadStream {
-    fn respond(&self, response: Message) {
-        match response {
-            Message::Rpc(outputs) => {
-                let id = match outputs.get(0) {
-                    Some(&rpc::Output::Success(ref success)) => success.id.clone(),
-                    Some(&rpc::Output::Failure(ref failure)) => failure.id.clone(),
-                    None => rpc::Id::Num(0),
-                };
-
-                if let rpc::Id::Num(num) = id {
-                    if let Some(request) = self.pending.lock().remove(&(num as usize)) {
-                        log::trace!("Responding to (id: {:?}) with {:?}", num, outputs);
-                        if let Err(err) = request.send(helpers::to_results_from_outputs(outputs)) {
-                            log::warn!("Sending a response to deallocated channel: {:?}", err);
-                        }
-                    } else {
-                        log::warn!("Got response for unknown request (id: {:?})", num);
-                    }
-                } else {
-                    log::warn!("Got unsupported response (id: {:?})", id);
-                }
+    fn connect(&mut self) -> std::result::Result<(), ()> {
+        let connect = self.connect.as_ref().expect("Reconnecting only with a connector; qed");
+        match connect(&self.handle) {
+            Ok(stream) => {
+                log::debug!("IPC connection re-established");
+                self.stream = Some(stream);
+                self.attempts = 0;
+                Ok(())
             }
-            Message::Notification(notification) => {
-                if let rpc::Params::Map(params) = notification.params {
-                    let id = params.get("subscription");
-                    let result = params.get("result");
-
-                    if let (Some(&rpc::Value::String(ref id)), Some(result)) = (id, result) {
-                        let id: SubscriptionId = id.clone().into();
-                        if let Some(stream) = self.subscriptions.lock().get(&id) {
-                            if let Err(e) = stream.unbounded_send(result.clone()) {
-                                log::error!("Error sending notification (id: {:?}): {:?}", id, e);
-                            }
-                        } else {
-                            log::warn!("Got notification for unknown subscription (id: {:?})", id);
-                        }
-                    } else {
-                        log::error!("Got unsupported notification (id: {:?})", id);
-                    }
+            Err(err) => {
+                log::warn!("Unable to re-establish IPC connection: {:?}", err);
+                self.attempts += 1;
+                if self.attempts >= MAX_RECONNECT_ATTEMPTS {
+                    self.attempts = 0;
+                    self.fail_pending("Unable to re-establish IPC connection");
                 }
+                self.schedule_reconnect()
             }
         }
     }
 
-    fn extract_response(buf: &[u8], min: usize) -> Option<(Message, usize)> {
-        for pos in (min..buf.len()).rev() {
-            // Look for end character
-            if buf[pos] == b']' || buf[pos] == b'}' {
-                // Try to deserialize
-                let pos = pos + 1;
-                match helpers::to_response_from_slice(&buf[0..pos]) {
-                    Ok(rpc::Response::Single(output)) => return Some((Message::Rpc(vec![output]), pos)),
-                    Ok(rpc::Response::Batch(outputs)) => return Some((Message::Rpc(outputs), pos)),
-                    // just continue
-                    _ => {}
-                }
-                match helpers::to_notification_from_slice(&buf[0..pos]) {
-                    Ok(notification) => return Some((Message::Notification(notification), pos)),
-                    _ => {}
-                }
+    fn dispatch(&self, frame: &[u8]) {
+        if let Ok(response) = helpers::to_response_from_slice(frame) {
+            self.respond(match response {
+                rpc::Response::Single(output) => vec![output],
+                rpc::Response::Batch(outputs) => outputs,
+            });
+        } else if let Ok(notification) = helpers::to_notification_from_slice(frame) {
+            self.notify(notification);
+        } else {
+            log::warn!("Got invalid message: {}", String::from_utf8_lossy(frame));
+        }
+    }
+
+    fn respond(&self, outputs: Vec<rpc::Output>) {
+        let mut pending = self.pending.lock();
+        let id = helpers::pending_request(&*pending, |&(ref ids, _)| &ids[..], &outputs);
+
+        if let Some((ids, request)) = id.and_then(|id| pending.remove(&id)) {
+            log::trace!("Responding to (ids: {:?}) with {:?}", ids, outputs);
+            if let Err(err) = request.send(Ok(helpers::to_results_by_id(&ids, outputs))) {
+                log::warn!("Sending a response to deallocated channel: {:?}", err);
             }
+        } else {
+            log::warn!("Got response for unknown request: {:?}", outputs);
         }
+    }
+
+    fn notify(&self, notification: rpc::Notification) {
+        if let rpc::Params::Map(params) = notification.params {
+            let id = params.get("subscription");
+            let result = params.get("result");
 
-        None
+            if let (Some(&rpc::Value::String(ref id)), Some(result)) = (id, result) {
+                let id: SubscriptionId = id.clone().into();
+                if let Some(stream) = self.subscriptions.lock().get(&id) {
+                    if let Err(e) = stream.unbounded_send(Ok(result.clone())) {
+                        log::error!("Error sending notification (id: {:?}): {:?}", id, e);
+                    }
+                } else {
+                    log::warn!("Got notification for unknown subscription (id: {:?})", id);
+                }
+            } else {
+                log::error!("Got unsupported notification (id: {:?})", id);
+            }
+        }
     }
 }
 
@@ -379,9 +451,21 @@ mod tests {
 
     use super::Ipc;
     use crate::rpc;
-    use crate::Transport;
-    use futures::{self, Future};
+    use crate::{BatchTransport, DuplexTransport, Error, Transport};
+    use futures::{self, Future, Stream};
     use std::io::{self, Read, Write};
+    use std::thread;
+    use std::time::Duration;
+
+    macro_rules! try_nb {
+        ($e:expr) => {
+            match $e {
+                Ok(t) => t,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(futures::Async::NotReady),
+                Err(e) => panic!("Unexpected IO error: {:?}", e),
+            }
+        };
+    }
 
     #[test]
     fn should_send_a_request() {
@@ -389,7 +473,7 @@ mod tests {
         let mut eloop = tokio_core::reactor::Core::new().unwrap();
         let handle = eloop.handle();
         let (server, client) = tokio_uds::UnixStream::pair(&handle).unwrap();
-        let ipc = Ipc::with_stream(client, &handle).unwrap();
+        let ipc = Ipc::with_stream(client, None, &handle);
 
         eloop.remote().spawn(move |_| {
             struct Task {
@@ -434,7 +518,7 @@ mod tests {
         let mut eloop = tokio_core::reactor::Core::new().unwrap();
         let handle = eloop.handle();
         let (server, client) = tokio_uds::UnixStream::pair(&handle).unwrap();
-        let ipc = Ipc::with_stream(client, &handle).unwrap();
+        let ipc = Ipc::with_stream(client, None, &handle);
 
         eloop.remote().spawn(move |_| {
             struct Task {
@@ -473,4 +557,213 @@ mod tests {
             Ok((rpc::Value::String("x".into()), rpc::Value::String("x".into())))
         );
     }
+
+    #[test]
+    fn should_match_reordered_batch_responses() {
+        // given
+        let mut eloop = tokio_core::reactor::Core::new().unwrap();
+        let handle = eloop.handle();
+        let (server, client) = tokio_uds::UnixStream::pair(&handle).unwrap();
+        let ipc = Ipc::with_stream(client, None, &handle);
+
+        eloop.remote().spawn(move |_| {
+            struct Task {
+                server: tokio_uds::UnixStream,
+            }
+
+            impl Future for Task {
+                type Item = ();
+                type Error = ();
+                fn poll(&mut self) -> futures::Poll<(), ()> {
+                    let mut data = [0; 2048];
+                    // Read request
+                    assert!(try_nb!(self.server.read(&mut data)) > 0);
+
+                    // Write responses in reverse order, one of them with a string id
+                    let response = r#"[{"jsonrpc":"2.0","id":"2","result":"y"},{"jsonrpc":"2.0","id":1,"result":"x"}]"#;
+                    self.server.write_all(response.as_bytes()).unwrap();
+                    self.server.flush().unwrap();
+
+                    Ok(futures::Async::Ready(()))
+                }
+            }
+
+            Task { server }
+        });
+
+        // when
+        let requests = vec![ipc.prepare("eth_accounts", vec![]), ipc.prepare("eth_accounts", vec![])];
+        let res = ipc.send_batch(requests);
+
+        // then
+        assert_eq!(
+            eloop.run(res),
+            Ok(vec![
+                Ok(rpc::Value::String("x".into())),
+                Ok(rpc::Value::String("y".into()))
+            ])
+        );
+    }
+
+    #[test]
+    fn should_handle_fragmented_messages() {
+        // given
+        let mut eloop = tokio_core::reactor::Core::new().unwrap();
+        let handle = eloop.handle();
+        let (mut server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+        let client = tokio_uds::UnixStream::from_stream(client, &handle).unwrap();
+        let ipc = Ipc::with_stream(client, None, &handle);
+        let notifications = ipc.subscribe(&"0x1".into());
+
+        let server = thread::spawn(move || {
+            let mut data = [0; 2048];
+            let read = server.read(&mut data).unwrap();
+            assert_eq!(
+                &data[..read],
+                &br#"{"jsonrpc":"2.0","method":"eth_accounts","params":["1"],"id":1}"#[..]
+            );
+
+            let messages = concat!(
+                r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"subscription":"0x1","result":"}{"}}"#,
+                "\n",
+                r#"{"jsonrpc":"2.0","id":1,"result":"x\"]"}"#,
+            );
+            for chunk in messages.as_bytes().chunks(7) {
+                server.write_all(chunk).unwrap();
+                thread::sleep(Duration::from_millis(1));
+            }
+            server
+        });
+
+        // when
+        let res = ipc.execute("eth_accounts", vec![rpc::Value::String("1".into())]);
+
+        // then
+        assert_eq!(eloop.run(res), Ok(rpc::Value::String("x\"]".into())));
+        let (notification, _) = eloop.run(notifications.into_future()).map_err(|(e, _)| e).unwrap();
+        assert_eq!(notification, Some(rpc::Value::String("}{".into())));
+        drop(server.join().unwrap());
+    }
+
+    #[test]
+    fn should_fail_only_pending_request_with_error_without_id() {
+        // given
+        let mut eloop = tokio_core::reactor::Core::new().unwrap();
+        let handle = eloop.handle();
+        let (mut server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+        let client = tokio_uds::UnixStream::from_stream(client, &handle).unwrap();
+        let ipc = Ipc::with_stream(client, None, &handle);
+
+        let server = thread::spawn(move || {
+            let mut data = [0; 2048];
+            assert!(server.read(&mut data).unwrap() > 0);
+            server
+                .write_all(br#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"Parse error"}}"#)
+                .unwrap();
+            server
+        });
+
+        // when
+        let res = ipc.execute("eth_accounts", vec![]);
+
+        // then
+        assert_eq!(eloop.run(res), Err(Error::Rpc(rpc::Error::parse_error())));
+        drop(server.join().unwrap());
+    }
+
+    #[test]
+    fn should_not_attribute_error_without_id_to_one_of_many_pending_requests() {
+        // given
+        let mut eloop = tokio_core::reactor::Core::new().unwrap();
+        let handle = eloop.handle();
+        let (mut server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+        let client = tokio_uds::UnixStream::from_stream(client, &handle).unwrap();
+        let ipc = Ipc::with_stream(client, None, &handle);
+
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            let mut data = [0; 2048];
+            while !String::from_utf8_lossy(&requests).contains(r#""id":2"#) {
+                let read = server.read(&mut data).unwrap();
+                requests.extend_from_slice(&data[..read]);
+            }
+            server
+                .write_all(br#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"Parse error"}}"#)
+                .unwrap();
+            server
+                .write_all(br#"{"jsonrpc":"2.0","id":1,"result":"x"}{"jsonrpc":"2.0","id":2,"result":"y"}"#)
+                .unwrap();
+            server
+        });
+
+        // when
+        let res1 = ipc.execute("eth_accounts", vec![]);
+        let res2 = ipc.execute("eth_accounts", vec![]);
+
+        // then
+        assert_eq!(
+            eloop.run(res1.join(res2)),
+            Ok((rpc::Value::String("x".into()), rpc::Value::String("y".into())))
+        );
+        drop(server.join().unwrap());
+    }
+
+    #[test]
+    fn should_end_subscriptions_when_connection_is_closed() {
+        // given
+        let mut eloop = tokio_core::reactor::Core::new().unwrap();
+        let handle = eloop.handle();
+        let (server, client) = tokio_uds::UnixStream::pair(&handle).unwrap();
+        let ipc = Ipc::with_stream(client, None, &handle);
+        let notifications = ipc.subscribe(&"0x1".into());
+
+        // when
+        drop(server);
+        let result = eloop
+            .run(notifications.into_future())
+            .map(|(notification, _)| notification);
+
+        // then
+        assert_eq!(
+            result.map_err(|(err, _)| err),
+            Err(Error::Transport("IPC connection closed".into()))
+        );
+    }
+
+    #[test]
+    fn should_reconnect_when_connection_is_closed() {
+        // given
+        let mut eloop = tokio_core::reactor::Core::new().unwrap();
+        let path = std::env::temp_dir().join(format!("web3-ipc-reconnect-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let ipc = Ipc::with_event_loop(&path, &eloop.handle()).unwrap();
+
+        let server = thread::spawn(move || {
+            let mut data = [0; 2048];
+            // Close the first connection without responding
+            let (mut first, _) = listener.accept().unwrap();
+            assert!(first.read(&mut data).unwrap() > 0);
+            drop(first);
+
+            let (mut second, _) = listener.accept().unwrap();
+            let read = second.read(&mut data).unwrap();
+            assert_eq!(
+                &data[..read],
+                &br#"{"jsonrpc":"2.0","method":"eth_accounts","params":[],"id":2}"#[..]
+            );
+            second.write_all(br#"{"jsonrpc":"2.0","id":2,"result":"x"}"#).unwrap();
+            second
+        });
+
+        // when
+        let first = eloop.run(ipc.execute("eth_accounts", vec![]));
+        let second = eloop.run(ipc.execute("eth_accounts", vec![]));
+
+        // then
+        assert_eq!(first, Err(Error::Transport("IPC connection closed".into())));
+        assert_eq!(second, Ok(rpc::Value::String("x".into())));
+        drop(server.join().unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,523 @@
+//! Request/response interceptors
+
+use crate::rpc;
+use crate::transports::Result;
+use crate::{BatchTransport, Error, RequestId, Transport};
+use futures::{Async, Future, Poll};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Upper bounds (in seconds) of the latency histogram buckets.
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// A request passed to the underlying transport.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestInfo {
+    /// Request id
+    pub id: RequestId,
+    /// Called method (empty for invalid calls)
+    pub method: String,
+    /// Call parameters (only if any interceptor `needs_params`)
+    pub params: Option<rpc::Params>,
+    /// Size of the JSON-serialized call in bytes (only if any interceptor `needs_sizes`)
+    pub size: Option<usize>,
+    /// Whether the call was sent as part of a batch
+    pub batch: bool,
+}
+
+impl RequestInfo {
+    fn new(id: RequestId, request: &rpc::Call, batch: bool, needs: Needs) -> Self {
+        let (method, params) = match *request {
+            rpc::Call::MethodCall(rpc::MethodCall {
+                ref method, ref params, ..
+            })
+            | rpc::Call::Notification(rpc::Notification {
+                ref method, ref params, ..
+            }) => (method.clone(), Some(params)),
+            rpc::Call::Invalid { .. } => (String::new(), None),
+        };
+
+        RequestInfo {
+            id,
+            method,
+            params: if needs.params {
+                Some(params.cloned().unwrap_or(rpc::Params::None))
+            } else {
+                None
+            },
+            size: if needs.sizes { Some(json_size(request)) } else { None },
+            batch,
+        }
+    }
+}
+
+/// Outcome of a request.
+#[derive(Debug)]
+pub struct ResponseInfo<'a> {
+    /// Time elapsed since the request was sent (for batches: until the whole batch finished)
+    pub latency: Duration,
+    /// Size of the JSON-serialized result in bytes (`0` for errors, only if any interceptor `needs_sizes`)
+    pub size: Option<usize>,
+    /// Result of the call
+    pub result: &'a Result<rpc::Value>,
+}
+
+/// Observes requests sent through the `Middleware` transport.
+pub trait Interceptor: fmt::Debug + Send + Sync {
+    /// Whether `RequestInfo::params` should be filled in, which copies the parameters of every call.
+    fn needs_params(&self) -> bool {
+        false
+    }
+
+    /// Whether request and response sizes should be computed, which serializes every call and result.
+    fn needs_sizes(&self) -> bool {
+        false
+    }
+
+    /// Called before the request is sent.
+    fn on_request(&self, _request: &RequestInfo) {}
+
+    /// Called when the request finished.
+    fn on_response(&self, request: &RequestInfo, response: &ResponseInfo);
+}
+
+/// Transport notifying interceptors about all requests of the underlying transport.
+///
+/// Batches are reported call by call. With the `tracing` feature every request
+/// (or batch) is also wrapped in a `tracing` span.
+#[derive(Debug, Clone)]
+pub struct Middleware<T> {
+    transport: T,
+    interceptors: Arc<Vec<Arc<dyn Interceptor>>>,
+    needs: Needs,
+}
+
+/// Request details any of the interceptors needs.
+#[derive(Debug, Default, Clone, Copy)]
+struct Needs {
+    params: bool,
+    sizes: bool,
+}
+
+impl<T: Transport> Middleware<T> {
+    /// Creates new Middleware transport without any interceptors.
+    pub fn new(transport: T) -> Self {
+        Middleware {
+            transport,
+            interceptors: Default::default(),
+            needs: Default::default(),
+        }
+    }
+
+    /// Adds an interceptor, interceptors are called in the order they were added.
+    pub fn with<I: Interceptor + 'static>(mut self, interceptor: I) -> Self {
+        self.needs.params |= interceptor.needs_params();
+        self.needs.sizes |= interceptor.needs_sizes();
+        Arc::make_mut(&mut self.interceptors).push(Arc::new(interceptor));
+        self
+    }
+
+    /// Returns the underlying transport.
+    pub fn inner(&self) -> &T {
+        &self.transport
+    }
+
+    fn observe(&self, requests: Vec<RequestInfo>) -> Observer {
+        for request in &requests {
+            for interceptor in self.interceptors.iter() {
+                interceptor.on_request(request);
+            }
+        }
+
+        Observer {
+            #[cfg(feature = "tracing")]
+            span: match requests.as_slice() {
+                [request] if !request.batch => {
+                    tracing::debug_span!("rpc", method = %request.method, id = request.id)
+                }
+                requests => tracing::debug_span!("rpc_batch", size = requests.len()),
+            },
+            requests,
+            started: Instant::now(),
+            interceptors: self.interceptors.clone(),
+            sizes: self.needs.sizes,
+        }
+    }
+}
+
+impl<T: Transport> Transport for Middleware<T> {
+    type Out = MiddlewareFuture<T::Out>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        let observer = self.observe(vec![RequestInfo::new(id, &request, false, self.needs)]);
+        MiddlewareFuture {
+            future: observer.in_scope(|| self.transport.send(id, request)),
+            observer,
+        }
+    }
+}
+
+impl<T: BatchTransport> BatchTransport for Middleware<T> {
+    type Batch = MiddlewareBatch<T::Batch>;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let requests = requests.into_iter().collect::<Vec<_>>();
+        let observer = self.observe(
+            requests
+                .iter()
+                .map(|&(id, ref request)| RequestInfo::new(id, request, true, self.needs))
+                .collect(),
+        );
+        MiddlewareBatch {
+            future: observer.in_scope(|| self.transport.send_batch(requests)),
+            observer,
+        }
+    }
+}
+
+/// Pending requests with the interceptors to notify.
+#[derive(Debug)]
+struct Observer {
+    requests: Vec<RequestInfo>,
+    started: Instant,
+    interceptors: Arc<Vec<Arc<dyn Interceptor>>>,
+    sizes: bool,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl Observer {
+    fn in_scope<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        #[cfg(feature = "tracing")]
+        let _entered = self.span.enter();
+        f()
+    }
+
+    fn finish<'a, I>(&mut self, results: I)
+    where
+        I: IntoIterator<Item = &'a Result<rpc::Value>>,
+    {
+        let latency = self.started.elapsed();
+        for (request, result) in self.requests.drain(..).zip(results) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                method = %request.method,
+                latency_ms = latency.as_millis() as u64,
+                success = result.is_ok(),
+                "rpc response"
+            );
+            let response = ResponseInfo {
+                latency,
+                size: match *result {
+                    _ if !self.sizes => None,
+                    Ok(ref value) => Some(json_size(value)),
+                    Err(_) => Some(0),
+                },
+                result,
+            };
+            for interceptor in self.interceptors.iter() {
+                interceptor.on_response(&request, &response);
+            }
+        }
+    }
+}
+
+/// A future representing an observed request.
+#[derive(Debug)]
+pub struct MiddlewareFuture<F> {
+    future: F,
+    observer: Observer,
+}
+
+impl<F: Future<Item = rpc::Value, Error = Error>> Future for MiddlewareFuture<F> {
+    type Item = rpc::Value;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let future = &mut self.future;
+        let result = match self.observer.in_scope(|| future.poll()) {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(value)) => Ok(value),
+            Err(err) => Err(err),
+        };
+
+        self.observer.finish(Some(&result));
+        result.map(Async::Ready)
+    }
+}
+
+/// A future representing an observed batch request.
+#[derive(Debug)]
+pub struct MiddlewareBatch<F> {
+    future: F,
+    observer: Observer,
+}
+
+impl<F: Future<Item = Vec<Result<rpc::Value>>, Error = Error>> Future for MiddlewareBatch<F> {
+    type Item = Vec<Result<rpc::Value>>;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let future = &mut self.future;
+        match self.observer.in_scope(|| future.poll()) {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Ok(Async::Ready(results)) => {
+                self.observer.finish(&results);
+                Ok(Async::Ready(results))
+            }
+            Err(err) => {
+                let result = Err(err.clone());
+                let len = self.observer.requests.len();
+                self.observer.finish(vec![&result; len]);
+                Err(err)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MethodMetrics {
+    requests: u64,
+    errors: u64,
+    request_bytes: u64,
+    response_bytes: u64,
+    /// Number of requests per latency bucket (not cumulative), the last one is `+Inf`.
+    latency_buckets: Vec<u64>,
+    latency_sum: f64,
+}
+
+/// Interceptor collecting per-method request counters and latency histograms.
+///
+/// Clones share the collected metrics, keep one to render them.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    methods: Arc<Mutex<BTreeMap<String, MethodMetrics>>>,
+}
+
+impl Metrics {
+    /// Creates new empty metrics.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the number of requests and errors of given method.
+    pub fn requests(&self, method: &str) -> (u64, u64) {
+        self.methods
+            .lock()
+            .get(method)
+            .map_or((0, 0), |metrics| (metrics.requests, metrics.errors))
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let methods = self.methods.lock();
+        let mut out = String::new();
+        let mut counter = |name: &str, help: &str, value: &dyn Fn(&MethodMetrics) -> u64| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for (method, metrics) in methods.iter() {
+                let _ = writeln!(out, "{}{{method=\"{}\"}} {}", name, escape(method), value(metrics));
+            }
+        };
+        counter("web3_rpc_requests_total", "Number of JSON-RPC requests.", &|m| {
+            m.requests
+        });
+        counter("web3_rpc_errors_total", "Number of failed JSON-RPC requests.", &|m| {
+            m.errors
+        });
+        counter(
+            "web3_rpc_request_bytes_total",
+            "Size of JSON-RPC requests in bytes.",
+            &|m| m.request_bytes,
+        );
+        counter(
+            "web3_rpc_response_bytes_total",
+            "Size of JSON-RPC results in bytes.",
+            &|m| m.response_bytes,
+        );
+
+        let name = "web3_rpc_request_duration_seconds";
+        let _ = writeln!(out, "# HELP {} Latency of JSON-RPC requests.", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (method, metrics) in methods.iter() {
+            let method = escape(method);
+            let mut cumulative = 0;
+            for (index, count) in metrics.latency_buckets.iter().enumerate() {
+                cumulative += count;
+                let bound = LATENCY_BUCKETS
+                    .get(index)
+                    .map_or_else(|| "+Inf".to_owned(), |bound| bound.to_string());
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{method=\"{}\",le=\"{}\"}} {}",
+                    name, method, bound, cumulative
+                );
+            }
+            let _ = writeln!(out, "{}_sum{{method=\"{}\"}} {}", name, method, metrics.latency_sum);
+            let _ = writeln!(out, "{}_count{{method=\"{}\"}} {}", name, method, metrics.requests);
+        }
+        out
+    }
+}
+
+impl Interceptor for Metrics {
+    fn needs_sizes(&self) -> bool {
+        true
+    }
+
+    fn on_response(&self, request: &RequestInfo, response: &ResponseInfo) {
+        let mut methods = self.methods.lock();
+        let metrics = methods.entry(request.method.clone()).or_insert_with(|| MethodMetrics {
+            latency_buckets: vec![0; LATENCY_BUCKETS.len() + 1],
+            ..Default::default()
+        });
+
+        let latency = response.latency.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        metrics.requests += 1;
+        metrics.errors += response.result.is_err() as u64;
+        metrics.request_bytes += request.size.unwrap_or_default() as u64;
+        metrics.response_bytes += response.size.unwrap_or_default() as u64;
+        metrics.latency_buckets[bucket] += 1;
+        metrics.latency_sum += latency;
+    }
+}
+
+/// Returns the length of given value serialized to JSON.
+fn json_size<T: serde::Serialize>(value: &T) -> usize {
+    serde_json::to_vec(value).map(|json| json.len()).unwrap_or_default()
+}
+
+/// Escapes a Prometheus label value.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Interceptor, Metrics, Middleware, RequestInfo, ResponseInfo};
+    use crate::transports::MockTransport;
+    use crate::{rpc, BatchTransport, Error, Transport};
+    use futures::Future;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    #[derive(Debug, Default)]
+    struct Collect(Arc<Mutex<Vec<(String, bool)>>>);
+
+    impl Interceptor for Collect {
+        fn on_response(&self, request: &RequestInfo, response: &ResponseInfo) {
+            self.0.lock().push((request.method.clone(), response.result.is_ok()));
+        }
+    }
+
+    #[test]
+    fn should_notify_interceptors() {
+        // given
+        let collected = Arc::new(Mutex::new(vec![]));
+        let mock = MockTransport::new();
+        mock.respond("eth_blockNumber", "0x1");
+        mock.fail("eth_gasPrice", Error::Unreachable);
+        mock.respond("net_version", "1");
+        let transport = Middleware::new(mock).with(Collect(collected.clone()));
+
+        // when
+        let number = transport.execute("eth_blockNumber", vec![]).wait();
+        let batch = transport
+            .send_batch(vec![
+                transport.prepare("eth_gasPrice", vec![]),
+                transport.prepare("net_version", vec![]),
+            ])
+            .wait();
+
+        // then
+        assert_eq!(number, Ok(rpc::Value::String("0x1".into())));
+        assert_eq!(
+            batch,
+            Ok(vec![Err(Error::Unreachable), Ok(rpc::Value::String("1".into()))])
+        );
+        assert_eq!(
+            *collected.lock(),
+            vec![
+                ("eth_blockNumber".to_owned(), true),
+                ("eth_gasPrice".to_owned(), false),
+                ("net_version".to_owned(), true),
+            ]
+        );
+    }
+
+    #[derive(Debug, Default)]
+    struct Params(Arc<Mutex<Vec<Option<rpc::Params>>>>);
+
+    impl Interceptor for Params {
+        fn needs_params(&self) -> bool {
+            true
+        }
+
+        fn on_response(&self, request: &RequestInfo, response: &ResponseInfo) {
+            assert_eq!(response.size, None);
+            self.0.lock().push(request.params.clone());
+        }
+    }
+
+    #[test]
+    fn should_copy_params_only_when_needed() {
+        // given
+        let params = Arc::new(Mutex::new(vec![]));
+        let mock = MockTransport::new();
+        mock.respond("eth_getBalance", "0x1");
+        mock.respond("eth_getBalance", "0x1");
+        let plain = Middleware::new(mock.clone()).with(Collect::default());
+        let transport = Middleware::new(mock).with(Params(params.clone()));
+
+        // when
+        let _ = plain.execute("eth_getBalance", vec!["0x1".into()]).wait();
+        let _ = transport.execute("eth_getBalance", vec!["0x1".into()]).wait();
+
+        // then
+        assert!(!plain.needs.params);
+        assert_eq!(
+            *params.lock(),
+            vec![Some(rpc::Params::Array(vec![rpc::Value::String("0x1".into())]))]
+        );
+    }
+
+    #[test]
+    fn should_render_metrics() {
+        // given
+        let metrics = Metrics::new();
+        let mock = MockTransport::new();
+        mock.respond("eth_blockNumber", "0x1");
+        mock.respond("eth_blockNumber", "0x2");
+        let transport = Middleware::new(mock).with(metrics.clone());
+
+        // when
+        transport.execute("eth_blockNumber", vec![]).wait().unwrap();
+        transport.execute("eth_blockNumber", vec![]).wait().unwrap();
+        let rendered = metrics.render();
+
+        // then
+        assert_eq!(metrics.requests("eth_blockNumber"), (2, 0));
+        assert!(rendered.contains("# TYPE web3_rpc_requests_total counter\n"));
+        assert!(rendered.contains("web3_rpc_requests_total{method=\"eth_blockNumber\"} 2\n"));
+        assert!(rendered.contains("web3_rpc_errors_total{method=\"eth_blockNumber\"} 0\n"));
+        assert!(rendered.contains("web3_rpc_response_bytes_total{method=\"eth_blockNumber\"} 10\n"));
+        assert!(
+            rendered.contains("web3_rpc_request_duration_seconds_bucket{method=\"eth_blockNumber\",le=\"+Inf\"} 2\n")
+        );
+        assert!(rendered.contains("web3_rpc_request_duration_seconds_count{method=\"eth_blockNumber\"} 2\n"));
+    }
+}
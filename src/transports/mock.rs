@@ -0,0 +1,292 @@
+//! Mock Transport
+
+use crate::api::SubscriptionId;
+use crate::rpc;
+use crate::transports::Result;
+use crate::{BatchTransport, DuplexTransport, Error, RequestId, Transport};
+use futures::sync::mpsc;
+use futures::{self, Stream};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::{atomic, Arc};
+
+type Handler = Arc<dyn Fn(&[rpc::Value]) -> Result<rpc::Value> + Send + Sync>;
+
+#[derive(Default)]
+struct MockState {
+    requests: Vec<(String, Vec<rpc::Value>)>,
+    asserted: usize,
+    responses: BTreeMap<String, VecDeque<Result<rpc::Value>>>,
+    handlers: BTreeMap<String, Handler>,
+    subscriptions: BTreeMap<SubscriptionId, mpsc::UnboundedSender<rpc::Value>>,
+}
+
+impl fmt::Debug for MockState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MockState")
+            .field("requests", &self.requests)
+            .field("asserted", &self.asserted)
+            .field("responses", &self.responses)
+            .field("handlers", &self.handlers.keys().collect::<Vec<_>>())
+            .field("subscriptions", &self.subscriptions.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Transport answering requests with programmed responses, for testing code built on `Web3`.
+///
+/// Queued responses (`respond`, `fail`) take precedence over method handlers (`on`).
+/// Requests without any response fail with `Error::Transport`.
+#[derive(Debug, Clone)]
+pub struct MockTransport {
+    id: Arc<atomic::AtomicUsize>,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl Default for MockTransport {
+    fn default() -> Self {
+        MockTransport {
+            id: Arc::new(atomic::AtomicUsize::new(1)),
+            state: Default::default(),
+        }
+    }
+}
+
+impl MockTransport {
+    /// Creates new MockTransport without any responses.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Handles all calls of given method with a function of the call parameters.
+    pub fn on<F>(&self, method: &str, handler: F)
+    where
+        F: Fn(&[rpc::Value]) -> Result<rpc::Value> + Send + Sync + 'static,
+    {
+        self.state.lock().handlers.insert(method.into(), Arc::new(handler));
+    }
+
+    /// Queues a result for the next call of given method.
+    pub fn respond<V: Into<rpc::Value>>(&self, method: &str, value: V) {
+        self.queue(method, Ok(value.into()));
+    }
+
+    /// Queues an error for the next call of given method.
+    pub fn fail(&self, method: &str, error: Error) {
+        self.queue(method, Err(error));
+    }
+
+    fn queue(&self, method: &str, response: Result<rpc::Value>) {
+        self.state
+            .lock()
+            .responses
+            .entry(method.into())
+            .or_insert_with(Default::default)
+            .push_back(response);
+    }
+
+    /// Pushes a notification to given subscription, returns false if nobody is subscribed.
+    pub fn notify<V: Into<rpc::Value>>(&self, id: &SubscriptionId, value: V) -> bool {
+        match self.state.lock().subscriptions.get(id) {
+            Some(subscription) => subscription.unbounded_send(value.into()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Returns all requests sent so far.
+    pub fn requests(&self) -> Vec<(String, Vec<rpc::Value>)> {
+        self.state.lock().requests.clone()
+    }
+
+    /// Asserts that the next not yet asserted request was a call of given method with given
+    /// (JSON-serialized) parameters.
+    pub fn assert_request(&self, method: &str, params: &[String]) {
+        let mut state = self.state.lock();
+        let idx = state.asserted;
+        state.asserted += 1;
+
+        let (m, p) = state.requests.get(idx).expect("Expected a request.").clone();
+        assert_eq!(&m, method);
+        let p: Vec<String> = p.iter().map(|p| serde_json::to_string(p).unwrap()).collect();
+        assert_eq!(p, params);
+    }
+
+    /// Asserts that all requests were asserted.
+    pub fn assert_no_more_requests(&self) {
+        let state = self.state.lock();
+        assert_eq!(
+            state.asserted,
+            state.requests.len(),
+            "Expected no more requests, got: {:?}",
+            &state.requests[state.asserted..]
+        );
+    }
+
+    fn respond_to(&self, request: rpc::Call) -> Result<rpc::Value> {
+        let (method, params) = match request {
+            rpc::Call::MethodCall(call) => match call.params {
+                rpc::Params::Array(params) => (call.method, params),
+                rpc::Params::None => (call.method, vec![]),
+                rpc::Params::Map(params) => (call.method, vec![rpc::Value::Object(params)]),
+            },
+            request => return Err(Error::Transport(format!("Unsupported request: {:?}", request))),
+        };
+
+        let handler = {
+            let mut state = self.state.lock();
+            state.requests.push((method.clone(), params.clone()));
+
+            if let Some(response) = state.responses.get_mut(&method).and_then(VecDeque::pop_front) {
+                return response;
+            }
+            state.handlers.get(&method).cloned()
+        };
+        // Handlers are called without holding the lock, so that they can use the transport.
+        match handler {
+            Some(handler) => handler(&params),
+            None => Err(Error::Transport(format!("Unexpected request: {}", method))),
+        }
+    }
+}
+
+impl Transport for MockTransport {
+    type Out = crate::Result<rpc::Value>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        let id = self.id.fetch_add(1, atomic::Ordering::AcqRel);
+        let request = crate::helpers::build_request(id, method, params);
+
+        (id, request)
+    }
+
+    fn send(&self, _id: RequestId, request: rpc::Call) -> Self::Out {
+        Box::new(futures::done(self.respond_to(request)))
+    }
+}
+
+impl BatchTransport for MockTransport {
+    type Batch = crate::Result<Vec<Result<rpc::Value>>>;
+
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let responses = requests
+            .into_iter()
+            .map(|(_, request)| self.respond_to(request))
+            .collect();
+        Box::new(futures::finished(responses))
+    }
+}
+
+impl DuplexTransport for MockTransport {
+    type NotificationStream = Box<dyn Stream<Item = rpc::Value, Error = Error> + Send + 'static>;
+
+    fn subscribe(&self, id: &SubscriptionId) -> Self::NotificationStream {
+        let (tx, rx) = mpsc::unbounded();
+        self.state.lock().subscriptions.insert(id.clone(), tx);
+        Box::new(rx.map_err(|()| Error::Transport("No data available".into())))
+    }
+
+    fn unsubscribe(&self, id: &SubscriptionId) {
+        self.state.lock().subscriptions.remove(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockTransport;
+    use crate::api::Web3;
+    use crate::rpc;
+    use crate::types::{Address, H256, U256};
+    use crate::{BatchTransport, Error, Transport};
+    use futures::{Future, Stream};
+
+    #[test]
+    fn should_serve_queued_responses_and_handlers() {
+        // given
+        let transport = MockTransport::new();
+        transport.respond("eth_blockNumber", "0x10");
+        transport.on("eth_getBalance", |params| match params.get(1) {
+            Some(&rpc::Value::String(ref block)) if block == "latest" => Ok("0x2".into()),
+            _ => Ok("0x1".into()),
+        });
+        let web3 = Web3::new(transport.clone());
+
+        // when
+        let number = web3.eth().block_number().wait();
+        let balance = web3.eth().balance(Address::zero(), None).wait();
+        let missing = web3.eth().block_number().wait();
+
+        // then
+        assert_eq!(number, Ok(U256::from(16)));
+        assert_eq!(balance, Ok(U256::from(2)));
+        assert_eq!(
+            missing,
+            Err(Error::Transport("Unexpected request: eth_blockNumber".into()))
+        );
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_request(
+            "eth_getBalance",
+            &[
+                r#""0x0000000000000000000000000000000000000000""#.into(),
+                r#""latest""#.into(),
+            ],
+        );
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_no_more_requests();
+    }
+
+    #[test]
+    fn should_answer_batches() {
+        // given
+        let transport = MockTransport::new();
+        transport.respond("eth_blockNumber", "0x10");
+        transport.fail("eth_gasPrice", Error::Unreachable);
+        let requests = vec![
+            transport.prepare("eth_blockNumber", vec![]),
+            transport.prepare("eth_gasPrice", vec![]),
+        ];
+
+        // when
+        let result = transport.send_batch(requests).wait();
+
+        // then
+        assert_eq!(result, Ok(vec![Ok("0x10".into()), Err(Error::Unreachable)]));
+    }
+
+    #[test]
+    fn should_let_handlers_use_the_transport() {
+        // given
+        let transport = MockTransport::new();
+        let inner = transport.clone();
+        transport.on("eth_blockNumber", move |_| Ok(inner.requests().len().into()));
+
+        // when
+        let result = transport.execute("eth_blockNumber", vec![]).wait();
+
+        // then
+        assert_eq!(result, Ok(1.into()));
+    }
+
+    #[test]
+    fn should_push_notifications() {
+        // given
+        let transport = MockTransport::new();
+        transport.respond("eth_subscribe", "0x1");
+        let web3 = Web3::new(transport.clone());
+        let stream = web3
+            .eth_subscribe()
+            .subscribe_new_pending_transactions()
+            .wait()
+            .unwrap();
+
+        // when
+        assert!(transport.notify(stream.id(), format!("{:?}", H256::from_low_u64_be(1))));
+        let (hash, _) = stream.into_future().wait().map_err(|(err, _)| err).unwrap();
+
+        // then
+        assert_eq!(hash, Some(H256::from_low_u64_be(1)));
+    }
+}
@@ -0,0 +1,655 @@
+//! Local JSON-RPC server for integration tests
+
+#[cfg(all(unix, feature = "ipc"))]
+use crate::transports::framer::JsonFramer;
+use crate::transports::record::{self, Interaction, Replay};
+use crate::transports::Result;
+use crate::{helpers, rpc, Error};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+#[cfg(all(unix, feature = "ipc"))]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{atomic, Arc};
+use std::{fmt, fs, mem, thread};
+
+/// Result of a mocked method.
+pub type MethodResult = ::std::result::Result<rpc::Value, rpc::Error>;
+
+type Method = Arc<dyn Fn(rpc::Params) -> MethodResult + Send + Sync>;
+
+/// Closes an accepted connection from another thread.
+type Closer = Box<dyn Fn() + Send>;
+
+/// Served connections by their number, with the threads serving them.
+type Connections = Arc<Mutex<BTreeMap<usize, (Closer, thread::JoinHandle<()>)>>>;
+
+/// JSON-RPC node serving a table of methods or recorded interactions over HTTP, WebSockets and IPC.
+///
+/// Servers are bound to random local ports, so real transports can be tested end to end.
+/// Methods of the table take precedence over the interactions; requests of unknown methods
+/// fail with `Method not found`.
+#[derive(Clone, Default)]
+pub struct MockServer {
+    methods: Arc<Mutex<BTreeMap<String, Method>>>,
+    replay: Option<Replay>,
+    basic_auth: Option<String>,
+    requests: Arc<Mutex<Vec<rpc::Call>>>,
+    peers: Arc<Mutex<Vec<Arc<Mutex<Peer>>>>>,
+}
+
+impl fmt::Debug for MockServer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MockServer")
+            .field("methods", &self.methods.lock().keys().collect::<Vec<_>>())
+            .field("replay", &self.replay)
+            .field("requests", &self.requests.lock().len())
+            .field("peers", &self.peers.lock().len())
+            .finish()
+    }
+}
+
+impl MockServer {
+    /// Creates new server without any methods.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates new server responding with given interactions (see `Replay`).
+    pub fn from_interactions(interactions: Vec<Interaction>) -> Self {
+        MockServer {
+            replay: Some(Replay::new(interactions)),
+            ..Default::default()
+        }
+    }
+
+    /// Creates new server responding with interactions from given fixture file (see `Record`).
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::from_interactions(record::load(path)?))
+    }
+
+    /// Adds a method to the table, replacing previous method of the same name.
+    pub fn with_method<F>(self, name: &str, method: F) -> Self
+    where
+        F: Fn(rpc::Params) -> MethodResult + Send + Sync + 'static,
+    {
+        self.methods.lock().insert(name.into(), Arc::new(method));
+        self
+    }
+
+    /// Requires basic auth with given credentials, other HTTP requests are rejected with `401 Unauthorized`.
+    pub fn with_basic_auth(mut self, user: &str, password: &str) -> Self {
+        let credentials = format!("{}:{}", user, password);
+        self.basic_auth = Some(format!("Basic {}", base64::encode(&credentials)));
+        self
+    }
+
+    /// Returns all requests answered so far, in order.
+    pub fn requests(&self) -> Vec<rpc::Call> {
+        self.requests.lock().clone()
+    }
+
+    /// Returns names of methods called so far, in order.
+    pub fn methods(&self) -> Vec<String> {
+        self.requests()
+            .into_iter()
+            .filter_map(|call| match call {
+                rpc::Call::MethodCall(call) => Some(call.method),
+                rpc::Call::Notification(notification) => Some(notification.method),
+                rpc::Call::Invalid { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Returns interactions of the fixture which were not requested yet.
+    pub fn remaining(&self) -> Vec<Interaction> {
+        self.replay.as_ref().map(Replay::remaining).unwrap_or_default()
+    }
+
+    /// Sends a subscription notification to all WebSocket and IPC connections.
+    pub fn notify(&self, subscription: &str, result: rpc::Value) {
+        let notification = helpers::to_string(&rpc::Notification {
+            jsonrpc: Some(rpc::Version::V2),
+            method: "eth_subscription".into(),
+            params: rpc::Params::Map(
+                vec![
+                    ("subscription".to_owned(), rpc::Value::String(subscription.into())),
+                    ("result".to_owned(), result),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+        });
+        self.peers.lock().retain(|peer| peer.lock().send(&notification).is_ok());
+    }
+
+    /// Closes all WebSocket and IPC connections, e.g. to test reconnects.
+    pub fn disconnect(&self) {
+        for peer in self.peers.lock().drain(..) {
+            peer.lock().shutdown();
+        }
+    }
+
+    /// Starts serving HTTP requests.
+    pub fn serve_http(&self) -> Result<ServerHandle> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let running = self.listen(
+            move || accept_tcp(&listener),
+            |server, stream| server.serve_http_connection(stream),
+        );
+        Ok(ServerHandle::tcp(format!("http://{}", addr), addr, running))
+    }
+
+    /// Starts serving HTTPS requests with given certificate (e.g. a self-signed one for `localhost`).
+    #[cfg(feature = "tls")]
+    pub fn serve_https(&self, identity: native_tls::Identity) -> Result<ServerHandle> {
+        let acceptor = native_tls::TlsAcceptor::new(identity).map_err(|err| Error::Transport(format!("{:?}", err)))?;
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let running = self.listen(
+            move || accept_tcp(&listener),
+            move |server, stream| {
+                let stream = acceptor
+                    .accept(stream)
+                    .map_err(|err| Error::Transport(format!("{:?}", err)))?;
+                server.serve_http_connection(stream)
+            },
+        );
+        Ok(ServerHandle::tcp(
+            format!("https://localhost:{}", addr.port()),
+            addr,
+            running,
+        ))
+    }
+
+    /// Starts serving WebSocket connections.
+    #[cfg(feature = "ws")]
+    pub fn serve_ws(&self) -> Result<ServerHandle> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let running = self.listen(
+            move || accept_tcp(&listener),
+            |server, stream| server.serve_ws_connection(stream),
+        );
+        Ok(ServerHandle::tcp(format!("ws://{}", addr), addr, running))
+    }
+
+    /// Starts serving IPC connections on a Unix Domain Socket at given path.
+    #[cfg(all(unix, feature = "ipc"))]
+    pub fn serve_ipc<P: AsRef<Path>>(&self, path: P) -> Result<ServerHandle> {
+        let path = path.as_ref().to_owned();
+        let listener = UnixListener::bind(&path)?;
+        let running = self.listen(
+            move || {
+                let (stream, _) = listener.accept()?;
+                let closed = stream.try_clone()?;
+                Ok((
+                    stream,
+                    Box::new(move || drop(closed.shutdown(::std::net::Shutdown::Both))) as Closer,
+                ))
+            },
+            |server, stream| server.serve_ipc_connection(stream),
+        );
+        Ok(ServerHandle {
+            url: path.to_string_lossy().into_owned(),
+            running,
+            wake: Wake::Unix(path),
+        })
+    }
+
+    /// Accepts connections in a separate thread, serving each of them in its own thread.
+    fn listen<A, S, F>(&self, mut accept: A, serve: F) -> Running
+    where
+        A: FnMut() -> io::Result<(S, Closer)> + Send + 'static,
+        S: Send + 'static,
+        F: Fn(&MockServer, S) -> Result<()> + Send + Sync + 'static,
+    {
+        let done = Arc::new(atomic::AtomicBool::new(false));
+        let connections: Connections = Default::default();
+        let stopped = done.clone();
+        let served = connections.clone();
+        let server = self.clone();
+        let serve = Arc::new(serve);
+        let thread = thread::spawn(move || {
+            for number in 0.. {
+                let stream = accept();
+                if stopped.load(atomic::Ordering::SeqCst) {
+                    break;
+                }
+                match stream {
+                    Ok((stream, close)) => {
+                        let server = server.clone();
+                        let serve = serve.clone();
+                        let finished = served.clone();
+                        // Keep the lock until the connection is tracked, so that it's untracked only afterwards.
+                        let mut served = served.lock();
+                        let thread = thread::spawn(move || {
+                            if let Err(err) = serve(&server, stream) {
+                                log::debug!("Mock server connection closed: {:?}", err);
+                            }
+                            finished.lock().remove(&number);
+                        });
+                        served.insert(number, (close, thread));
+                    }
+                    Err(err) => log::warn!("Mock server failed to accept a connection: {:?}", err),
+                }
+            }
+        });
+        Running {
+            done,
+            thread: Some(thread),
+            connections,
+        }
+    }
+
+    fn serve_http_connection<S: Read + Write>(&self, stream: S) -> Result<()> {
+        let mut reader = BufReader::new(stream);
+        // Connections are kept alive, serve requests until closed.
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+
+            let mut headers = vec![];
+            loop {
+                line.clear();
+                reader.read_line(&mut line)?;
+                let header = line.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some(pos) = header.find(':') {
+                    headers.push((header[..pos].trim().to_lowercase(), header[pos + 1..].trim().to_owned()));
+                }
+            }
+            let header = |name: &str| {
+                headers
+                    .iter()
+                    .find(|header| header.0 == name)
+                    .map(|header| header.1.as_str())
+            };
+
+            let body = if header("transfer-encoding").map_or(false, |value| value.eq_ignore_ascii_case("chunked")) {
+                read_chunked(&mut reader)?
+            } else {
+                let length = header("content-length")
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or_default();
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body)?;
+                body
+            };
+
+            let authorized = match self.basic_auth {
+                Some(ref expected) => header("authorization") == Some(expected.as_str()),
+                None => true,
+            };
+            let stream = reader.get_mut();
+            let mut respond = |status: &str, body: String| -> Result<()> {
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )?;
+                stream.flush().map_err(Into::into)
+            };
+            if authorized {
+                self.handle(&body, |response| respond("200 OK", response.unwrap_or_default()))?;
+            } else {
+                respond("401 Unauthorized", String::new())?;
+            }
+        }
+    }
+
+    #[cfg(feature = "ws")]
+    fn serve_ws_connection(&self, stream: TcpStream) -> Result<()> {
+        use websocket::message::OwnedMessage;
+        use websocket::sync::server::IntoWs;
+
+        let upgrade = stream
+            .into_ws()
+            .map_err(|(_, _, _, err)| Error::Transport(format!("{:?}", err)))?;
+        let client = upgrade.accept().map_err(|(_, err)| err)?;
+        let (mut reader, writer) = client.split()?;
+        let peer = self.connect(Peer::Ws(writer));
+
+        for message in reader.incoming_messages() {
+            match message? {
+                OwnedMessage::Text(text) => self.handle(text.as_bytes(), |response| match response {
+                    Some(response) => peer.lock().send(&response),
+                    None => Ok(()),
+                })?,
+                OwnedMessage::Close(_) => break,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(all(unix, feature = "ipc"))]
+    fn serve_ipc_connection(&self, mut stream: UnixStream) -> Result<()> {
+        let peer = self.connect(Peer::Ipc(stream.try_clone()?));
+        let mut framer = JsonFramer::new();
+        let mut buffer = [0u8; 4096];
+
+        loop {
+            let read = stream.read(&mut buffer)?;
+            if read == 0 {
+                return Ok(());
+            }
+            framer.extend(&buffer[..read]);
+            while let Some(frame) = framer.next_frame() {
+                self.handle(&frame, |response| match response {
+                    Some(response) => peer.lock().send(&response),
+                    None => Ok(()),
+                })?;
+            }
+        }
+    }
+
+    fn connect(&self, peer: Peer) -> Arc<Mutex<Peer>> {
+        let peer = Arc::new(Mutex::new(peer));
+        self.peers.lock().push(peer.clone());
+        peer
+    }
+
+    /// Handles a request, passes the response (if any) to `respond` before recording the request.
+    ///
+    /// Recording after responding allows tests to wait for a request and then rely on the client
+    /// having received the response (e.g. before sending a notification).
+    fn handle<F>(&self, request: &[u8], respond: F) -> Result<()>
+    where
+        F: FnOnce(Option<String>) -> Result<()>,
+    {
+        let (calls, response) = match serde_json::from_slice(request) {
+            Ok(rpc::Request::Single(call)) => {
+                let output = self.call(&call);
+                (vec![call], output.map(rpc::Response::Single))
+            }
+            Ok(rpc::Request::Batch(calls)) => {
+                let outputs = calls.iter().filter_map(|call| self.call(call)).collect::<Vec<_>>();
+                let response = if outputs.is_empty() {
+                    None
+                } else {
+                    Some(rpc::Response::Batch(outputs))
+                };
+                (calls, response)
+            }
+            Err(_) => (
+                vec![],
+                Some(rpc::Response::from(rpc::Error::parse_error(), Some(rpc::Version::V2))),
+            ),
+        };
+
+        respond(response.map(|response| helpers::to_string(&response)))?;
+        self.requests.lock().extend(calls);
+        Ok(())
+    }
+
+    fn call(&self, call: &rpc::Call) -> Option<rpc::Output> {
+        match *call {
+            rpc::Call::MethodCall(ref method_call) => Some(rpc::Output::from(
+                self.respond(call),
+                method_call.id.clone(),
+                method_call.jsonrpc,
+            )),
+            rpc::Call::Notification(_) => None,
+            rpc::Call::Invalid { ref id } => Some(rpc::Output::invalid_request(id.clone(), Some(rpc::Version::V2))),
+        }
+    }
+
+    fn respond(&self, call: &rpc::Call) -> MethodResult {
+        let (name, params) = match *call {
+            rpc::Call::MethodCall(ref call) => (&call.method, call.params.clone()),
+            _ => return Err(rpc::Error::invalid_request()),
+        };
+
+        let method = self.methods.lock().get(name).cloned();
+        match (method, self.replay.as_ref()) {
+            (Some(method), _) => method(params),
+            (None, Some(replay)) => replay.respond(call).map_err(|err| match err {
+                Error::Rpc(err) => err,
+                err => rpc::Error {
+                    code: rpc::ErrorCode::InternalError,
+                    message: format!("{}", err),
+                    data: None,
+                },
+            }),
+            (None, None) => Err(rpc::Error::method_not_found()),
+        }
+    }
+}
+
+/// Reads a body sent with `Transfer-Encoding: chunked`.
+fn read_chunked<R: BufRead>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut body = vec![];
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let size = line.trim().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size, 16).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        // Each chunk (including the last, empty one) is followed by CRLF.
+        let mut chunk = vec![0; size + 2];
+        reader.read_exact(&mut chunk)?;
+        if size == 0 {
+            return Ok(body);
+        }
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
+
+/// Connection receiving notifications.
+enum Peer {
+    #[cfg(feature = "ws")]
+    Ws(websocket::sync::Writer<TcpStream>),
+    #[cfg(all(unix, feature = "ipc"))]
+    Ipc(UnixStream),
+}
+
+impl Peer {
+    fn send(&mut self, message: &str) -> Result<()> {
+        match *self {
+            #[cfg(feature = "ws")]
+            Peer::Ws(ref mut writer) => writer
+                .send_message(&websocket::message::OwnedMessage::Text(message.into()))
+                .map_err(Into::into),
+            #[cfg(all(unix, feature = "ipc"))]
+            Peer::Ipc(ref mut stream) => stream
+                .write_all(format!("{}\n", message).as_bytes())
+                .map_err(Into::into),
+        }
+    }
+
+    fn shutdown(&self) {
+        let result = match *self {
+            #[cfg(feature = "ws")]
+            Peer::Ws(ref writer) => writer.shutdown_all(),
+            #[cfg(all(unix, feature = "ipc"))]
+            Peer::Ipc(ref stream) => stream.shutdown(::std::net::Shutdown::Both),
+        };
+        if let Err(err) = result {
+            log::debug!("Unable to close mock server connection: {:?}", err);
+        }
+    }
+}
+
+/// Accepts a TCP connection.
+fn accept_tcp(listener: &TcpListener) -> io::Result<(TcpStream, Closer)> {
+    let (stream, _) = listener.accept()?;
+    let closed = stream.try_clone()?;
+    Ok((
+        stream,
+        Box::new(move || drop(closed.shutdown(::std::net::Shutdown::Both))),
+    ))
+}
+
+/// Accepting thread and served connections of a running server.
+struct Running {
+    done: Arc<atomic::AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+    connections: Connections,
+}
+
+enum Wake {
+    Tcp(SocketAddr),
+    #[cfg_attr(not(all(unix, feature = "ipc")), allow(dead_code))]
+    Unix(PathBuf),
+}
+
+/// Running server, stops accepting connections and closes the open ones when dropped.
+pub struct ServerHandle {
+    url: String,
+    running: Running,
+    wake: Wake,
+}
+
+impl ServerHandle {
+    fn tcp(url: String, addr: SocketAddr, running: Running) -> Self {
+        ServerHandle {
+            url,
+            running,
+            wake: Wake::Tcp(addr),
+        }
+    }
+
+    /// Returns URL of the server (or path of the socket for IPC).
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl fmt::Debug for ServerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ServerHandle").field("url", &self.url).finish()
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.running.done.store(true, atomic::Ordering::SeqCst);
+        // Wake up the thread blocked on accepting connections.
+        let woken = match self.wake {
+            Wake::Tcp(addr) => TcpStream::connect(addr).is_ok(),
+            Wake::Unix(ref path) => {
+                #[cfg(all(unix, feature = "ipc"))]
+                let woken = UnixStream::connect(path).is_ok();
+                #[cfg(not(all(unix, feature = "ipc")))]
+                let woken = false;
+                let _ = fs::remove_file(path);
+                woken
+            }
+        };
+        if let (true, Some(thread)) = (woken, self.running.thread.take()) {
+            let _ = thread.join();
+        }
+
+        let connections = mem::replace(&mut *self.running.connections.lock(), Default::default());
+        for (_, (close, thread)) in connections {
+            close();
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockServer;
+    use crate::rpc;
+    use crate::transports::record::Interaction;
+    use crate::transports::Http;
+    use crate::{BatchTransport, Error, Transport};
+    use futures::Future;
+
+    fn server() -> MockServer {
+        MockServer::new().with_method("eth_blockNumber", |_| Ok(rpc::Value::String("0x1".into())))
+    }
+
+    #[test]
+    fn should_serve_method_table_over_http() {
+        // given
+        let server = server();
+        let handle = server.serve_http().unwrap();
+        let (_eloop, http) = Http::new(handle.url()).unwrap();
+
+        // when
+        let block = http.execute("eth_blockNumber", vec![]).wait();
+        let missing = http.execute("eth_chainId", vec![]).wait();
+
+        // then
+        assert_eq!(block, Ok(rpc::Value::String("0x1".into())));
+        assert_eq!(missing, Err(Error::Rpc(rpc::Error::method_not_found())));
+        assert_eq!(server.methods(), vec!["eth_blockNumber", "eth_chainId"]);
+    }
+
+    #[test]
+    fn should_serve_fixture_over_http() {
+        // given
+        let server = MockServer::from_interactions(vec![
+            Interaction {
+                method: "eth_blockNumber".into(),
+                params: rpc::Params::Array(vec![]),
+                result: Some(rpc::Value::String("0x2".into())),
+                error: None,
+            },
+            Interaction {
+                method: "eth_getBalance".into(),
+                params: rpc::Params::Array(vec![rpc::Value::String("0x0".into())]),
+                result: None,
+                error: Some(rpc::Error::invalid_params("invalid address")),
+            },
+        ]);
+        let handle = server.serve_http().unwrap();
+        let (_eloop, http) = Http::new(handle.url()).unwrap();
+
+        // when
+        let results = http
+            .send_batch(vec![
+                http.prepare("eth_getBalance", vec![rpc::Value::String("0x0".into())]),
+                http.prepare("eth_blockNumber", vec![]),
+            ])
+            .wait();
+
+        // then
+        assert_eq!(
+            results,
+            Ok(vec![
+                Err(Error::Rpc(rpc::Error::invalid_params("invalid address"))),
+                Ok(rpc::Value::String("0x2".into())),
+            ])
+        );
+        assert_eq!(server.remaining(), vec![]);
+    }
+
+    #[cfg(all(unix, feature = "ipc"))]
+    #[test]
+    fn should_serve_ipc_and_accept_reconnects() {
+        use crate::transports::Ipc;
+
+        // given
+        let path = ::std::env::temp_dir().join(format!("web3-mock-server-{}.ipc", ::std::process::id()));
+        let _ = ::std::fs::remove_file(&path);
+        let server = server();
+        let handle = server.serve_ipc(&path).unwrap();
+        let (_eloop, ipc) = Ipc::new(handle.url()).unwrap();
+        let first = ipc.execute("eth_blockNumber", vec![]).wait();
+
+        // when
+        server.disconnect();
+        let second = (0..50)
+            .map(|_| {
+                ::std::thread::sleep(::std::time::Duration::from_millis(20));
+                ipc.execute("eth_blockNumber", vec![]).wait()
+            })
+            .find(Result::is_ok);
+
+        // then
+        assert_eq!(first, Ok(rpc::Value::String("0x1".into())));
+        assert_eq!(second, Some(Ok(rpc::Value::String("0x1".into()))));
+    }
+}
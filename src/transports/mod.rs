@@ -5,13 +5,45 @@ use crate::Error;
 /// RPC Result.
 pub type Result<T> = ::std::result::Result<T, Error>;
 
+pub mod auto_batch;
+pub use self::auto_batch::AutoBatch;
 pub mod batch;
 pub use self::batch::Batch;
+pub mod cache;
+pub use self::cache::Cache;
+pub mod middleware;
+pub use self::middleware::{Metrics, Middleware};
+pub mod mock;
+pub use self::mock::MockTransport;
+#[cfg(any(test, feature = "mock-server"))]
+pub mod mock_server;
+#[cfg(any(test, feature = "mock-server"))]
+pub use self::mock_server::MockServer;
+pub mod multi;
+pub use self::multi::Multi;
+pub mod rate_limit;
+pub use self::rate_limit::RateLimit;
+pub mod record;
+pub use self::record::{Record, Replay};
+pub mod retry;
+pub use self::retry::Retry;
+
+mod backoff;
+mod timer;
+
+#[cfg(feature = "devnet")]
+pub mod devnet;
+#[cfg(feature = "devnet")]
+pub use self::devnet::Devnet;
 
 #[cfg(feature = "http")]
 pub mod http;
 #[cfg(feature = "http")]
-pub use self::http::Http;
+pub use self::http::{Http, HttpBuilder};
+#[cfg(feature = "http")]
+pub mod graphql;
+#[cfg(feature = "http")]
+pub use self::graphql::GraphQl;
 
 #[cfg(feature = "ipc")]
 pub mod ipc;
@@ -23,11 +55,11 @@ pub mod ws;
 #[cfg(feature = "ws")]
 pub use self::ws::WebSocket;
 
+#[cfg(feature = "ipc")]
+mod framer;
 #[cfg(any(feature = "ipc", feature = "http", feature = "ws"))]
 mod shared;
 #[cfg(any(feature = "ipc", feature = "http", feature = "ws"))]
 extern crate tokio_core;
-#[cfg(any(feature = "ipc"))]
-extern crate tokio_io;
 #[cfg(any(feature = "ipc", feature = "http", feature = "ws"))]
 pub use self::shared::EventLoopHandle;
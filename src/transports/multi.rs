@@ -0,0 +1,483 @@
+//! Multi-endpoint Transport
+
+use crate::helpers;
+use crate::rpc;
+use crate::transports::retry::IDEMPOTENT_METHODS;
+use crate::transports::Result;
+use crate::{BatchTransport, Error, RequestId, Transport};
+use futures::{Async, Future, Poll};
+use std::fmt;
+use std::sync::{atomic, Arc};
+
+/// The way requests are spread across endpoints.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// Sends requests to the first endpoint, failing over to the next ones on error.
+    ///
+    /// Only transport errors (including `429` and `5xx` HTTP statuses) are failed over and only
+    /// for requests which are safe to send twice (reads and raw transactions), RPC errors are
+    /// returned as they are.
+    Fallback,
+    /// Starts each request at the next endpoint in turn, failing over to the following ones on error.
+    RoundRobin,
+    /// Sends read requests to all endpoints and returns a result once the given number of them agree,
+    /// otherwise fails with `Error::Disagreement`.
+    ///
+    /// Other requests (e.g. `eth_sendRawTransaction`) are sent as with `Fallback`.
+    Quorum(usize),
+}
+
+/// Transport distributing requests between several endpoints.
+///
+/// Protects against unavailable endpoints and, with `Strategy::Quorum`, against endpoints
+/// returning stale or invalid data.
+#[derive(Debug, Clone)]
+pub struct Multi<T> {
+    id: Arc<atomic::AtomicUsize>,
+    next: Arc<atomic::AtomicUsize>,
+    transports: Arc<Vec<T>>,
+    strategy: Strategy,
+}
+
+impl<T: Transport> Multi<T> {
+    /// Creates new Multi transport with given endpoints.
+    ///
+    /// Panics if there are no endpoints or the quorum can't ever be reached.
+    pub fn new(transports: Vec<T>, strategy: Strategy) -> Self {
+        assert!(!transports.is_empty(), "At least one transport is required.");
+        if let Strategy::Quorum(required) = strategy {
+            assert!(
+                required > 0 && required <= transports.len(),
+                "Quorum of {} can't be reached with {} transports.",
+                required,
+                transports.len()
+            );
+        }
+
+        Multi {
+            id: Arc::new(atomic::AtomicUsize::new(1)),
+            next: Default::default(),
+            transports: Arc::new(transports),
+            strategy,
+        }
+    }
+
+    /// Returns the endpoints.
+    pub fn transports(&self) -> &[T] {
+        &self.transports
+    }
+
+    fn call<R, F>(&self, request: R, read: bool, resend: bool, send: fn(&T, &R) -> F) -> MultiFuture<T, R, F>
+    where
+        F: Future<Error = Error>,
+    {
+        let start = match self.strategy {
+            Strategy::Quorum(required) if read => {
+                return MultiFuture::Quorum(QuorumFuture {
+                    futures: self
+                        .transports
+                        .iter()
+                        .map(|transport| Some(send(transport, &request)))
+                        .collect(),
+                    required,
+                    results: vec![],
+                    errors: vec![],
+                })
+            }
+            Strategy::RoundRobin => self.next.fetch_add(1, atomic::Ordering::AcqRel) % self.transports.len(),
+            _ => 0,
+        };
+
+        MultiFuture::Fallback(FallbackFuture {
+            current: send(&self.transports[start], &request),
+            transports: self.transports.clone(),
+            request,
+            send,
+            index: start,
+            remaining: if resend { self.transports.len() - 1 } else { 0 },
+        })
+    }
+}
+
+fn is_read(request: &rpc::Call) -> bool {
+    match *request {
+        rpc::Call::MethodCall(ref call) => IDEMPOTENT_METHODS.contains(&call.method.as_str()),
+        _ => false,
+    }
+}
+
+/// Whether the request may be sent to another endpoint after a failure.
+///
+/// A raw transaction sent twice is rejected as already known, other writes would be executed twice.
+fn can_resend(request: &rpc::Call) -> bool {
+    match *request {
+        rpc::Call::MethodCall(ref call) => is_read(request) || call.method == "eth_sendRawTransaction",
+        _ => false,
+    }
+}
+
+/// Whether the error is caused by the endpoint (rather than the request) and another one may succeed.
+fn is_endpoint_failure(error: &Error) -> bool {
+    match *error {
+        Error::HttpStatus(status, _) => status == 429 || (500..600).contains(&status),
+        Error::Transport(_) | Error::Io(_) | Error::Unreachable => true,
+        _ => false,
+    }
+}
+
+fn send_single<T: Transport>(transport: &T, request: &(RequestId, rpc::Call)) -> T::Out {
+    transport.send(request.0, request.1.clone())
+}
+
+#[allow(clippy::ptr_arg)]
+fn send_batch<T: BatchTransport>(transport: &T, requests: &Vec<(RequestId, rpc::Call)>) -> T::Batch {
+    transport.send_batch(requests.clone())
+}
+
+impl<T: Transport> Transport for Multi<T> {
+    type Out = MultiFuture<T, (RequestId, rpc::Call), T::Out>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        let id = self.id.fetch_add(1, atomic::Ordering::AcqRel);
+        let request = helpers::build_request(id, method, params);
+
+        (id, request)
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        let (read, resend) = (is_read(&request), can_resend(&request));
+        self.call((id, request), read, resend, send_single)
+    }
+}
+
+impl<T: BatchTransport> BatchTransport for Multi<T> {
+    type Batch = MultiFuture<T, Vec<(RequestId, rpc::Call)>, T::Batch>;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let requests = requests.into_iter().collect::<Vec<_>>();
+        let read = requests.iter().all(|&(_, ref request)| is_read(request));
+        let resend = requests.iter().all(|&(_, ref request)| can_resend(request));
+        self.call(requests, read, resend, send_batch)
+    }
+}
+
+/// A future representing a request sent to multiple endpoints.
+#[derive(Debug)]
+pub enum MultiFuture<T, R, F: Future> {
+    /// Sending to endpoints one by one.
+    Fallback(FallbackFuture<T, R, F>),
+    /// Sending to all endpoints at once.
+    Quorum(QuorumFuture<F>),
+}
+
+impl<T, R, F> Future for MultiFuture<T, R, F>
+where
+    F: Future<Error = Error>,
+    F::Item: PartialEq,
+{
+    type Item = F::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match *self {
+            MultiFuture::Fallback(ref mut future) => future.poll(),
+            MultiFuture::Quorum(ref mut future) => future.poll(),
+        }
+    }
+}
+
+/// A future trying endpoints one by one until one of them succeeds.
+pub struct FallbackFuture<T, R, F> {
+    transports: Arc<Vec<T>>,
+    request: R,
+    send: fn(&T, &R) -> F,
+    index: usize,
+    remaining: usize,
+    current: F,
+}
+
+impl<T: fmt::Debug, R: fmt::Debug, F: fmt::Debug> fmt::Debug for FallbackFuture<T, R, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FallbackFuture")
+            .field("transports", &self.transports)
+            .field("request", &self.request)
+            .field("index", &self.index)
+            .field("remaining", &self.remaining)
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+impl<T, R, F> Future for FallbackFuture<T, R, F>
+where
+    F: Future<Error = Error>,
+{
+    type Item = F::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            match self.current.poll() {
+                Err(ref err) if self.remaining > 0 && is_endpoint_failure(err) => {
+                    log::warn!(
+                        "Endpoint {} failed, falling back to the next one: {:?}",
+                        self.index,
+                        err
+                    );
+                }
+                result => return result,
+            }
+
+            self.remaining -= 1;
+            self.index = (self.index + 1) % self.transports.len();
+            self.current = (self.send)(&self.transports[self.index], &self.request);
+        }
+    }
+}
+
+/// A future resolving once enough endpoints return the same result.
+#[derive(Debug)]
+pub struct QuorumFuture<F: Future> {
+    futures: Vec<Option<F>>,
+    required: usize,
+    results: Vec<(F::Item, usize)>,
+    errors: Vec<Error>,
+}
+
+impl<F> Future for QuorumFuture<F>
+where
+    F: Future<Error = Error>,
+    F::Item: PartialEq,
+{
+    type Item = F::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        for slot in self.futures.iter_mut() {
+            let result = match *slot {
+                Some(ref mut future) => future.poll(),
+                None => continue,
+            };
+
+            match result {
+                Ok(Async::NotReady) => continue,
+                Ok(Async::Ready(value)) => match self.results.iter_mut().find(|result| result.0 == value) {
+                    Some(result) => result.1 += 1,
+                    None => self.results.push((value, 1)),
+                },
+                Err(err) => self.errors.push(err),
+            }
+            *slot = None;
+        }
+
+        let required = self.required;
+        if let Some(idx) = self.results.iter().position(|&(_, count)| count >= required) {
+            return Ok(Async::Ready(self.results.swap_remove(idx).0));
+        }
+
+        let pending = self.futures.iter().filter(|future| future.is_some()).count();
+        let best = self.results.iter().map(|&(_, count)| count).max().unwrap_or(0);
+        if best + pending >= required {
+            return Ok(Async::NotReady);
+        }
+
+        if self.results.is_empty() {
+            if let Some(err) = self.errors.pop() {
+                return Err(err);
+            }
+        }
+        Err(Error::Disagreement(format!(
+            "{} distinct results, at most {} of {} required endpoints agree ({} errors)",
+            self.results.len(),
+            best,
+            required,
+            self.errors.len()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Multi, Strategy};
+    use crate::helpers;
+    use crate::rpc;
+    use crate::transports::Result;
+    use crate::{Error, RequestId, Transport};
+    use futures::{self, Future};
+    use parking_lot::Mutex;
+    use std::sync::{atomic, Arc};
+
+    #[derive(Debug, Clone)]
+    struct FixedTransport {
+        id: Arc<atomic::AtomicUsize>,
+        response: Result<rpc::Value>,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl FixedTransport {
+        fn new(response: Result<rpc::Value>) -> Self {
+            FixedTransport {
+                id: Arc::new(atomic::AtomicUsize::new(1)),
+                response,
+                requests: Default::default(),
+            }
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().clone()
+        }
+    }
+
+    impl Transport for FixedTransport {
+        type Out = crate::Result<rpc::Value>;
+
+        fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+            let id = self.id.fetch_add(1, atomic::Ordering::AcqRel);
+            (id, helpers::build_request(id, method, params))
+        }
+
+        fn send(&self, _id: RequestId, request: rpc::Call) -> Self::Out {
+            if let rpc::Call::MethodCall(call) = request {
+                self.requests.lock().push(call.method);
+            }
+            Box::new(futures::done(self.response.clone()))
+        }
+    }
+
+    fn value(value: &str) -> Result<rpc::Value> {
+        Ok(rpc::Value::String(value.into()))
+    }
+
+    #[test]
+    fn should_fall_back_to_next_endpoint() {
+        // given
+        let failing = FixedTransport::new(Err(Error::Transport("connection refused".into())));
+        let working = FixedTransport::new(value("0x1"));
+        let multi = Multi::new(vec![failing.clone(), working.clone()], Strategy::Fallback);
+
+        // when
+        let result = multi.execute("eth_blockNumber", vec![]).wait();
+
+        // then
+        assert_eq!(result, value("0x1"));
+        assert_eq!(failing.requests(), vec!["eth_blockNumber".to_owned()]);
+        assert_eq!(working.requests(), vec!["eth_blockNumber".to_owned()]);
+    }
+
+    #[test]
+    fn should_return_last_error_if_all_endpoints_fail() {
+        // given
+        let first = FixedTransport::new(Err(Error::Transport("connection refused".into())));
+        let second = FixedTransport::new(Err(Error::Unreachable));
+        let multi = Multi::new(vec![first, second], Strategy::Fallback);
+
+        // when
+        let result = multi.execute("eth_blockNumber", vec![]).wait();
+
+        // then
+        assert_eq!(result, Err(Error::Unreachable));
+    }
+
+    #[test]
+    fn should_balance_requests() {
+        // given
+        let first = FixedTransport::new(value("0x1"));
+        let second = FixedTransport::new(value("0x2"));
+        let multi = Multi::new(vec![first, second], Strategy::RoundRobin);
+
+        // when
+        let results = (0..3)
+            .map(|_| multi.execute("eth_blockNumber", vec![]).wait())
+            .collect::<Vec<_>>();
+
+        // then
+        assert_eq!(results, vec![value("0x1"), value("0x2"), value("0x1")]);
+    }
+
+    #[test]
+    fn should_return_result_agreed_by_quorum() {
+        // given
+        let honest = FixedTransport::new(value("0x1"));
+        let lying = FixedTransport::new(value("0x2"));
+        let multi = Multi::new(vec![lying, honest.clone(), honest], Strategy::Quorum(2));
+
+        // when
+        let result = multi.execute("eth_getBalance", vec![]).wait();
+
+        // then
+        assert_eq!(result, value("0x1"));
+    }
+
+    #[test]
+    fn should_fail_without_quorum() {
+        // given
+        let first = FixedTransport::new(value("0x1"));
+        let second = FixedTransport::new(value("0x2"));
+        let failing = FixedTransport::new(Err(Error::Unreachable));
+        let multi = Multi::new(vec![first, second, failing], Strategy::Quorum(2));
+
+        // when
+        let result = multi.execute("eth_getBalance", vec![]).wait();
+
+        // then
+        assert_eq!(
+            result,
+            Err(Error::Disagreement(
+                "2 distinct results, at most 1 of 2 required endpoints agree (1 errors)".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn should_send_writes_to_single_endpoint_with_quorum() {
+        // given
+        let first = FixedTransport::new(value("0x1"));
+        let second = FixedTransport::new(value("0x1"));
+        let multi = Multi::new(vec![first.clone(), second.clone()], Strategy::Quorum(2));
+
+        // when
+        let result = multi
+            .execute("eth_sendRawTransaction", vec![rpc::Value::String("0x00".into())])
+            .wait();
+
+        // then
+        assert_eq!(result, value("0x1"));
+        assert_eq!(first.requests(), vec!["eth_sendRawTransaction".to_owned()]);
+        assert_eq!(second.requests(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn should_not_fall_back_on_rpc_errors() {
+        // given
+        let reverting = FixedTransport::new(Err(Error::Rpc(rpc::Error::invalid_params("nonce too low"))));
+        let working = FixedTransport::new(value("0x1"));
+        let multi = Multi::new(vec![reverting, working.clone()], Strategy::Fallback);
+
+        // when
+        let result = multi.execute("eth_call", vec![]).wait();
+
+        // then
+        assert_eq!(result, Err(Error::Rpc(rpc::Error::invalid_params("nonce too low"))));
+        assert_eq!(working.requests(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn should_fall_back_only_for_requests_safe_to_resend() {
+        // given
+        let failing = FixedTransport::new(Err(Error::Transport("timed out".into())));
+        let working = FixedTransport::new(value("0x1"));
+        let multi = Multi::new(vec![failing, working.clone()], Strategy::Fallback);
+
+        // when
+        let transaction = multi.execute("eth_sendTransaction", vec![]).wait();
+        let raw_transaction = multi.execute("eth_sendRawTransaction", vec![]).wait();
+
+        // then
+        assert_eq!(transaction, Err(Error::Transport("timed out".into())));
+        assert_eq!(raw_transaction, value("0x1"));
+        assert_eq!(working.requests(), vec!["eth_sendRawTransaction".to_owned()]);
+    }
+}
@@ -0,0 +1,404 @@
+//! Rate-limiting Transport
+
+use crate::rpc;
+use crate::{BatchTransport, Error, RequestId, Transport};
+use futures::task::{self, Task};
+use futures::{Async, Future, Poll};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{cmp, fmt, mem};
+use tokio_timer::{Sleep, Timer};
+
+/// Longest single sleep, well below the maximal timeout of the timer.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// Token bucket and concurrency limit shared by all clones of the transport.
+#[derive(Debug)]
+struct Limiter {
+    /// Tokens added per second.
+    rate: f64,
+    /// Capacity of the bucket.
+    burst: f64,
+    /// Available tokens, negative when requests are queued.
+    tokens: f64,
+    last_refill: Instant,
+    max_concurrent: Option<usize>,
+    in_flight: usize,
+    waiting: VecDeque<Task>,
+}
+
+impl Limiter {
+    /// Takes `cost` tokens and returns how long the request has to wait for them.
+    fn reserve(&mut self, cost: u32, now: Instant) -> Duration {
+        let elapsed = now.duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.burst);
+        self.last_refill = now;
+        self.tokens -= f64::from(cost);
+
+        if self.tokens >= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    fn try_acquire(&mut self) -> bool {
+        match self.max_concurrent {
+            Some(max) if self.in_flight >= max => {
+                self.waiting.push_back(task::current());
+                false
+            }
+            _ => {
+                self.in_flight += 1;
+                true
+            }
+        }
+    }
+
+    fn release(&mut self) {
+        self.in_flight -= 1;
+        // Waiting requests might have been dropped in the meantime, wake all of them.
+        for task in self.waiting.drain(..) {
+            task.notify();
+        }
+    }
+}
+
+/// Transport limiting the rate (and optionally the concurrency) of requests.
+///
+/// Uses a token bucket refilled at a constant rate. Each call costs one token (or the weight
+/// configured for its method), so a batch costs the sum of its calls. Requests exceeding
+/// the limit are queued (delayed), not failed.
+#[derive(Debug, Clone)]
+pub struct RateLimit<T> {
+    transport: T,
+    limiter: Arc<Mutex<Limiter>>,
+    weights: Arc<BTreeMap<String, u32>>,
+    timer: Timer,
+}
+
+impl<T: Transport> RateLimit<T> {
+    /// Creates new RateLimit transport allowing given number of requests per second.
+    ///
+    /// Bursts up to the same number of requests are allowed.
+    pub fn new(transport: T, requests_per_second: u32) -> Self {
+        assert!(requests_per_second > 0, "Rate limit has to be positive.");
+        let rate = f64::from(requests_per_second);
+        RateLimit {
+            transport,
+            limiter: Arc::new(Mutex::new(Limiter {
+                rate,
+                burst: rate,
+                tokens: rate,
+                last_refill: Instant::now(),
+                max_concurrent: None,
+                in_flight: 0,
+                waiting: Default::default(),
+            })),
+            weights: Default::default(),
+            timer: super::timer::shared(),
+        }
+    }
+
+    /// Sets the timer used to delay requests, a timer shared by all transports is used by default.
+    pub fn with_timer(mut self, timer: Timer) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Sets the maximal number of requests sent at once, without waiting for a rate limit.
+    pub fn with_burst(self, burst: u32) -> Self {
+        {
+            let mut limiter = self.limiter.lock();
+            limiter.burst = f64::from(burst);
+            limiter.tokens = limiter.tokens.min(limiter.burst);
+        }
+        self
+    }
+
+    /// Sets the cost of calling given method (e.g. to match provider compute units), defaults to `1`.
+    pub fn with_weight(mut self, method: &str, weight: u32) -> Self {
+        Arc::make_mut(&mut self.weights).insert(method.into(), weight);
+        self
+    }
+
+    /// Sets the maximal number of requests in flight, further requests wait for a response.
+    pub fn with_max_concurrent(self, max_concurrent: usize) -> Self {
+        self.limiter.lock().max_concurrent = Some(max_concurrent);
+        self
+    }
+
+    /// Returns the cost of given call.
+    pub fn cost(&self, request: &rpc::Call) -> u32 {
+        match *request {
+            rpc::Call::MethodCall(rpc::MethodCall { ref method, .. })
+            | rpc::Call::Notification(rpc::Notification { ref method, .. }) => {
+                self.weights.get(method).cloned().unwrap_or(1)
+            }
+            rpc::Call::Invalid { .. } => 1,
+        }
+    }
+
+    /// Returns the underlying transport.
+    pub fn inner(&self) -> &T {
+        &self.transport
+    }
+
+    fn call<R, F>(&self, request: R, cost: u32, send: fn(&T, R) -> F) -> RateLimited<T, R, F> {
+        RateLimited {
+            state: LimitState::Reserving(cost),
+            transport: self.transport.clone(),
+            request: Some(request),
+            send,
+            limiter: self.limiter.clone(),
+            timer: self.timer.clone(),
+        }
+    }
+}
+
+fn send_single<T: Transport>(transport: &T, (id, request): (RequestId, rpc::Call)) -> T::Out {
+    transport.send(id, request)
+}
+
+fn send_batch<T: BatchTransport>(transport: &T, requests: Vec<(RequestId, rpc::Call)>) -> T::Batch {
+    transport.send_batch(requests)
+}
+
+impl<T: Transport> Transport for RateLimit<T> {
+    type Out = RateLimited<T, (RequestId, rpc::Call), T::Out>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        let cost = self.cost(&request);
+        self.call((id, request), cost, send_single)
+    }
+}
+
+impl<T: BatchTransport> BatchTransport for RateLimit<T> {
+    type Batch = RateLimited<T, Vec<(RequestId, rpc::Call)>, T::Batch>;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let requests = requests.into_iter().collect::<Vec<_>>();
+        let cost = requests.iter().map(|&(_, ref request)| self.cost(request)).sum();
+        self.call(requests, cost, send_batch)
+    }
+}
+
+/// Releases a concurrency slot when dropped.
+#[derive(Debug)]
+struct Permit(Arc<Mutex<Limiter>>);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.0.lock().release();
+    }
+}
+
+#[derive(Debug)]
+enum LimitState<F> {
+    /// Tokens are reserved on the first poll, so that futures dropped before being polled are free.
+    Reserving(u32),
+    Delayed(Instant, Option<Sleep>),
+    Acquiring,
+    Sending(F, Permit),
+    Done,
+}
+
+/// A future representing a request waiting for the rate limit.
+pub struct RateLimited<T, R, F> {
+    state: LimitState<F>,
+    transport: T,
+    request: Option<R>,
+    send: fn(&T, R) -> F,
+    limiter: Arc<Mutex<Limiter>>,
+    timer: Timer,
+}
+
+impl<T: fmt::Debug, R: fmt::Debug, F: fmt::Debug> fmt::Debug for RateLimited<T, R, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RateLimited")
+            .field("state", &self.state)
+            .field("transport", &self.transport)
+            .field("request", &self.request)
+            .finish()
+    }
+}
+
+impl<T, R, F> Future for RateLimited<T, R, F>
+where
+    F: Future<Error = Error>,
+{
+    type Item = F::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            let next_state = match mem::replace(&mut self.state, LimitState::Done) {
+                LimitState::Reserving(cost) => {
+                    let now = Instant::now();
+                    let delay = self.limiter.lock().reserve(cost, now);
+                    if delay > Duration::from_secs(0) {
+                        log::debug!("Rate limit exceeded, delaying request by {:?}", delay);
+                    }
+                    LimitState::Delayed(now + delay, None)
+                }
+                LimitState::Delayed(until, sleep) => {
+                    let now = Instant::now();
+                    if now >= until {
+                        LimitState::Acquiring
+                    } else {
+                        let mut sleep = sleep.unwrap_or_else(|| self.timer.sleep(cmp::min(until - now, MAX_SLEEP)));
+                        match sleep.poll() {
+                            Ok(Async::NotReady) => {
+                                self.state = LimitState::Delayed(until, Some(sleep));
+                                return Ok(Async::NotReady);
+                            }
+                            Ok(Async::Ready(())) => LimitState::Delayed(until, None),
+                            Err(err) => {
+                                log::warn!("Sending request without delay: {:?}", err);
+                                LimitState::Acquiring
+                            }
+                        }
+                    }
+                }
+                LimitState::Acquiring => {
+                    if !self.limiter.lock().try_acquire() {
+                        self.state = LimitState::Acquiring;
+                        return Ok(Async::NotReady);
+                    }
+                    let request = self.request.take().expect("Request is sent only once; qed");
+                    LimitState::Sending((self.send)(&self.transport, request), Permit(self.limiter.clone()))
+                }
+                LimitState::Sending(mut future, permit) => match future.poll()? {
+                    Async::NotReady => {
+                        self.state = LimitState::Sending(future, permit);
+                        return Ok(Async::NotReady);
+                    }
+                    Async::Ready(result) => return Ok(Async::Ready(result)),
+                },
+                LimitState::Done => panic!("Polled a completed future."),
+            };
+            self.state = next_state;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Limiter, RateLimit};
+    use crate::helpers::tests::TestTransport;
+    use crate::rpc;
+    use crate::Transport;
+    use futures::Future;
+    use std::time::{Duration, Instant};
+
+    fn limiter(rate: f64, burst: f64, max_concurrent: Option<usize>) -> Limiter {
+        Limiter {
+            rate,
+            burst,
+            tokens: burst,
+            last_refill: Instant::now(),
+            max_concurrent,
+            in_flight: 0,
+            waiting: Default::default(),
+        }
+    }
+
+    #[test]
+    fn should_queue_requests_over_the_limit() {
+        // given
+        let mut limiter = limiter(2.0, 2.0, None);
+        let now = limiter.last_refill;
+
+        // when
+        let delays = (0..4).map(|_| limiter.reserve(1, now)).collect::<Vec<_>>();
+
+        // then
+        assert_eq!(
+            delays,
+            vec![
+                Duration::from_secs(0),
+                Duration::from_secs(0),
+                Duration::from_millis(500),
+                Duration::from_secs(1),
+            ]
+        );
+        assert_eq!(
+            limiter.reserve(1, now + Duration::from_millis(500)),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn should_count_weights() {
+        // given
+        let transport = RateLimit::new(TestTransport::default(), 10).with_weight("eth_getLogs", 75);
+        let (_, logs) = transport.prepare("eth_getLogs", vec![]);
+        let (_, number) = transport.prepare("eth_blockNumber", vec![]);
+
+        // then
+        assert_eq!(transport.cost(&logs), 75);
+        assert_eq!(transport.cost(&number), 1);
+    }
+
+    #[test]
+    fn should_limit_concurrent_requests() {
+        futures::future::lazy(|| {
+            // given
+            let mut limiter = limiter(1.0, 1.0, Some(1));
+
+            // when
+            let first = limiter.try_acquire();
+            let second = limiter.try_acquire();
+            limiter.release();
+
+            // then
+            assert!(first);
+            assert!(!second);
+            assert!(limiter.waiting.is_empty());
+            assert!(limiter.try_acquire());
+            Ok::<_, ()>(())
+        })
+        .wait()
+        .unwrap();
+    }
+
+    #[test]
+    fn should_send_a_request() {
+        // given
+        let mut transport = TestTransport::default();
+        transport.set_response(rpc::Value::String("0x1".into()));
+        let limited = RateLimit::new(transport.clone(), 1);
+
+        // when
+        let result = limited.execute("eth_blockNumber", vec![]).wait();
+
+        // then
+        transport.assert_request("eth_blockNumber", &[]);
+        transport.assert_no_more_requests();
+        assert_eq!(result, Ok(rpc::Value::String("0x1".into())));
+    }
+
+    #[test]
+    fn should_reserve_tokens_when_polled() {
+        // given
+        let limited = RateLimit::new(TestTransport::default(), 1);
+
+        // when
+        let request = limited.execute("eth_blockNumber", vec![]);
+        let tokens = limited.limiter.lock().tokens;
+        drop(request);
+
+        // then
+        assert_eq!(tokens, 1.0);
+        assert_eq!(limited.limiter.lock().tokens, 1.0);
+    }
+}
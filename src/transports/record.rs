@@ -0,0 +1,429 @@
+//! Record/replay Transports
+
+use crate::rpc;
+use crate::transports::Result;
+use crate::{BatchTransport, Error, RequestId, Transport};
+use futures::{self, Async, Future, Poll};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{atomic, Arc};
+
+/// A recorded request together with its response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Called method
+    pub method: String,
+    /// Call parameters
+    pub params: rpc::Params,
+    /// Successful result (`null` if missing)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<rpc::Value>,
+    /// RPC error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<rpc::Error>,
+}
+
+impl Interaction {
+    fn request(request: &rpc::Call) -> Option<(String, rpc::Params)> {
+        match *request {
+            rpc::Call::MethodCall(ref call) => Some((call.method.clone(), call.params.clone())),
+            _ => None,
+        }
+    }
+
+    fn response(&self) -> Result<rpc::Value> {
+        match self.error {
+            Some(ref error) => Err(Error::Rpc(error.clone())),
+            None => Ok(self.result.clone().unwrap_or(rpc::Value::Null)),
+        }
+    }
+}
+
+/// Reads interactions from a JSON fixture file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<Interaction>> {
+    let file = fs::File::open(path)?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+#[derive(Debug)]
+struct Log {
+    path: PathBuf,
+    interactions: Mutex<Vec<Interaction>>,
+}
+
+impl Log {
+    /// Records responses and rewrites the fixture file.
+    ///
+    /// Only successful responses and RPC errors are recorded, transport failures are not
+    /// a property of the node and would make the fixture flaky.
+    fn record<I>(&self, responses: I)
+    where
+        I: IntoIterator<Item = ((String, rpc::Params), Result<rpc::Value>)>,
+    {
+        let mut interactions = self.interactions.lock();
+        for ((method, params), response) in responses {
+            let (result, error) = match response {
+                Ok(result) => (Some(result), None),
+                Err(Error::Rpc(error)) => (None, Some(error)),
+                Err(_) => continue,
+            };
+            interactions.push(Interaction {
+                method,
+                params,
+                result,
+                error,
+            });
+        }
+
+        let written = serde_json::to_vec_pretty(&*interactions)
+            .map_err(Into::into)
+            .and_then(|json| fs::write(&self.path, json));
+        if let Err(err) = written {
+            log::warn!("Unable to write fixture {:?}: {:?}", self.path, err);
+        }
+    }
+}
+
+/// Transport recording all requests and responses of the underlying transport to a fixture file.
+///
+/// The file is rewritten after every response and can be served with `Replay`.
+#[derive(Debug, Clone)]
+pub struct Record<T> {
+    transport: T,
+    log: Arc<Log>,
+}
+
+impl<T: Transport> Record<T> {
+    /// Creates new Record transport writing to given file.
+    pub fn new<P: AsRef<Path>>(transport: T, path: P) -> Self {
+        Record {
+            transport,
+            log: Arc::new(Log {
+                path: path.as_ref().into(),
+                interactions: Default::default(),
+            }),
+        }
+    }
+
+    /// Returns interactions recorded so far.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.log.interactions.lock().clone()
+    }
+}
+
+impl<T: Transport> Transport for Record<T> {
+    type Out = Recording<T::Out>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        Recording {
+            request: Interaction::request(&request),
+            future: self.transport.send(id, request),
+            log: self.log.clone(),
+        }
+    }
+}
+
+impl<T: BatchTransport> BatchTransport for Record<T> {
+    type Batch = RecordingBatch<T::Batch>;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let requests = requests.into_iter().collect::<Vec<_>>();
+        RecordingBatch {
+            requests: requests
+                .iter()
+                .map(|&(_, ref request)| Interaction::request(request))
+                .collect(),
+            future: self.transport.send_batch(requests),
+            log: self.log.clone(),
+        }
+    }
+}
+
+/// A future representing a recorded request.
+#[derive(Debug)]
+pub struct Recording<F> {
+    future: F,
+    request: Option<(String, rpc::Params)>,
+    log: Arc<Log>,
+}
+
+impl<F: Future<Item = rpc::Value, Error = Error>> Future for Recording<F> {
+    type Item = rpc::Value;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let response = match self.future.poll() {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(value)) => Ok(value),
+            Err(err) => Err(err),
+        };
+
+        if let Some(request) = self.request.take() {
+            self.log.record(Some((request, response.clone())));
+        }
+        response.map(Async::Ready)
+    }
+}
+
+/// A future representing a recorded batch request.
+#[derive(Debug)]
+pub struct RecordingBatch<F> {
+    future: F,
+    requests: Vec<Option<(String, rpc::Params)>>,
+    log: Arc<Log>,
+}
+
+impl<F: Future<Item = Vec<Result<rpc::Value>>, Error = Error>> Future for RecordingBatch<F> {
+    type Item = Vec<Result<rpc::Value>>;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let responses = try_ready!(self.future.poll());
+        let requests = self.requests.drain(..);
+        self.log.record(
+            requests
+                .zip(responses.iter().cloned())
+                .filter_map(|(request, response)| request.map(|request| (request, response))),
+        );
+        Ok(Async::Ready(responses))
+    }
+}
+
+/// Transport serving recorded interactions, without any connection.
+///
+/// Each interaction is served once, in the recorded order for identical requests.
+/// Requests not present in the fixture fail with `Error::Transport`.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    id: Arc<atomic::AtomicUsize>,
+    interactions: Arc<Mutex<Vec<Option<Interaction>>>>,
+}
+
+impl Replay {
+    /// Creates new Replay transport serving given interactions.
+    pub fn new(interactions: Vec<Interaction>) -> Self {
+        Replay {
+            id: Arc::new(atomic::AtomicUsize::new(1)),
+            interactions: Arc::new(Mutex::new(interactions.into_iter().map(Some).collect())),
+        }
+    }
+
+    /// Creates new Replay transport serving interactions from given fixture file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(load(path)?))
+    }
+
+    /// Returns interactions which were not requested yet.
+    pub fn remaining(&self) -> Vec<Interaction> {
+        self.interactions.lock().iter().filter_map(Clone::clone).collect()
+    }
+
+    /// Panics if any of the interactions was not requested.
+    pub fn assert_all_used(&self) {
+        let remaining = self.remaining();
+        assert!(remaining.is_empty(), "Expected more requests: {:?}", remaining);
+    }
+
+    pub(crate) fn respond(&self, request: &rpc::Call) -> Result<rpc::Value> {
+        let (method, params) = Interaction::request(request)
+            .ok_or_else(|| Error::Transport(format!("Unsupported request: {:?}", request)))?;
+
+        let mut interactions = self.interactions.lock();
+        let interaction = interactions
+            .iter_mut()
+            .find(|interaction| match **interaction {
+                Some(ref interaction) => interaction.method == method && interaction.params == params,
+                None => false,
+            })
+            .and_then(Option::take)
+            .ok_or_else(|| {
+                Error::Transport(format!(
+                    "Unexpected request: {} {}",
+                    method,
+                    serde_json::to_string(&params).unwrap_or_default()
+                ))
+            })?;
+
+        interaction.response()
+    }
+}
+
+impl Transport for Replay {
+    type Out = crate::Result<rpc::Value>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        let id = self.id.fetch_add(1, atomic::Ordering::AcqRel);
+        let request = crate::helpers::build_request(id, method, params);
+
+        (id, request)
+    }
+
+    fn send(&self, _id: RequestId, request: rpc::Call) -> Self::Out {
+        Box::new(futures::done(self.respond(&request)))
+    }
+}
+
+impl BatchTransport for Replay {
+    type Batch = crate::Result<Vec<Result<rpc::Value>>>;
+
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let responses = requests
+            .into_iter()
+            .map(|(_, request)| self.respond(&request))
+            .collect();
+        Box::new(futures::finished(responses))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Interaction, Record, Replay};
+    use crate::api::{Bool, Eth, Namespace, Web3};
+    use crate::contract::{Contract, Options};
+    use crate::helpers::tests::TestTransport;
+    use crate::rpc;
+    use crate::types::{Address, BlockNumber, H256, U256};
+    use crate::{Error, Transport};
+    use futures::Future;
+    use std::fs;
+    use std::str::FromStr;
+
+    fn fixture(name: &str) -> Replay {
+        Replay::from_file(format!("{}/tests/res/{}.json", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+    }
+
+    #[test]
+    fn should_record_and_replay_interactions() {
+        // given
+        let path = std::env::temp_dir().join(format!("web3-fixture-{}.json", std::process::id()));
+        let mut transport = TestTransport::default();
+        transport.add_response(rpc::Value::String("0x10".into()));
+        transport.add_response(rpc::Value::String("0x1".into()));
+        let record = Record::new(transport, &path);
+
+        // when
+        let number = Web3::new(&record).eth().block_number().wait();
+        let version = record.execute("net_version", vec![]).wait();
+        let replay = Replay::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // then
+        assert_eq!(number, Ok(U256::from(16)));
+        assert_eq!(version, Ok(rpc::Value::String("0x1".into())));
+        assert_eq!(record.interactions().len(), 2);
+        assert_eq!(Web3::new(&replay).eth().block_number().wait(), Ok(U256::from(16)));
+        assert_eq!(
+            replay.execute("net_version", vec![]).wait(),
+            Ok(rpc::Value::String("0x1".into()))
+        );
+        replay.assert_all_used();
+    }
+
+    #[test]
+    fn should_fail_on_unexpected_requests() {
+        // given
+        let replay = Replay::new(vec![Interaction {
+            method: "eth_blockNumber".into(),
+            params: rpc::Params::Array(vec![]),
+            result: Some(rpc::Value::String("0x10".into())),
+            error: None,
+        }]);
+
+        // when
+        let result = replay.execute("eth_gasPrice", vec![]).wait();
+
+        // then
+        assert_eq!(
+            result,
+            Err(Error::Transport("Unexpected request: eth_gasPrice []".into()))
+        );
+        assert_eq!(replay.remaining().len(), 1);
+    }
+
+    #[test]
+    fn should_replay_rpc_errors() {
+        // given
+        let error = rpc::Error::invalid_params("missing value");
+        let replay = Replay::new(vec![Interaction {
+            method: "eth_call".into(),
+            params: rpc::Params::Array(vec![]),
+            result: None,
+            error: Some(error.clone()),
+        }]);
+
+        // when
+        let result = replay.execute("eth_call", vec![]).wait();
+
+        // then
+        assert_eq!(result, Err(Error::Rpc(error)));
+    }
+
+    #[test]
+    fn should_replay_receipt_proof() {
+        // given
+        let replay = fixture("receipt_proof");
+        let hash = H256::from_str("c6ef2fc5426d6ad6fd9e2a26abeab0aa2411b7ab17f30a99d3cb96aed1d1055b").unwrap();
+
+        // when
+        let (index, proof, header_hash) = Bool::new(&replay).receipt_proof(hash).wait().unwrap().unwrap();
+
+        // then
+        replay.assert_all_used();
+        assert_eq!(index, 0);
+        assert!(!proof.is_empty());
+        assert_eq!(
+            header_hash,
+            H256::from_str("beab0aa2411b7ab17f30a99d3cb9c6ef2fc5426d6ad6fd9e2a26a6aed1d1055b").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_replay_contract_query_and_call() {
+        // given
+        let replay = fixture("contract");
+        let token = Contract::from_json(
+            Eth::new(&replay),
+            Address::from_low_u64_be(1),
+            include_bytes!("../contract/res/token.json"),
+        )
+        .unwrap();
+
+        // when
+        let balance: U256 = token
+            .query(
+                "balanceOf",
+                Address::from_low_u64_be(5),
+                None,
+                Options::default(),
+                BlockNumber::Latest,
+            )
+            .wait()
+            .unwrap();
+        let transfer = token
+            .call(
+                "transfer",
+                (Address::from_low_u64_be(6), U256::from(1_000)),
+                Address::from_low_u64_be(5),
+                Options::default(),
+            )
+            .wait()
+            .unwrap();
+
+        // then
+        replay.assert_all_used();
+        assert_eq!(balance, U256::from(1_000_000));
+        assert_eq!(transfer, H256::from_low_u64_be(0x77));
+    }
+}
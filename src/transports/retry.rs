@@ -0,0 +1,554 @@
+//! Retrying Transport
+
+use crate::rpc;
+use crate::transports::{backoff, Result};
+use crate::{BatchTransport, Error, RequestId, Transport};
+use futures::{Async, Future, Poll};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_timer::{Sleep, Timer};
+
+/// Read-only methods which are safe to send more than once.
+///
+/// Methods changing node state (e.g. `eth_sendRawTransaction`, `eth_newFilter`) or consuming
+/// it (`eth_getFilterChanges`) are deliberately left out.
+pub const IDEMPOTENT_METHODS: &[&str] = &[
+    "eth_accounts",
+    "eth_blockNumber",
+    "eth_call",
+    "eth_chainId",
+    "eth_coinbase",
+    "eth_createAccessList",
+    "eth_estimateGas",
+    "eth_feeHistory",
+    "eth_gasPrice",
+    "eth_getBalance",
+    "eth_getBlockByHash",
+    "eth_getBlockByNumber",
+    "eth_getBlockTransactionCountByHash",
+    "eth_getBlockTransactionCountByNumber",
+    "eth_getCode",
+    "eth_getFilterLogs",
+    "eth_getLogs",
+    "eth_getStorageAt",
+    "eth_getTransactionByBlockHashAndIndex",
+    "eth_getTransactionByBlockNumberAndIndex",
+    "eth_getTransactionByHash",
+    "eth_getTransactionCount",
+    "eth_getTransactionReceipt",
+    "eth_getUncleByBlockHashAndIndex",
+    "eth_getUncleByBlockNumberAndIndex",
+    "eth_getUncleCountByBlockHash",
+    "eth_getUncleCountByBlockNumber",
+    "eth_hashrate",
+    "eth_maxPriorityFeePerGas",
+    "eth_mining",
+    "eth_protocolVersion",
+    "eth_syncing",
+    "net_listening",
+    "net_peerCount",
+    "net_version",
+    "trace_block",
+    "trace_call",
+    "trace_filter",
+    "trace_get",
+    "trace_replayBlockTransactions",
+    "trace_replayTransaction",
+    "trace_transaction",
+    "web3_clientVersion",
+    "web3_sha3",
+];
+
+/// Retry settings of the `Retry` transport.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximal number of retries of a single request.
+    pub max_retries: usize,
+    /// Delay before the first retry, doubled after each failed attempt.
+    pub initial_delay: Duration,
+    /// Upper bound of the delay between retries.
+    pub max_delay: Duration,
+    /// Whether retry delays are randomized.
+    pub jitter: bool,
+    /// Methods that may be retried, requests calling any other method are sent only once.
+    pub idempotent_methods: BTreeSet<String>,
+    /// RPC error codes considered transient (e.g. `-32005`, limit exceeded).
+    pub rpc_codes: Vec<i64>,
+    /// Parts of RPC error messages considered transient (e.g. `header not found`).
+    pub rpc_messages: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+            idempotent_methods: IDEMPOTENT_METHODS.iter().map(|method| method.to_string()).collect(),
+            rpc_codes: vec![-32005],
+            rpc_messages: vec!["header not found".into(), "unknown block".into()],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns true if the method may be sent more than once.
+    pub fn is_idempotent(&self, method: &str) -> bool {
+        self.idempotent_methods.contains(method)
+    }
+
+    /// Returns true if a request failing with given error may succeed when retried.
+    ///
+    /// Transport and IO errors are transient, as are HTTP `429` and `5xx` responses.
+    /// RPC errors are transient if their code or message is listed in the policy.
+    pub fn is_transient(&self, error: &Error) -> bool {
+        match *error {
+            Error::HttpStatus(status, _) => status == 429 || (500..600).contains(&status),
+            Error::Transport(_) | Error::Io(_) | Error::Unreachable => true,
+            Error::Rpc(ref error) => {
+                let message = error.message.to_lowercase();
+                self.rpc_codes.contains(&error.code.code())
+                    || self.rpc_messages.iter().any(|m| message.contains(&m.to_lowercase()))
+            }
+            _ => false,
+        }
+    }
+
+    /// Delay before given retry attempt (starting from 0).
+    pub fn delay(&self, attempt: usize) -> Duration {
+        backoff::delay(self.initial_delay, self.max_delay, self.jitter, attempt)
+    }
+}
+
+fn method(request: &rpc::Call) -> Option<&str> {
+    match *request {
+        rpc::Call::MethodCall(ref call) => Some(&call.method),
+        rpc::Call::Notification(ref notification) => Some(&notification.method),
+        rpc::Call::Invalid { .. } => None,
+    }
+}
+
+/// Transport retrying requests failing with transient errors.
+///
+/// Requests are retried with exponential backoff, only if their method is idempotent
+/// according to the `RetryPolicy`, so that e.g. `eth_sendRawTransaction` is never sent twice.
+#[derive(Debug, Clone)]
+pub struct Retry<T> {
+    transport: T,
+    policy: Arc<RetryPolicy>,
+    timer: Timer,
+}
+
+impl<T: Transport> Retry<T> {
+    /// Creates new Retry transport with default policy.
+    pub fn new(transport: T) -> Self {
+        Self::with_policy(transport, Default::default())
+    }
+
+    /// Creates new Retry transport with given policy.
+    pub fn with_policy(transport: T, policy: RetryPolicy) -> Self {
+        Retry {
+            transport,
+            policy: Arc::new(policy),
+            timer: super::timer::shared(),
+        }
+    }
+
+    /// Sets the timer used to delay retries, a timer shared by all transports is used by default.
+    pub fn with_timer(mut self, timer: Timer) -> Self {
+        self.timer = timer;
+        self
+    }
+
+    /// Returns the retry policy.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Returns the underlying transport.
+    pub fn inner(&self) -> &T {
+        &self.transport
+    }
+
+    fn backoff<'a, I: IntoIterator<Item = &'a rpc::Call>>(&self, requests: I) -> Backoff {
+        let idempotent = requests
+            .into_iter()
+            .all(|request| method(request).map_or(false, |m| self.policy.is_idempotent(m)));
+        Backoff {
+            policy: self.policy.clone(),
+            timer: self.timer.clone(),
+            retries: if idempotent { self.policy.max_retries } else { 0 },
+            attempt: 0,
+            sleep: None,
+        }
+    }
+}
+
+impl<T: Transport> Transport for Retry<T> {
+    type Out = RetrySend<T>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        self.transport.prepare(method, params)
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        let backoff = self.backoff(Some(&request));
+        RetrySend {
+            future: Some(self.transport.send(id, request.clone())),
+            transport: self.transport.clone(),
+            id,
+            request,
+            backoff,
+        }
+    }
+}
+
+impl<T: BatchTransport> BatchTransport for Retry<T> {
+    type Batch = RetryBatch<T>;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let requests = requests.into_iter().collect::<Vec<_>>();
+        let backoff = self.backoff(requests.iter().map(|&(_, ref request)| request));
+        RetryBatch {
+            future: Some(self.transport.send_batch(requests.clone())),
+            transport: self.transport.clone(),
+            requests,
+            backoff,
+        }
+    }
+}
+
+/// Schedules retries of a single request (or batch).
+#[derive(Debug)]
+struct Backoff {
+    policy: Arc<RetryPolicy>,
+    timer: Timer,
+    retries: usize,
+    attempt: usize,
+    sleep: Option<Sleep>,
+}
+
+impl Backoff {
+    /// Returns true (and starts waiting) if a request failed with given error should be retried.
+    fn schedule(&mut self, error: &Error) -> bool {
+        if self.attempt >= self.retries || !self.policy.is_transient(error) {
+            return false;
+        }
+
+        let delay = self.policy.delay(self.attempt);
+        self.attempt += 1;
+        log::debug!("Retrying in {:?} (attempt {}): {:?}", delay, self.attempt, error);
+        self.sleep = Some(self.timer.sleep(delay));
+        true
+    }
+
+    fn poll(&mut self) -> Async<()> {
+        if let Some(ref mut sleep) = self.sleep {
+            match sleep.poll() {
+                Ok(Async::NotReady) => return Async::NotReady,
+                Ok(Async::Ready(())) => {}
+                Err(err) => log::warn!("Retrying without delay: {:?}", err),
+            }
+        }
+        self.sleep = None;
+        Async::Ready(())
+    }
+}
+
+/// A future representing a retried request.
+#[derive(Debug)]
+pub struct RetrySend<T: Transport> {
+    transport: T,
+    id: RequestId,
+    request: rpc::Call,
+    future: Option<T::Out>,
+    backoff: Backoff,
+}
+
+impl<T: Transport> Future for RetrySend<T> {
+    type Item = rpc::Value;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            if let Some(ref mut future) = self.future {
+                match future.poll() {
+                    Ok(result) => return Ok(result),
+                    Err(err) => {
+                        if !self.backoff.schedule(&err) {
+                            return Err(err);
+                        }
+                    }
+                }
+            }
+            self.future = None;
+
+            if let Async::NotReady = self.backoff.poll() {
+                return Ok(Async::NotReady);
+            }
+            self.future = Some(self.transport.send(self.id, self.request.clone()));
+        }
+    }
+}
+
+/// A future representing a retried batch request.
+///
+/// The whole batch is retried if it fails or any of its requests fails with a transient error.
+#[derive(Debug)]
+pub struct RetryBatch<T: BatchTransport> {
+    transport: T,
+    requests: Vec<(RequestId, rpc::Call)>,
+    future: Option<T::Batch>,
+    backoff: Backoff,
+}
+
+impl<T: BatchTransport> Future for RetryBatch<T> {
+    type Item = Vec<Result<rpc::Value>>;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            if let Some(ref mut future) = self.future {
+                match future.poll() {
+                    Ok(Async::Ready(results)) => {
+                        let backoff = &mut self.backoff;
+                        let retry = results
+                            .iter()
+                            .filter_map(|result| result.as_ref().err())
+                            .any(|err| backoff.schedule(err));
+                        if !retry {
+                            return Ok(Async::Ready(results));
+                        }
+                    }
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(err) => {
+                        if !self.backoff.schedule(&err) {
+                            return Err(err);
+                        }
+                    }
+                }
+            }
+            self.future = None;
+
+            if let Async::NotReady = self.backoff.poll() {
+                return Ok(Async::NotReady);
+            }
+            self.future = Some(self.transport.send_batch(self.requests.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Retry, RetryPolicy};
+    use crate::rpc;
+    use crate::transports::Result;
+    use crate::{BatchTransport, Error, RequestId, Transport};
+    use futures::{self, Future};
+    use parking_lot::Mutex;
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[derive(Debug, Default, Clone)]
+    struct ScriptedTransport {
+        requests: Arc<Mutex<usize>>,
+        responses: Arc<Mutex<VecDeque<Result<rpc::Value>>>>,
+    }
+
+    impl ScriptedTransport {
+        fn new(responses: Vec<Result<rpc::Value>>) -> Self {
+            ScriptedTransport {
+                requests: Default::default(),
+                responses: Arc::new(Mutex::new(responses.into())),
+            }
+        }
+
+        fn requests(&self) -> usize {
+            *self.requests.lock()
+        }
+    }
+
+    impl Transport for ScriptedTransport {
+        type Out = crate::Result<rpc::Value>;
+
+        fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+            (1, crate::helpers::build_request(1, method, params))
+        }
+
+        fn send(&self, _id: RequestId, _request: rpc::Call) -> Self::Out {
+            *self.requests.lock() += 1;
+            let response = self.responses.lock().pop_front().unwrap_or(Err(Error::Internal));
+            Box::new(futures::done(response))
+        }
+    }
+
+    impl BatchTransport for ScriptedTransport {
+        type Batch = crate::Result<Vec<Result<rpc::Value>>>;
+
+        fn send_batch<T>(&self, requests: T) -> Self::Batch
+        where
+            T: IntoIterator<Item = (RequestId, rpc::Call)>,
+        {
+            let requests = requests.into_iter().collect::<Vec<_>>();
+            *self.requests.lock() += 1;
+            let mut responses = self.responses.lock();
+            let results = (0..requests.len())
+                .map(|_| responses.pop_front().unwrap_or(Err(Error::Internal)))
+                .collect();
+            Box::new(futures::finished(results))
+        }
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            initial_delay: Duration::from_millis(0),
+            ..Default::default()
+        }
+    }
+
+    fn rpc_error(code: i64, message: &str) -> Error {
+        Error::Rpc(rpc::Error {
+            code: rpc::ErrorCode::ServerError(code),
+            message: message.into(),
+            data: None,
+        })
+    }
+
+    #[test]
+    fn should_classify_errors() {
+        let policy = RetryPolicy::default();
+
+        assert!(policy.is_transient(&Error::Transport("connection reset".into())));
+        assert!(policy.is_transient(&Error::Io(::std::io::ErrorKind::TimedOut.into())));
+        assert!(policy.is_transient(&Error::HttpStatus(429, "Too Many Requests".into())));
+        assert!(policy.is_transient(&Error::HttpStatus(503, "Service Unavailable".into())));
+        assert!(!policy.is_transient(&Error::HttpStatus(401, "Unauthorized".into())));
+        assert!(policy.is_transient(&rpc_error(-32000, "Header not found")));
+        assert!(policy.is_transient(&rpc_error(-32005, "limit exceeded")));
+        assert!(!policy.is_transient(&rpc_error(-32000, "execution reverted")));
+        assert!(!policy.is_transient(&Error::Decoder("invalid hex".into())));
+    }
+
+    #[test]
+    fn should_grow_delay_up_to_max_delay() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..Default::default()
+        };
+
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(3), Duration::from_millis(800));
+        assert_eq!(policy.delay(10), Duration::from_secs(5));
+        let jittered = RetryPolicy::default().delay(3);
+        assert!(jittered >= Duration::from_millis(400) && jittered <= Duration::from_millis(800));
+    }
+
+    #[test]
+    fn should_retry_transient_errors() {
+        // given
+        let transport = ScriptedTransport::new(vec![
+            Err(Error::Transport("connection reset".into())),
+            Err(rpc_error(-32000, "header not found")),
+            Ok(rpc::Value::String("0x1".into())),
+        ]);
+        let retry = Retry::with_policy(transport.clone(), policy());
+
+        // when
+        let result = retry.execute("eth_blockNumber", vec![]).wait();
+
+        // then
+        assert_eq!(result, Ok(rpc::Value::String("0x1".into())));
+        assert_eq!(transport.requests(), 3);
+    }
+
+    #[test]
+    fn should_give_up_after_max_retries() {
+        // given
+        let transport = ScriptedTransport::new(vec![Err(Error::Transport("connection reset".into())); 4]);
+        let retry = Retry::with_policy(
+            transport.clone(),
+            RetryPolicy {
+                max_retries: 2,
+                ..policy()
+            },
+        );
+
+        // when
+        let result = retry.execute("eth_blockNumber", vec![]).wait();
+
+        // then
+        assert_eq!(result, Err(Error::Transport("connection reset".into())));
+        assert_eq!(transport.requests(), 3);
+    }
+
+    #[test]
+    fn should_not_retry_non_idempotent_methods() {
+        // given
+        let transport = ScriptedTransport::new(vec![
+            Err(Error::Transport("connection reset".into())),
+            Ok(rpc::Value::String("0x1".into())),
+        ]);
+        let retry = Retry::with_policy(transport.clone(), policy());
+
+        // when
+        let result = retry
+            .execute("eth_sendRawTransaction", vec![rpc::Value::String("0x00".into())])
+            .wait();
+
+        // then
+        assert_eq!(result, Err(Error::Transport("connection reset".into())));
+        assert_eq!(transport.requests(), 1);
+    }
+
+    #[test]
+    fn should_not_retry_permanent_errors() {
+        // given
+        let transport = ScriptedTransport::new(vec![
+            Err(rpc_error(-32000, "execution reverted")),
+            Ok(rpc::Value::String("0x1".into())),
+        ]);
+        let retry = Retry::with_policy(transport.clone(), policy());
+
+        // when
+        let result = retry.execute("eth_call", vec![]).wait();
+
+        // then
+        assert_eq!(result, Err(rpc_error(-32000, "execution reverted")));
+        assert_eq!(transport.requests(), 1);
+    }
+
+    #[test]
+    fn should_retry_a_batch() {
+        // given
+        let transport = ScriptedTransport::new(vec![
+            Ok(rpc::Value::String("0x1".into())),
+            Err(rpc_error(-32000, "header not found")),
+            Ok(rpc::Value::String("0x1".into())),
+            Ok(rpc::Value::String("0x2".into())),
+        ]);
+        let retry = Retry::with_policy(transport.clone(), policy());
+        let requests = vec![
+            retry.prepare("eth_blockNumber", vec![]),
+            retry.prepare("eth_getBlockByNumber", vec![]),
+        ];
+
+        // when
+        let result = retry.send_batch(requests).wait();
+
+        // then
+        assert_eq!(
+            result,
+            Ok(vec![
+                Ok(rpc::Value::String("0x1".into())),
+                Ok(rpc::Value::String("0x2".into()))
+            ])
+        );
+        assert_eq!(transport.requests(), 2);
+    }
+}
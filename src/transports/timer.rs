@@ -0,0 +1,24 @@
+//! Timer shared by the transports delaying requests.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio_timer::Timer;
+
+/// Returns the timer shared by all transports, started on first use.
+///
+/// Every `tokio_timer::Timer` runs on its own thread, so transports use this one
+/// unless given another timer explicitly.
+pub fn shared() -> Timer {
+    static TIMER: OnceLock<Timer> = OnceLock::new();
+
+    TIMER
+        .get_or_init(|| {
+            tokio_timer::wheel()
+                .tick_duration(Duration::from_millis(1))
+                .num_slots(65_536)
+                .max_timeout(Duration::from_secs(3600))
+                .thread_name("web3-timer")
+                .build()
+        })
+        .clone()
+}
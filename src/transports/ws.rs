@@ -2,20 +2,24 @@
 
 extern crate websocket;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
+use std::mem;
 use std::sync::{atomic, Arc};
+use std::time::Duration;
 
+use self::websocket::r#async::{Client, ClientNew, Stream as AsyncStream};
 use self::websocket::url::Url;
 use self::websocket::{ClientBuilder, OwnedMessage};
 use crate::api::SubscriptionId;
 use crate::helpers;
 use crate::rpc;
+use crate::transports::backoff;
 use crate::transports::shared::{EventLoopHandle, Response};
 use crate::transports::tokio_core::reactor;
 use crate::transports::Result;
 use crate::{BatchTransport, DuplexTransport, Error, RequestId, Transport};
 use futures::sync::{mpsc, oneshot};
-use futures::{self, Future, Sink, Stream};
+use futures::{self, Async, AsyncSink, Future, Poll, Sink, Stream};
 use parking_lot::Mutex;
 
 impl From<websocket::WebSocketError> for Error {
@@ -34,16 +38,400 @@ type Pending = oneshot::Sender<Result<Vec<Result<rpc::Value>>>>;
 
 type Subscription = mpsc::UnboundedSender<rpc::Value>;
 
+type Connection = Client<Box<dyn AsyncStream + Send>>;
+
 /// A future representing pending WebSocket request, resolves to a response.
 pub type WsTask<F> = Response<F, Vec<Result<rpc::Value>>>;
 
+/// Reconnection settings of the WebSocket transport.
+///
+/// After the connection drops the transport reconnects with exponential backoff, re-issues
+/// all active `eth_subscribe` calls and keeps delivering notifications to the existing
+/// `SubscriptionStream`s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reconnect {
+    /// Delay before the first reconnection attempt, doubled after each failed attempt.
+    pub initial_delay: Duration,
+    /// Upper bound of the delay between reconnection attempts.
+    pub max_delay: Duration,
+    /// Number of consecutive failed attempts after which the transport gives up (None to never give up).
+    pub max_attempts: Option<usize>,
+    /// Whether requests in flight when the connection drops are re-sent after reconnecting.
+    ///
+    /// Disabled by default, since re-sending non-idempotent calls (e.g. `eth_sendTransaction`)
+    /// is unsafe. When disabled such requests fail with a transport error.
+    pub retry_requests: bool,
+    /// Whether reconnection delays are randomized.
+    pub jitter: bool,
+}
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Reconnect {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(30),
+            max_attempts: None,
+            retry_requests: false,
+            jitter: true,
+        }
+    }
+}
+
+impl Reconnect {
+    /// Never reconnect, fail all requests once the connection drops.
+    pub fn disabled() -> Self {
+        Reconnect {
+            max_attempts: Some(0),
+            ..Default::default()
+        }
+    }
+
+    fn delay(&self, attempt: usize) -> Duration {
+        backoff::delay(self.initial_delay, self.max_delay, self.jitter, attempt)
+    }
+}
+
+#[derive(Debug)]
+struct PendingRequest {
+    sender: Pending,
+    /// Ids of the requests, to match them with the responses.
+    ids: Vec<rpc::Id>,
+    /// Serialized request, re-sent after reconnecting.
+    request: String,
+    /// Params of `eth_subscribe` calls, replayed after reconnecting.
+    subscription: Option<rpc::Params>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    pending: BTreeMap<RequestId, PendingRequest>,
+    /// Notification streams by client subscription id (the one returned by the first `eth_subscribe`).
+    subscriptions: BTreeMap<SubscriptionId, Subscription>,
+    /// `eth_subscribe` params by client subscription id.
+    subscription_params: BTreeMap<SubscriptionId, rpc::Params>,
+    /// Current server subscription ids by client subscription id, if different.
+    server_ids: BTreeMap<SubscriptionId, String>,
+    /// Client subscription ids by current server subscription id, if different.
+    client_ids: BTreeMap<String, SubscriptionId>,
+    /// Client subscription ids of replayed `eth_subscribe` calls by request id.
+    resubscriptions: BTreeMap<RequestId, SubscriptionId>,
+}
+
+impl Shared {
+    fn fail_pending(&mut self, error: &Error) {
+        for (id, request) in mem::replace(&mut self.pending, Default::default()) {
+            log::trace!("Failing request (id: {:?}): {:?}", id, error);
+            let _ = request.sender.send(Err(error.clone()));
+        }
+    }
+
+    fn remove_subscription(&mut self, id: &SubscriptionId) {
+        self.subscriptions.remove(id);
+        self.subscription_params.remove(id);
+        if let Some(server_id) = self.server_ids.remove(id) {
+            self.client_ids.remove(&server_id);
+        }
+    }
+
+    fn handle_notification(&mut self, notification: rpc::Notification) {
+        if let rpc::Params::Map(params) = notification.params {
+            let id = params.get("subscription");
+            let result = params.get("result");
+
+            if let (Some(&rpc::Value::String(ref id)), Some(result)) = (id, result) {
+                let id = self
+                    .client_ids
+                    .get(id)
+                    .cloned()
+                    .unwrap_or_else(|| SubscriptionId::from(id.clone()));
+                if let Some(stream) = self.subscriptions.get(&id) {
+                    if stream.unbounded_send(result.clone()).is_err() {
+                        log::warn!("Sending a notification to deallocated stream (id: {:?})", id);
+                        self.remove_subscription(&id);
+                    }
+                } else {
+                    log::warn!("Got notification for unknown subscription (id: {:?})", id);
+                }
+            } else {
+                log::error!("Got unsupported notification (id: {:?})", id);
+            }
+        }
+    }
+
+    fn handle_response(&mut self, outputs: Vec<rpc::Output>) {
+        let resubscription = match outputs.first().map(helpers::output_id) {
+            Some(&rpc::Id::Num(num)) if outputs.len() == 1 => self.resubscriptions.remove(&(num as usize)),
+            _ => None,
+        };
+
+        if let Some(client_id) = resubscription {
+            match outputs.into_iter().next() {
+                Some(rpc::Output::Success(rpc::Success {
+                    result: rpc::Value::String(server_id),
+                    ..
+                })) => {
+                    log::debug!("Resubscribed {:?} as {:?}", client_id, server_id);
+                    if let Some(previous) = self.server_ids.insert(client_id.clone(), server_id.clone()) {
+                        self.client_ids.remove(&previous);
+                    }
+                    self.client_ids.insert(server_id, client_id);
+                }
+                output => {
+                    log::error!("Failed to resubscribe {:?}: {:?}", client_id, output);
+                    // Close the stream, so that consumers are aware the subscription is gone.
+                    self.remove_subscription(&client_id);
+                }
+            }
+            return;
+        }
+
+        let id = helpers::pending_request(&self.pending, |request| &request.ids[..], &outputs);
+        if let Some(request) = id.and_then(|id| self.pending.remove(&id)) {
+            let results = helpers::to_results_by_id(&request.ids, outputs);
+            if let (Some(params), Some(&Ok(rpc::Value::String(ref id)))) = (request.subscription, results.get(0)) {
+                self.subscription_params.insert(id.clone().into(), params);
+            }
+
+            log::trace!("Responding to (ids: {:?}) with {:?}", request.ids, results);
+            if let Err(err) = request.sender.send(Ok(results)) {
+                log::warn!("Sending a response to deallocated channel: {:?}", err);
+            }
+        } else {
+            log::warn!("Got response for unknown request: {:?}", outputs);
+        }
+    }
+}
+
+enum ConnectionState {
+    Connecting(ClientNew<Box<dyn AsyncStream + Send>>),
+    Connected(Connection),
+    Waiting(reactor::Timeout),
+    Closed,
+}
+
+/// Maintains the connection, reconnecting when it drops.
+struct WsConnection {
+    url: Url,
+    handle: reactor::Handle,
+    reconnect: Reconnect,
+    id: Arc<atomic::AtomicUsize>,
+    shared: Arc<Mutex<Shared>>,
+    write_receiver: mpsc::UnboundedReceiver<OwnedMessage>,
+    outgoing: VecDeque<OwnedMessage>,
+    state: ConnectionState,
+    attempts: usize,
+}
+
+impl WsConnection {
+    fn connect(&self) -> ConnectionState {
+        log::trace!("Connecting to: {:?}", self.url);
+        ConnectionState::Connecting(ClientBuilder::from_url(&self.url).async_connect(None, &self.handle))
+    }
+
+    fn on_connected(&mut self) {
+        log::debug!("Connected to: {:?}", self.url);
+        let reconnected = self.attempts > 0 || !self.shared.lock().subscription_params.is_empty();
+        self.attempts = 0;
+        if !reconnected {
+            return;
+        }
+
+        let mut shared = self.shared.lock();
+        if self.reconnect.retry_requests {
+            // All queued requests are pending as well, re-send them only once.
+            while let Ok(Async::Ready(Some(_))) = self.write_receiver.poll() {}
+            for request in shared.pending.values() {
+                self.outgoing.push_back(OwnedMessage::Text(request.request.clone()));
+            }
+        }
+
+        // Forget subscriptions whose streams are gone (or were never created).
+        let closed = shared
+            .subscriptions
+            .iter()
+            .filter(|&(_, stream)| stream.is_closed())
+            .map(|(id, _)| id.clone())
+            .chain(
+                shared
+                    .subscription_params
+                    .keys()
+                    .filter(|id| !shared.subscriptions.contains_key(id))
+                    .cloned(),
+            )
+            .collect::<Vec<_>>();
+        for id in closed {
+            shared.remove_subscription(&id);
+        }
+
+        let subscriptions = shared
+            .subscriptions
+            .keys()
+            .filter_map(|id| {
+                shared
+                    .subscription_params
+                    .get(id)
+                    .map(|params| (id.clone(), params.clone()))
+            })
+            .collect::<Vec<_>>();
+        for (client_id, params) in subscriptions {
+            let id = self.id.fetch_add(1, atomic::Ordering::AcqRel);
+            let request = rpc::Request::Single(rpc::Call::MethodCall(rpc::MethodCall {
+                jsonrpc: Some(rpc::Version::V2),
+                method: "eth_subscribe".into(),
+                params,
+                id: rpc::Id::Num(id as u64),
+            }));
+            log::debug!("[{}] Resubscribing {:?}", id, client_id);
+            shared.resubscriptions.insert(id, client_id);
+            self.outgoing
+                .push_back(OwnedMessage::Text(helpers::to_string(&request)));
+        }
+    }
+
+    fn on_disconnected(&mut self) -> ConnectionState {
+        self.outgoing.clear();
+        let mut shared = self.shared.lock();
+        shared.resubscriptions.clear();
+
+        // All transport instances are gone, nobody is waiting for the connection.
+        if Arc::strong_count(&self.shared) == 1 {
+            return ConnectionState::Closed;
+        }
+
+        let give_up = match self.reconnect.max_attempts {
+            Some(max_attempts) => self.attempts >= max_attempts,
+            None => false,
+        };
+        if give_up || !self.reconnect.retry_requests {
+            shared.fail_pending(&Error::Transport("WebSocket connection closed".into()));
+            while let Ok(Async::Ready(Some(_))) = self.write_receiver.poll() {}
+        }
+        if give_up {
+            log::error!("Giving up reconnecting to: {:?}", self.url);
+            // Dropping the senders ends all subscription streams.
+            shared.subscriptions.clear();
+            return ConnectionState::Closed;
+        }
+
+        let delay = self.reconnect.delay(self.attempts);
+        self.attempts += 1;
+        log::warn!(
+            "Reconnecting to {:?} in {:?} (attempt {})",
+            self.url,
+            delay,
+            self.attempts
+        );
+        match reactor::Timeout::new(delay, &self.handle) {
+            Ok(timeout) => ConnectionState::Waiting(timeout),
+            Err(err) => {
+                log::error!("Unable to schedule reconnection: {:?}", err);
+                drop(shared);
+                self.connect()
+            }
+        }
+    }
+}
+
+/// Polls the connection, returns `Ready` once all transport instances are dropped.
+fn poll_connection(
+    connection: &mut Connection,
+    write_receiver: &mut mpsc::UnboundedReceiver<OwnedMessage>,
+    outgoing: &mut VecDeque<OwnedMessage>,
+    shared: &Mutex<Shared>,
+) -> Poll<(), Error> {
+    loop {
+        match connection.poll()? {
+            Async::Ready(Some(message)) => {
+                log::trace!("Message received: {:?}", message);
+
+                match message {
+                    OwnedMessage::Close(e) => outgoing.push_back(OwnedMessage::Close(e)),
+                    OwnedMessage::Ping(d) => outgoing.push_back(OwnedMessage::Pong(d)),
+                    OwnedMessage::Text(t) => {
+                        if let Ok(notification) = helpers::to_notification_from_slice(t.as_bytes()) {
+                            shared.lock().handle_notification(notification);
+                            continue;
+                        }
+
+                        let outputs = match helpers::to_response_from_slice(t.as_bytes()) {
+                            Ok(rpc::Response::Single(output)) => vec![output],
+                            Ok(rpc::Response::Batch(outputs)) => outputs,
+                            _ => vec![],
+                        };
+                        shared.lock().handle_response(outputs);
+                    }
+                    _ => {}
+                }
+            }
+            Async::Ready(None) => return Err(Error::Transport("WebSocket connection closed".into())),
+            Async::NotReady => break,
+        }
+    }
+
+    loop {
+        let message = match outgoing.pop_front() {
+            Some(message) => message,
+            None => match write_receiver.poll() {
+                Ok(Async::Ready(Some(message))) => message,
+                Ok(Async::Ready(None)) | Err(()) => return Ok(Async::Ready(())),
+                Ok(Async::NotReady) => break,
+            },
+        };
+        if let AsyncSink::NotReady(message) = connection.start_send(message)? {
+            outgoing.push_front(message);
+            break;
+        }
+    }
+    connection.poll_complete()?;
+
+    Ok(Async::NotReady)
+}
+
+impl Future for WsConnection {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            let next_state = match self.state {
+                ConnectionState::Connecting(ref mut future) => match future.poll() {
+                    Ok(Async::Ready((connection, _))) => {
+                        self.on_connected();
+                        ConnectionState::Connected(connection)
+                    }
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(err) => {
+                        log::warn!("Unable to connect to {:?}: {:?}", self.url, err);
+                        self.on_disconnected()
+                    }
+                },
+                ConnectionState::Connected(ref mut connection) => {
+                    match poll_connection(connection, &mut self.write_receiver, &mut self.outgoing, &self.shared) {
+                        Ok(Async::Ready(())) => ConnectionState::Closed,
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Err(err) => {
+                            log::warn!("WebSocketError: {:?}", err);
+                            self.on_disconnected()
+                        }
+                    }
+                }
+                ConnectionState::Waiting(ref mut timeout) => match timeout.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(())) | Err(_) => self.connect(),
+                },
+                ConnectionState::Closed => return Ok(Async::Ready(())),
+            };
+            self.state = next_state;
+        }
+    }
+}
+
 /// WebSocket transport
 #[derive(Debug, Clone)]
 pub struct WebSocket {
     id: Arc<atomic::AtomicUsize>,
     url: Url,
-    pending: Arc<Mutex<BTreeMap<RequestId, Pending>>>,
-    subscriptions: Arc<Mutex<BTreeMap<SubscriptionId, Subscription>>>,
+    shared: Arc<Mutex<Shared>>,
     write_sender: mpsc::UnboundedSender<OwnedMessage>,
 }
 
@@ -56,124 +444,85 @@ impl WebSocket {
     }
 
     /// Create new WebSocket transport within existing Event Loop.
+    ///
+    /// The transport reconnects with default `Reconnect` settings.
     pub fn with_event_loop(url: &str, handle: &reactor::Handle) -> Result<Self> {
-        log::trace!("Connecting to: {:?}", url);
+        Self::with_reconnect(url, handle, Reconnect::default())
+    }
 
+    /// Create new WebSocket transport within existing Event Loop with given reconnection settings.
+    pub fn with_reconnect(url: &str, handle: &reactor::Handle, reconnect: Reconnect) -> Result<Self> {
         let url: Url = url.parse()?;
-        let pending: Arc<Mutex<BTreeMap<RequestId, Pending>>> = Default::default();
-        let subscriptions: Arc<Mutex<BTreeMap<SubscriptionId, Subscription>>> = Default::default();
+        let id = Arc::new(atomic::AtomicUsize::new(1));
+        let shared: Arc<Mutex<Shared>> = Default::default();
         let (write_sender, write_receiver) = mpsc::unbounded();
 
-        let ws_future = {
-            let pending_ = pending.clone();
-            let subscriptions_ = subscriptions.clone();
-            let write_sender_ = write_sender.clone();
-
-            ClientBuilder::from_url(&url)
-                .async_connect(None, handle)
-                .from_err::<Error>()
-                .map(|(duplex, _)| duplex.split())
-                .and_then(move |(sink, stream)| {
-                    let reader = stream.from_err::<Error>().for_each(move |message| {
-                        log::trace!("Message received: {:?}", message);
-
-                        match message {
-                            OwnedMessage::Close(e) => write_sender_
-                                .unbounded_send(OwnedMessage::Close(e))
-                                .map_err(|_| Error::Transport("Error sending close message".into())),
-                            OwnedMessage::Ping(d) => write_sender_
-                                .unbounded_send(OwnedMessage::Pong(d))
-                                .map_err(|_| Error::Transport("Error sending pong message".into())),
-                            OwnedMessage::Text(t) => {
-                                if let Ok(notification) = helpers::to_notification_from_slice(t.as_bytes()) {
-                                    if let rpc::Params::Map(params) = notification.params {
-                                        let id = params.get("subscription");
-                                        let result = params.get("result");
-
-                                        if let (Some(&rpc::Value::String(ref id)), Some(result)) = (id, result) {
-                                            let id: SubscriptionId = id.clone().into();
-                                            if let Some(stream) = subscriptions_.lock().get(&id) {
-                                                return stream.unbounded_send(result.clone()).map_err(|_| {
-                                                    Error::Transport("Error sending notification".into())
-                                                });
-                                            } else {
-                                                log::warn!("Got notification for unknown subscription (id: {:?})", id);
-                                            }
-                                        } else {
-                                            log::error!("Got unsupported notification (id: {:?})", id);
-                                        }
-                                    }
-
-                                    return Ok(());
-                                }
-
-                                let response = helpers::to_response_from_slice(t.as_bytes());
-                                let outputs = match response {
-                                    Ok(rpc::Response::Single(output)) => vec![output],
-                                    Ok(rpc::Response::Batch(outputs)) => outputs,
-                                    _ => vec![],
-                                };
-
-                                let id = match outputs.get(0) {
-                                    Some(&rpc::Output::Success(ref success)) => success.id.clone(),
-                                    Some(&rpc::Output::Failure(ref failure)) => failure.id.clone(),
-                                    None => rpc::Id::Num(0),
-                                };
-
-                                if let rpc::Id::Num(num) = id {
-                                    if let Some(request) = pending_.lock().remove(&(num as usize)) {
-                                        log::trace!("Responding to (id: {:?}) with {:?}", num, outputs);
-                                        if let Err(err) = request.send(helpers::to_results_from_outputs(outputs)) {
-                                            log::warn!("Sending a response to deallocated channel: {:?}", err);
-                                        }
-                                    } else {
-                                        log::warn!("Got response for unknown request (id: {:?})", num);
-                                    }
-                                } else {
-                                    log::warn!("Got unsupported response (id: {:?})", id);
-                                }
-
-                                Ok(())
-                            }
-                            _ => Ok(()),
-                        }
-                    });
-
-                    let writer = sink
-                        .sink_from_err()
-                        .send_all(write_receiver.map_err(|_| websocket::WebSocketError::NoDataAvailable))
-                        .map(|_| ());
-
-                    reader.join(writer)
-                })
+        let mut connection = WsConnection {
+            url: url.clone(),
+            handle: handle.clone(),
+            reconnect,
+            id: id.clone(),
+            shared: shared.clone(),
+            write_receiver,
+            outgoing: Default::default(),
+            state: ConnectionState::Closed,
+            attempts: 0,
         };
-
-        handle.spawn(ws_future.map(|_| ()).map_err(|err| {
-            log::error!("WebSocketError: {:?}", err);
-        }));
+        connection.state = connection.connect();
+        handle.spawn(connection);
 
         Ok(Self {
-            id: Arc::new(atomic::AtomicUsize::new(1)),
+            id,
             url,
-            pending,
-            subscriptions,
+            shared,
             write_sender,
         })
     }
 
-    fn send_request<F, O>(&self, id: RequestId, request: rpc::Request, extract: F) -> WsTask<F>
+    fn send_request<F, O>(&self, id: RequestId, mut request: rpc::Request, extract: F) -> WsTask<F>
     where
         F: Fn(Vec<Result<rpc::Value>>) -> O,
     {
+        let mut shared = self.shared.lock();
+        let mut subscription = None;
+        if let rpc::Request::Single(rpc::Call::MethodCall(ref mut call)) = request {
+            match call.method.as_str() {
+                "eth_subscribe" => subscription = Some(call.params.clone()),
+                // The server only knows the id of the latest subscription.
+                "eth_unsubscribe" => {
+                    if let rpc::Params::Array(ref mut params) = call.params {
+                        if let Some(&mut rpc::Value::String(ref mut sub_id)) = params.get_mut(0) {
+                            if let Some(server_id) = shared.server_ids.get(&sub_id.clone().into()) {
+                                *sub_id = server_id.clone();
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let ids = helpers::request_ids(&request);
         let request = helpers::to_string(&request);
         log::debug!("[{}] Calling: {}", id, request);
         let (tx, rx) = futures::oneshot();
-        self.pending.lock().insert(id, tx);
+        shared.pending.insert(
+            id,
+            PendingRequest {
+                sender: tx,
+                ids,
+                request: request.clone(),
+                subscription,
+            },
+        );
 
         let result = self
             .write_sender
             .unbounded_send(OwnedMessage::Text(request))
             .map_err(|_| Error::Transport("Error sending request".into()));
+        if result.is_err() {
+            shared.pending.remove(&id);
+        }
 
         Response::new(id, result, rx, extract)
     }
@@ -218,14 +567,14 @@ impl DuplexTransport for WebSocket {
 
     fn subscribe(&self, id: &SubscriptionId) -> Self::NotificationStream {
         let (tx, rx) = mpsc::unbounded();
-        if self.subscriptions.lock().insert(id.clone(), tx).is_some() {
+        if self.shared.lock().subscriptions.insert(id.clone(), tx).is_some() {
             log::warn!("Replacing already-registered subscription with id {:?}", id)
         }
         Box::new(rx.map_err(|()| Error::Transport("No data available".into())))
     }
 
     fn unsubscribe(&self, id: &SubscriptionId) {
-        self.subscriptions.lock().remove(id);
+        self.shared.lock().remove_subscription(id);
     }
 }
 
@@ -239,15 +588,17 @@ mod tests {
     use self::websocket::server::InvalidConnection;
     use super::WebSocket;
     use crate::rpc;
-    use crate::Transport;
-    use futures::{Future, Sink, Stream};
+    use crate::transports::MockServer;
+    use crate::{DuplexTransport, Transport};
+    use futures::{self, Future, Sink, Stream};
 
     #[test]
     fn should_send_a_request() {
         // given
         let mut eloop = tokio_core::reactor::Core::new().unwrap();
         let handle = eloop.handle();
-        let server = Server::bind("localhost:3000", &handle).unwrap();
+        let server = Server::bind("127.0.0.1:0", &handle).unwrap();
+        let url = format!("ws://{}", server.local_addr().unwrap());
         let f = {
             let handle_ = handle.clone();
             server
@@ -283,7 +634,7 @@ mod tests {
         };
         handle.spawn(f.map_err(|_| ()));
 
-        let ws = WebSocket::with_event_loop("ws://localhost:3000", &handle).unwrap();
+        let ws = WebSocket::with_event_loop(&url, &handle).unwrap();
 
         // when
         let res = ws.execute("eth_accounts", vec![rpc::Value::String("1".into())]);
@@ -291,4 +642,104 @@ mod tests {
         // then
         assert_eq!(eloop.run(res), Ok(rpc::Value::String("x".into())));
     }
+
+    #[test]
+    fn should_reconnect_and_replay_subscriptions() {
+        // given
+        let mut eloop = tokio_core::reactor::Core::new().unwrap();
+        let handle = eloop.handle();
+        let server = Server::bind("127.0.0.1:0", &handle).unwrap();
+        let url = format!("ws://{}", server.local_addr().unwrap());
+        let f = {
+            let handle_ = handle.clone();
+            server
+                .incoming()
+                .take(2)
+                .map_err(|InvalidConnection { error, .. }| error)
+                .zip(futures::stream::iter_ok(1..))
+                .for_each(move |((upgrade, addr), n)| {
+                    log::trace!("Got a connection from {}", addr);
+                    let f = upgrade.accept().and_then(move |(s, _)| {
+                        let (sink, stream) = s.split();
+
+                        stream
+                            .into_future()
+                            .map_err(|(error, _)| error)
+                            .and_then(move |(message, stream)| {
+                                assert_eq!(
+                                    message,
+                                    Some(OwnedMessage::Text(format!(
+                                        r#"{{"jsonrpc":"2.0","method":"eth_subscribe","params":["newHeads"],"id":{}}}"#,
+                                        n
+                                    )))
+                                );
+                                let mut messages = vec![
+                                    OwnedMessage::Text(format!(r#"{{"jsonrpc":"2.0","id":{},"result":"0x{}"}}"#, n, n)),
+                                    OwnedMessage::Text(format!(
+                                        r#"{{"jsonrpc":"2.0","method":"eth_subscription","params":{{"subscription":"0x{}","result":{}}}}}"#,
+                                        n, n
+                                    )),
+                                ];
+                                // Drop the first connection right after the notification.
+                                if n == 1 {
+                                    messages.push(OwnedMessage::Close(None));
+                                }
+
+                                sink.send_all(futures::stream::iter_ok(messages))
+                                    .and_then(|_| stream.take_while(|m| Ok(!m.is_close())).for_each(|_| Ok(())))
+                            })
+                    });
+
+                    handle_.spawn(f.map(|_| ()).map_err(|_| ()));
+
+                    Ok(())
+                })
+        };
+        handle.spawn(f.map_err(|_| ()));
+
+        let ws = WebSocket::with_event_loop(&url, &handle).unwrap();
+        let notifications = ws.subscribe(&"0x1".to_owned().into());
+
+        // when
+        let id = eloop.run(ws.execute("eth_subscribe", vec![rpc::Value::String("newHeads".into())]));
+        let res = eloop.run(notifications.take(2).collect());
+
+        // then
+        assert_eq!(id, Ok(rpc::Value::String("0x1".into())));
+        assert_eq!(res, Ok(vec![rpc::Value::from(1), rpc::Value::from(2)]));
+    }
+
+    #[test]
+    fn should_resubscribe_after_server_disconnects() {
+        // given
+        let ids = ::std::sync::atomic::AtomicUsize::new(1);
+        let server = MockServer::new().with_method("eth_subscribe", move |_| {
+            let id = ids.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+            Ok(rpc::Value::String(format!("0x{}", id)))
+        });
+        let handle = server.serve_ws().unwrap();
+        let (_eloop, ws) = WebSocket::new(handle.url()).unwrap();
+        let notifications = ws.subscribe(&"0x1".to_owned().into());
+        let id = ws
+            .execute("eth_subscribe", vec![rpc::Value::String("newHeads".into())])
+            .wait();
+        server.notify("0x1", rpc::Value::from(1));
+
+        // when
+        server.disconnect();
+        // Wait until the client maps the new server id to the subscription.
+        let resubscribed = (0..100).any(|_| {
+            ::std::thread::sleep(::std::time::Duration::from_millis(20));
+            ws.shared.lock().client_ids.contains_key("0x2")
+        });
+        server.notify("0x2", rpc::Value::from(2));
+
+        // then
+        assert_eq!(id, Ok(rpc::Value::String("0x1".into())));
+        assert!(resubscribed);
+        assert_eq!(
+            notifications.take(2).collect().wait(),
+            Ok(vec![rpc::Value::from(1), rpc::Value::from(2)])
+        );
+    }
 }
@@ -47,6 +47,9 @@ pub struct BlockHeader {
     pub mix_hash: Option<H256>,
     /// Nonce
     pub nonce: Option<H64>,
+    /// Base fee per unit of gas (EIP-1559). None for pre-London blocks.
+    #[serde(rename = "baseFeePerGas", default, skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
 }
 
 /// The block type returned from RPC calls.
@@ -108,6 +111,9 @@ pub struct Block<TX> {
     pub mix_hash: Option<H256>,
     /// Nonce
     pub nonce: Option<H64>,
+    /// Base fee per unit of gas (EIP-1559). None for pre-London blocks.
+    #[serde(rename = "baseFeePerGas", default, skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
 }
 
 /// Block Number
@@ -0,0 +1,45 @@
+use crate::types::{U256, U64};
+use serde::{Deserialize, Serialize};
+
+/// The fee history type returned from `eth_feeHistory` call.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FeeHistory {
+    /// Lowest number block of the returned range.
+    #[serde(rename = "oldestBlock")]
+    pub oldest_block: U64,
+    /// Base fee per gas of each block in the range, followed by the base fee of the next block.
+    #[serde(rename = "baseFeePerGas")]
+    pub base_fee_per_gas: Vec<U256>,
+    /// Ratio of gas used to gas limit of each block in the range.
+    #[serde(rename = "gasUsedRatio")]
+    pub gas_used_ratio: Vec<f64>,
+    /// Effective priority fees per gas at the requested percentiles, for each block in the range.
+    /// None if no percentiles were requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reward: Option<Vec<Vec<U256>>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FeeHistory;
+    use serde_json;
+
+    #[test]
+    fn should_deserialize_fee_history() {
+        let history_str = r#"{
+        "oldestBlock": "0xc5043f",
+        "baseFeePerGas": ["0x3da8e7618", "0x3e1ba3b1b", "0x3dbd8aab3"],
+        "gasUsedRatio": [0.5290747666666666, 0.49240453333333334],
+        "reward": [["0x59682f00"], ["0x3b9aca00"]]
+    }"#;
+
+        let history: FeeHistory = serde_json::from_str(history_str).unwrap();
+
+        assert_eq!(history.oldest_block, 0xc5043f.into());
+        assert_eq!(history.base_fee_per_gas.len(), 3);
+        assert_eq!(
+            history.reward,
+            Some(vec![vec![0x59682f00.into()], vec![0x3b9aca00.into()]])
+        );
+    }
+}
@@ -2,24 +2,31 @@
 
 mod block;
 mod bytes;
+mod fee_history;
 mod log;
 mod parity_peers;
+mod revert;
+mod signed;
 mod sync_state;
 mod trace_filtering;
 mod traces;
 mod transaction;
 mod transaction_id;
 mod transaction_request;
+mod typed_data;
 mod uint;
 mod work;
 mod raw;
 
 pub use self::block::{Block, BlockHeader, BlockId, BlockNumber};
 pub use self::bytes::Bytes;
+pub use self::fee_history::FeeHistory;
 pub use self::log::{Filter, FilterBuilder, Log};
 pub use self::parity_peers::{
     EthProtocolInfo, ParityPeerInfo, ParityPeerType, PeerNetworkInfo, PeerProtocolsInfo, PipProtocolInfo,
 };
+pub use self::revert::{CustomError, Revert};
+pub use self::signed::{SignedData, SignedTransaction, TransactionParameters};
 pub use self::sync_state::{SyncInfo, SyncState};
 pub use self::trace_filtering::{
     Action, ActionType, Call, CallResult, CallType, Create, CreateResult, Res, Reward, RewardType, Suicide, Trace,
@@ -29,9 +36,12 @@ pub use self::traces::{
     AccountDiff, BlockTrace, ChangedType, Diff, MemoryDiff, StateDiff, StorageDiff, TraceType, TransactionTrace,
     VMExecutedOperation, VMOperation, VMTrace,
 };
-pub use self::transaction::{RawTransaction, Receipt as TransactionReceipt, Transaction};
+pub use self::transaction::{
+    AccessList, AccessListItem, AccessListWithGasUsed, RawTransaction, Receipt as TransactionReceipt, Transaction,
+};
 pub use self::transaction_id::TransactionId;
 pub use self::transaction_request::{CallRequest, TransactionCondition, TransactionRequest};
+pub use self::typed_data::{Eip712Domain, TypedData, TypedDataField, TypedDataTypes, EIP712_DOMAIN_TYPE};
 pub use self::uint::{H128, H160, H2048, H256, H512, H520, H64, U128, U256, U64};
 pub use self::work::Work;
 pub use self::raw::{Receipt as RawReceipt, Header as RawHeader};
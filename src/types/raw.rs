@@ -49,6 +49,8 @@ pub struct Header {
     pub difficulty: U256,
     /// Vector of post-RLP-encoded fields.
     pub seal: Vec<Bytes>,
+    /// Base fee per gas (EIP-1559). Encoded after the seal when present.
+    pub base_fee_per_gas: Option<U256>,
 
     /// Memoized hash of that header and the seal.
     pub hash: Option<H256>,
@@ -74,6 +76,7 @@ impl Default for Header {
 
             difficulty: U256::default(),
             seal: Vec::new(),
+            base_fee_per_gas: None,
             hash: None,
         }
     }
@@ -99,10 +102,11 @@ impl Header {
 
     /// Place this header into an RLP stream `s`, optionally `with_seal`.
     fn stream_rlp(&self, s: &mut RlpStream, with_seal: Seal) {
+        let base_fee_len = if self.base_fee_per_gas.is_some() { 1 } else { 0 };
         if let Seal::With = with_seal {
-            s.begin_list(13 + self.seal.len());
+            s.begin_list(13 + self.seal.len() + base_fee_len);
         } else {
-            s.begin_list(13);
+            s.begin_list(13 + base_fee_len);
         }
 
         s.append(&self.parent_hash);
@@ -124,6 +128,10 @@ impl Header {
                 s.append_raw(b, 1);
             }
         }
+
+        if let Some(ref base_fee) = self.base_fee_per_gas {
+            s.append(base_fee);
+        }
     }
 }
 
@@ -144,6 +152,9 @@ impl Decodable for Header {
             timestamp: r.val_at(11)?,
             extra_data: r.val_at(12)?,
             seal: Vec::new(),
+            // The seal length depends on the engine, so trailing fields can't be told apart from the seal.
+            // They are kept in `seal`, which still round-trips the encoding.
+            base_fee_per_gas: None,
             hash: keccak(r.as_raw()).into(),
         };
 
@@ -207,6 +218,21 @@ impl Encodable for Receipt {
 }
 
 impl Receipt {
+    /// Encodes the receipt as it is stored in the receipts trie.
+    ///
+    /// Receipts of typed transactions (EIP-2718) are prefixed with the transaction type,
+    /// legacy receipts (no type or type `0`) are plain RLP.
+    pub fn trie_value(&self, transaction_type: Option<u8>) -> Bytes {
+        match transaction_type {
+            Some(tx_type) if tx_type != 0 => {
+                let mut out = vec![tx_type];
+                out.extend_from_slice(&rlp::encode(self));
+                out
+            }
+            _ => rlp::encode(self),
+        }
+    }
+
     /// Create a new receipt.
     pub fn new(outcome: TransactionOutcome, gas_used: U256, logs: Vec<LogEntry>) -> Self {
         Self {
@@ -318,6 +344,7 @@ impl From<RpcBlock<H256>> for Header {
             gas_limit: block.gas_limit,
             difficulty: block.difficulty,
             seal: seal,
+            base_fee_per_gas: block.base_fee_per_gas,
             hash: None,
         }
     }
@@ -424,4 +451,30 @@ mod tests {
 
         assert_eq!(header_rlp, encoded_header);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_typed_receipt_trie_value() {
+        let r = Receipt::new(TransactionOutcome::StatusCode(1), 0x5208.into(), vec![]);
+        let encoded = ::rlp::encode(&r);
+
+        assert_eq!(r.trie_value(None), encoded);
+        assert_eq!(r.trie_value(Some(0)), encoded);
+
+        let typed = r.trie_value(Some(2));
+        assert_eq!(typed[0], 2);
+        assert_eq!(&typed[1..], &encoded[..]);
+    }
+
+    #[test]
+    fn test_header_with_base_fee() {
+        let mut header = Header::default();
+        let legacy = rlp::encode(&header);
+
+        header.base_fee_per_gas = Some(7.into());
+        let london = rlp::encode(&header);
+
+        assert_eq!(rlp::Rlp::new(&legacy).item_count().unwrap(), 13);
+        assert_eq!(rlp::Rlp::new(&london).item_count().unwrap(), 14);
+        assert_eq!(rlp::Rlp::new(&london).val_at::<U256>(13).unwrap(), 7.into());
+    }
+}
@@ -0,0 +1,229 @@
+//! Revert reason decoding.
+
+use std::fmt;
+
+use ethabi::{self, ParamType, Token};
+use rustc_hex::ToHex;
+use serde::Deserialize;
+
+use crate::types::{Bytes, U256};
+
+/// Selector of `Error(string)`, used by `require` and `revert` with a reason.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`, used by failed assertions and checked arithmetic.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Custom Solidity error declared in the contract's ABI.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CustomError {
+    /// Error name.
+    pub name: String,
+    /// Error parameters.
+    #[serde(default)]
+    pub inputs: Vec<ethabi::Param>,
+}
+
+impl CustomError {
+    /// Returns the error selector (first 4 bytes of the revert data).
+    pub fn selector(&self) -> [u8; 4] {
+        let types = self
+            .inputs
+            .iter()
+            .map(|param| ethabi::param_type::Writer::write(&param.kind))
+            .collect::<Vec<_>>();
+        let hash = hash::keccak(format!("{}({})", self.name, types.join(",")).as_bytes());
+        let mut selector = [0u8; 4];
+        selector.copy_from_slice(&hash.as_bytes()[..4]);
+        selector
+    }
+
+    /// Decodes error parameters (revert data without the selector).
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<Token>, ethabi::Error> {
+        let types = self.inputs.iter().map(|param| param.kind.clone()).collect::<Vec<_>>();
+        ethabi::decode(&types, data)
+    }
+}
+
+/// Decoded reason of a reverted call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Revert {
+    /// `require` or `revert` with a reason string
+    Reason(String),
+    /// failed assertion, arithmetic overflow, division by zero etc.
+    Panic(U256),
+    /// custom error declared in the contract's ABI
+    Custom {
+        /// error name
+        name: String,
+        /// decoded error parameters
+        tokens: Vec<Token>,
+    },
+    /// data that couldn't be decoded (empty if the contract reverted without a reason)
+    Unknown(Bytes),
+}
+
+impl Revert {
+    /// Decodes revert data, matching custom errors against given definitions.
+    pub fn decode(data: &[u8], errors: &[CustomError]) -> Self {
+        if data.len() < 4 {
+            return Revert::Unknown(Bytes(data.to_vec()));
+        }
+
+        let (selector, params) = data.split_at(4);
+        let decoded = if selector == ERROR_SELECTOR {
+            match ethabi::decode(&[ParamType::String], params)
+                .ok()
+                .and_then(|tokens| tokens.into_iter().next())
+            {
+                Some(Token::String(reason)) => Some(Revert::Reason(reason)),
+                _ => None,
+            }
+        } else if selector == PANIC_SELECTOR {
+            match ethabi::decode(&[ParamType::Uint(256)], params)
+                .ok()
+                .and_then(|tokens| tokens.into_iter().next())
+            {
+                Some(Token::Uint(code)) => Some(Revert::Panic(code)),
+                _ => None,
+            }
+        } else {
+            errors
+                .iter()
+                .filter(|error| error.selector() == selector)
+                .filter_map(|error| {
+                    error.decode(params).ok().map(|tokens| Revert::Custom {
+                        name: error.name.clone(),
+                        tokens,
+                    })
+                })
+                .next()
+        };
+
+        decoded.unwrap_or_else(|| Revert::Unknown(Bytes(data.to_vec())))
+    }
+
+    /// Returns the meaning of the panic code, as documented by Solidity.
+    pub fn panic_reason(&self) -> Option<&'static str> {
+        let code = match *self {
+            Revert::Panic(code) if code <= U256::from(u8::max_value()) => code.low_u32(),
+            Revert::Panic(_) => return Some("unknown panic"),
+            _ => return None,
+        };
+
+        Some(match code {
+            0x00 => "generic compiler panic",
+            0x01 => "assertion failed",
+            0x11 => "arithmetic overflow or underflow",
+            0x12 => "division or modulo by zero",
+            0x21 => "invalid enum value",
+            0x22 => "invalid storage byte array encoding",
+            0x31 => "pop on empty array",
+            0x32 => "array index out of bounds",
+            0x41 => "out of memory",
+            0x51 => "call to uninitialized function",
+            _ => "unknown panic",
+        })
+    }
+}
+
+impl fmt::Display for Revert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Revert::Reason(ref reason) => write!(f, "{}", reason),
+            Revert::Panic(code) => write!(
+                f,
+                "panic 0x{:x} ({})",
+                code,
+                self.panic_reason().expect("panic has a reason; qed")
+            ),
+            Revert::Custom { ref name, ref tokens } => {
+                let tokens = tokens.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "{}({})", name, tokens.join(", "))
+            }
+            Revert::Unknown(ref data) if data.0.is_empty() => write!(f, "no reason"),
+            Revert::Unknown(ref data) => write!(f, "unknown data 0x{}", data.0.to_hex::<String>()),
+        }
+    }
+}
+
+/// Loads the ABI, extracting custom error definitions (not supported by `ethabi`).
+pub(crate) fn load_abi(json: &[u8]) -> Result<(ethabi::Contract, Vec<CustomError>), ethabi::Error> {
+    let entries = match serde_json::from_slice(json)? {
+        serde_json::Value::Array(entries) => entries,
+        _ => return Ok((ethabi::Contract::load(json)?, vec![])),
+    };
+
+    let (errors, entries): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|entry| entry.get("type").and_then(serde_json::Value::as_str) == Some("error"));
+    let errors = errors
+        .into_iter()
+        .map(serde_json::from_value)
+        .collect::<Result<Vec<CustomError>, _>>()?;
+    let abi = ethabi::Contract::load(&serde_json::to_vec(&entries)?[..])?;
+    Ok((abi, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_abi, Revert};
+    use crate::types::Bytes;
+    use ethabi::Token;
+    use rustc_hex::FromHex;
+
+    const ABI: &[u8] = br#"[
+        {"type":"function","name":"withdraw","inputs":[{"name":"amount","type":"uint256"}],"outputs":[],"constant":false,"payable":false},
+        {"type":"error","name":"InsufficientBalance","inputs":[{"name":"available","type":"uint256"},{"name":"required","type":"uint256"}]}
+    ]"#;
+
+    fn data(hex: &str) -> Vec<u8> {
+        hex.from_hex().unwrap()
+    }
+
+    #[test]
+    fn should_decode_reason() {
+        let revert = Revert::decode(
+            &data(concat!(
+                "08c379a0",
+                "0000000000000000000000000000000000000000000000000000000000000020",
+                "000000000000000000000000000000000000000000000000000000000000000b",
+                "4e6f7420616c6c6f776564000000000000000000000000000000000000000000"
+            )),
+            &[],
+        );
+
+        assert_eq!(revert, Revert::Reason("Not allowed".into()));
+    }
+
+    #[test]
+    fn should_decode_panic() {
+        let revert = Revert::decode(
+            &data("4e487b710000000000000000000000000000000000000000000000000000000000000011"),
+            &[],
+        );
+
+        assert_eq!(revert, Revert::Panic(0x11.into()));
+        assert_eq!(revert.panic_reason(), Some("arithmetic overflow or underflow"));
+        assert_eq!(revert.to_string(), "panic 0x11 (arithmetic overflow or underflow)");
+    }
+
+    #[test]
+    fn should_decode_custom_error() {
+        let (abi, errors) = load_abi(ABI).unwrap();
+        let mut revert_data = errors[0].selector().to_vec();
+        revert_data.extend(ethabi::encode(&[Token::Uint(1.into()), Token::Uint(2.into())]));
+
+        let revert = Revert::decode(&revert_data, &errors);
+
+        assert!(abi.function("withdraw").is_ok());
+        assert_eq!(
+            revert,
+            Revert::Custom {
+                name: "InsufficientBalance".into(),
+                tokens: vec![Token::Uint(1.into()), Token::Uint(2.into())],
+            }
+        );
+        assert_eq!(Revert::decode(&revert_data, &[]), Revert::Unknown(Bytes(revert_data)));
+        assert_eq!(Revert::decode(&[], &errors), Revert::Unknown(Bytes(vec![])));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::types::{AccessList, Address, Bytes, TransactionRequest, H256, U256, U64};
+
+/// Struct representing signed data returned from `Accounts::sign` method.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedData {
+    /// The original message that was signed.
+    pub message: Vec<u8>,
+    /// The keccak256 hash of the signed data.
+    pub message_hash: H256,
+    /// V value in 'Electrum' notation (`27` or `28`).
+    pub v: u8,
+    /// R value.
+    pub r: H256,
+    /// S value.
+    pub s: H256,
+    /// The signature bytes (`r || s || v`).
+    pub signature: Bytes,
+}
+
+/// Transaction data for signing locally.
+///
+/// Missing `nonce`, `gas`, `gas_price`, `chain_id` and EIP-1559 fees are fetched from the node
+/// by `Accounts::sign_transaction`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionParameters {
+    /// Transaction nonce (None for account transaction count)
+    pub nonce: Option<U256>,
+    /// To address (None for contract creation)
+    pub to: Option<Address>,
+    /// Supplied gas (None for `eth_estimateGas`)
+    pub gas: Option<U256>,
+    /// Gas price (None for estimated gas price, ignored for EIP-1559 transactions)
+    pub gas_price: Option<U256>,
+    /// Transferred value
+    pub value: U256,
+    /// Data
+    pub data: Bytes,
+    /// The chain ID (None for `eth_chainId`)
+    pub chain_id: Option<u64>,
+    /// Transaction type (None to infer it from the other fields)
+    ///
+    /// Transactions with EIP-1559 fees are type `2`, with only an access list type `1`,
+    /// otherwise they are legacy (type `0`).
+    pub transaction_type: Option<U64>,
+    /// Access list
+    pub access_list: Option<AccessList>,
+    /// Max fee per gas (None for estimated fee, EIP-1559 only)
+    pub max_fee_per_gas: Option<U256>,
+    /// Max priority fee per gas (None for estimated fee, EIP-1559 only)
+    pub max_priority_fee_per_gas: Option<U256>,
+}
+
+impl Default for TransactionParameters {
+    fn default() -> Self {
+        TransactionParameters {
+            nonce: None,
+            to: None,
+            gas: None,
+            gas_price: None,
+            value: U256::zero(),
+            data: Bytes::default(),
+            chain_id: None,
+            transaction_type: None,
+            access_list: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+        }
+    }
+}
+
+impl TransactionParameters {
+    /// Transaction type to encode, explicit or inferred from the set fields.
+    pub fn resolved_type(&self) -> u64 {
+        match self.transaction_type {
+            Some(transaction_type) => transaction_type.low_u64(),
+            None if self.max_fee_per_gas.is_some() || self.max_priority_fee_per_gas.is_some() => 2,
+            None if self.access_list.is_some() => 1,
+            None => 0,
+        }
+    }
+}
+
+/// Drops `from` and `condition` which can't be part of a signed transaction.
+impl From<TransactionRequest> for TransactionParameters {
+    fn from(tx: TransactionRequest) -> Self {
+        let default = TransactionParameters::default();
+        TransactionParameters {
+            nonce: tx.nonce,
+            to: tx.to,
+            gas: tx.gas,
+            gas_price: tx.gas_price,
+            value: tx.value.unwrap_or(default.value),
+            data: tx.data.unwrap_or(default.data),
+            chain_id: None,
+            transaction_type: tx.transaction_type,
+            access_list: tx.access_list,
+            max_fee_per_gas: tx.max_fee_per_gas,
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+        }
+    }
+}
+
+/// Data for offline signed transaction
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedTransaction {
+    /// The given message hash
+    pub message_hash: H256,
+    /// V value with chain replay protection for legacy transactions, the y-parity otherwise.
+    pub v: u64,
+    /// R value.
+    pub r: H256,
+    /// S value.
+    pub s: H256,
+    /// The raw signed transaction ready to be sent with `send_raw_transaction`
+    pub raw_transaction: Bytes,
+    /// The transaction hash for the RLP encoded transaction.
+    pub transaction_hash: H256,
+}
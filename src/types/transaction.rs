@@ -30,6 +30,45 @@ pub struct Transaction {
     pub gas: U256,
     /// Input data
     pub input: Bytes,
+    /// Transaction type (EIP-2718). None for legacy transactions.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub transaction_type: Option<U64>,
+    /// Access list (EIP-2930)
+    #[serde(rename = "accessList", default, skip_serializing_if = "Option::is_none")]
+    pub access_list: Option<AccessList>,
+    /// Max fee per gas (EIP-1559)
+    #[serde(rename = "maxFeePerGas", default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    /// Max priority fee per gas (EIP-1559)
+    #[serde(rename = "maxPriorityFeePerGas", default, skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
+}
+
+/// Access list (EIP-2930)
+pub type AccessList = Vec<AccessListItem>;
+
+/// Addresses and storage keys a transaction plans to access.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AccessListItem {
+    /// Accessed address
+    pub address: H160,
+    /// Accessed storage keys
+    #[serde(rename = "storageKeys")]
+    pub storage_keys: Vec<H256>,
+}
+
+/// Access list generated by `eth_createAccessList` along with the gas used.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessListWithGasUsed {
+    /// Generated access list
+    #[serde(rename = "accessList")]
+    pub access_list: AccessList,
+    /// Gas used by the call with the access list applied
+    #[serde(rename = "gasUsed")]
+    pub gas_used: U256,
+    /// Error reported by the node when the call would fail
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// "Receipt" of an executed transaction: details of its execution.
@@ -65,7 +104,14 @@ pub struct Receipt {
     /// The OR-wide combination of all logs' blooms for this transaction.
     #[serde(rename = "logsBloom")]
     pub logs_bloom: H2048,
+    /// State root. Only present for pre-Byzantium receipts.
     pub root: Option<H256>,
+    /// Transaction type (EIP-2718). None for legacy transactions.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub transaction_type: Option<U64>,
+    /// Price paid per unit of gas, including the base fee (EIP-1559).
+    #[serde(rename = "effectiveGasPrice", default, skip_serializing_if = "Option::is_none")]
+    pub effective_gas_price: Option<U256>,
 }
 
 /// Raw bytes of a signed, but not yet sent transaction
@@ -118,6 +164,8 @@ pub struct RawTransactionDetails {
 mod tests {
     use super::RawTransaction;
     use super::Receipt;
+    use super::{AccessListItem, Transaction};
+    use crate::types::{H160, H256};
     use serde_json;
 
     #[test]
@@ -209,4 +257,47 @@ mod tests {
 
         let _tx: RawTransaction = serde_json::from_str(tx_str).unwrap();
     }
+
+    #[test]
+    fn should_deserialize_eip1559_transaction() {
+        let tx_str = r#"{
+        "accessList": [
+          {
+            "address": "0xde0b295669a9fd93d5f28d9ec85e40f4cb697bae",
+            "storageKeys": ["0x0000000000000000000000000000000000000000000000000000000000000003"]
+          }
+        ],
+        "blockHash": "0x2f4bc4c4fca9b3d3fa5d2f2cd10ae9b4d0ad3a3ad5ac8f0aa6a1aaa3cc68b5ab",
+        "blockNumber": "0xc5043f",
+        "chainId": "0x1",
+        "from": "0x5abfec25f74cd88437631a7731906932776356f9",
+        "gas": "0x5208",
+        "gasPrice": "0x1c1ebf6a0b",
+        "hash": "0x9eb7bfa05f3f3e0f0b0f4fa5ed73a7bc2e1fe4f2d7ef1e4fb6d1e4fc8ab8e0f4",
+        "input": "0x",
+        "maxFeePerGas": "0x2540be400",
+        "maxPriorityFeePerGas": "0x3b9aca00",
+        "nonce": "0x2",
+        "r": "0x1",
+        "s": "0x1",
+        "to": "0xde0b295669a9fd93d5f28d9ec85e40f4cb697bae",
+        "transactionIndex": "0x3",
+        "type": "0x2",
+        "v": "0x0",
+        "value": "0x0"
+    }"#;
+
+        let tx: Transaction = serde_json::from_str(tx_str).unwrap();
+
+        assert_eq!(tx.transaction_type, Some(2.into()));
+        assert_eq!(tx.max_fee_per_gas, Some(10_000_000_000u64.into()));
+        assert_eq!(tx.max_priority_fee_per_gas, Some(1_000_000_000u64.into()));
+        assert_eq!(
+            tx.access_list,
+            Some(vec![AccessListItem {
+                address: "de0b295669a9fd93d5f28d9ec85e40f4cb697bae".parse::<H160>().unwrap(),
+                storage_keys: vec![H256::from_low_u64_be(3)],
+            }])
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use crate::types::{Address, Bytes, U256};
+use crate::types::{AccessList, Address, Bytes, U256, U64};
 use serde::{Deserialize, Serialize};
 
 /// Call contract request (eth_call / eth_estimateGas)
@@ -22,6 +22,18 @@ pub struct CallRequest {
     /// Data (None for empty data)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<Bytes>,
+    /// Transaction type (None for legacy transactions)
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub transaction_type: Option<U64>,
+    /// Access list (None for no access list)
+    #[serde(rename = "accessList", skip_serializing_if = "Option::is_none")]
+    pub access_list: Option<AccessList>,
+    /// Max fee per gas (EIP-1559, None for sensible default)
+    #[serde(rename = "maxFeePerGas", skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    /// Max priority fee per gas (EIP-1559, None for sensible default)
+    #[serde(rename = "maxPriorityFeePerGas", skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
 }
 
 /// Send Transaction Parameters
@@ -51,6 +63,18 @@ pub struct TransactionRequest {
     /// Min block inclusion (None for include immediately)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub condition: Option<TransactionCondition>,
+    /// Transaction type (None for legacy transactions)
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub transaction_type: Option<U64>,
+    /// Access list (None for no access list)
+    #[serde(rename = "accessList", skip_serializing_if = "Option::is_none")]
+    pub access_list: Option<AccessList>,
+    /// Max fee per gas (EIP-1559, None for sensible default)
+    #[serde(rename = "maxFeePerGas", skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<U256>,
+    /// Max priority fee per gas (EIP-1559, None for sensible default)
+    #[serde(rename = "maxPriorityFeePerGas", skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<U256>,
 }
 
 /// Represents condition on minimum block number or block timestamp.
@@ -68,6 +92,7 @@ pub enum TransactionCondition {
 #[cfg(test)]
 mod tests {
     use super::{Address, CallRequest, TransactionCondition, TransactionRequest};
+    use crate::types::{AccessListItem, H256};
     use serde_json;
 
     #[test]
@@ -80,6 +105,10 @@ mod tests {
             gas_price: None,
             value: Some(5_000_000.into()),
             data: Some(vec![1, 2, 3].into()),
+            transaction_type: None,
+            access_list: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
         };
 
         // when
@@ -109,6 +138,10 @@ mod tests {
             data: Some(vec![1, 2, 3].into()),
             nonce: None,
             condition: Some(TransactionCondition::Block(5)),
+            transaction_type: None,
+            access_list: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
         };
 
         // when
@@ -125,6 +158,52 @@ mod tests {
   "condition": {
     "block": 5
   }
+}"#
+        );
+    }
+
+    #[test]
+    fn should_serialize_eip1559_transaction_request() {
+        // given
+        let tx_request = TransactionRequest {
+            from: Address::from_low_u64_be(5),
+            to: Some(Address::from_low_u64_be(6)),
+            gas: Some(21_000.into()),
+            gas_price: None,
+            value: None,
+            data: None,
+            nonce: None,
+            condition: None,
+            transaction_type: Some(2.into()),
+            access_list: Some(vec![AccessListItem {
+                address: Address::from_low_u64_be(6),
+                storage_keys: vec![H256::from_low_u64_be(1)],
+            }]),
+            max_fee_per_gas: Some(20_000_000_000u64.into()),
+            max_priority_fee_per_gas: Some(1_000_000_000.into()),
+        };
+
+        // when
+        let serialized = serde_json::to_string_pretty(&tx_request).unwrap();
+
+        // then
+        assert_eq!(
+            serialized,
+            r#"{
+  "from": "0x0000000000000000000000000000000000000005",
+  "to": "0x0000000000000000000000000000000000000006",
+  "gas": "0x5208",
+  "type": "0x2",
+  "accessList": [
+    {
+      "address": "0x0000000000000000000000000000000000000006",
+      "storageKeys": [
+        "0x0000000000000000000000000000000000000000000000000000000000000001"
+      ]
+    }
+  ],
+  "maxFeePerGas": "0x4a817c800",
+  "maxPriorityFeePerGas": "0x3b9aca00"
 }"#
         );
     }
@@ -0,0 +1,578 @@
+//! EIP-712 typed structured data.
+
+use crate::types::{Address, H256, U256};
+use crate::Error;
+use hash::keccak;
+use rustc_hex::FromHex;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+/// Name of the EIP-712 domain struct type.
+pub const EIP712_DOMAIN_TYPE: &str = "EIP712Domain";
+
+/// A member of an EIP-712 struct type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypedDataField {
+    /// Member name
+    pub name: String,
+    /// Member type, e.g. `uint256`, `Person` or `address[]`
+    #[serde(rename = "type")]
+    pub field_type: String,
+}
+
+impl TypedDataField {
+    /// Creates a new struct member.
+    pub fn new<N: Into<String>, T: Into<String>>(name: N, field_type: T) -> Self {
+        TypedDataField {
+            name: name.into(),
+            field_type: field_type.into(),
+        }
+    }
+}
+
+/// EIP-712 struct types by name.
+pub type TypedDataTypes = BTreeMap<String, Vec<TypedDataField>>;
+
+/// EIP-712 signing domain.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Eip712Domain {
+    /// Name of the signing domain (dApp or protocol)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Current major version of the signing domain
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Chain id
+    #[serde(
+        default,
+        rename = "chainId",
+        deserialize_with = "deserialize_optional_uint",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub chain_id: Option<U256>,
+    /// Address of the contract verifying the signature
+    #[serde(default, rename = "verifyingContract", skip_serializing_if = "Option::is_none")]
+    pub verifying_contract: Option<Address>,
+    /// Disambiguating salt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<H256>,
+    /// Other fields, hashed when declared in `types.EIP712Domain`
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
+}
+
+impl Eip712Domain {
+    /// Struct type of this domain, containing only the fields which are set.
+    pub fn fields(&self) -> Vec<TypedDataField> {
+        let mut fields = vec![];
+        if self.name.is_some() {
+            fields.push(TypedDataField::new("name", "string"));
+        }
+        if self.version.is_some() {
+            fields.push(TypedDataField::new("version", "string"));
+        }
+        if self.chain_id.is_some() {
+            fields.push(TypedDataField::new("chainId", "uint256"));
+        }
+        if self.verifying_contract.is_some() {
+            fields.push(TypedDataField::new("verifyingContract", "address"));
+        }
+        if self.salt.is_some() {
+            fields.push(TypedDataField::new("salt", "bytes32"));
+        }
+        fields
+    }
+}
+
+/// EIP-712 typed structured data, as accepted by `eth_signTypedData_v4`.
+///
+/// Can be parsed from its JSON representation with `str::parse`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypedData {
+    /// Struct types definitions
+    ///
+    /// `EIP712Domain` is derived from the `domain` when missing.
+    pub types: TypedDataTypes,
+    /// Name of the struct type of `message`
+    #[serde(rename = "primaryType")]
+    pub primary_type: String,
+    /// Signing domain
+    pub domain: Eip712Domain,
+    /// Message to sign
+    pub message: Value,
+}
+
+impl FromStr for TypedData {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(serde_json::from_str(s)?)
+    }
+}
+
+impl TypedData {
+    /// Hash to sign: `keccak256("\x19\x01" || domainSeparator || hashStruct(message))`.
+    pub fn signing_hash(&self) -> Result<H256, Error> {
+        let mut data = Vec::with_capacity(66);
+        data.extend_from_slice(b"\x19\x01");
+        data.extend_from_slice(self.domain_separator()?.as_bytes());
+        data.extend_from_slice(self.struct_hash()?.as_bytes());
+        Ok(keccak(&data))
+    }
+
+    /// Domain separator, `hashStruct(domain)`.
+    ///
+    /// Uses the fields declared in `types.EIP712Domain` (in their order), if present.
+    pub fn domain_separator(&self) -> Result<H256, Error> {
+        self.hash_struct(EIP712_DOMAIN_TYPE, &serde_json::to_value(&self.domain)?)
+    }
+
+    /// Hash of the message, `hashStruct(message)`.
+    pub fn struct_hash(&self) -> Result<H256, Error> {
+        self.hash_struct(&self.primary_type, &self.message)
+    }
+
+    /// Hash of given struct type and value, `keccak256(typeHash || encodeData(value))`.
+    pub fn hash_struct(&self, struct_type: &str, value: &Value) -> Result<H256, Error> {
+        let fields = self.fields(struct_type)?;
+        let value = value
+            .as_object()
+            .ok_or_else(|| invalid(format!("Expected object for {}, got: {}", struct_type, value)))?;
+
+        let mut data = Vec::with_capacity(32 * (fields.len() + 1));
+        data.extend_from_slice(self.type_hash(struct_type)?.as_bytes());
+        for field in &fields {
+            let member = value.get(&field.name).unwrap_or(&Value::Null);
+            data.extend_from_slice(self.encode_value(&field.field_type, member)?.as_bytes());
+        }
+        Ok(keccak(&data))
+    }
+
+    /// Hash of the encoded struct type, `keccak256(encodeType(type))`.
+    pub fn type_hash(&self, struct_type: &str) -> Result<H256, Error> {
+        Ok(keccak(self.encode_type(struct_type)?.as_bytes()))
+    }
+
+    /// Encodes struct type along with its referenced struct types sorted by name,
+    /// e.g. `Mail(Person from,Person to,string contents)Person(string name,address wallet)`.
+    pub fn encode_type(&self, struct_type: &str) -> Result<String, Error> {
+        let mut dependencies = BTreeSet::new();
+        self.collect_dependencies(struct_type, &mut dependencies)?;
+        dependencies.remove(struct_type);
+
+        let mut encoded = String::new();
+        for name in Some(struct_type)
+            .into_iter()
+            .chain(dependencies.iter().map(String::as_str))
+        {
+            let fields = self
+                .fields(name)?
+                .iter()
+                .map(|field| format!("{} {}", field.field_type, field.name))
+                .collect::<Vec<_>>()
+                .join(",");
+            encoded.push_str(&format!("{}({})", name, fields));
+        }
+        Ok(encoded)
+    }
+
+    fn fields(&self, struct_type: &str) -> Result<Vec<TypedDataField>, Error> {
+        match self.types.get(struct_type) {
+            Some(fields) => Ok(fields.clone()),
+            None if struct_type == EIP712_DOMAIN_TYPE => Ok(self.domain.fields()),
+            None => Err(invalid(format!("Unknown struct type: {}", struct_type))),
+        }
+    }
+
+    fn is_struct(&self, name: &str) -> bool {
+        self.types.contains_key(name) || name == EIP712_DOMAIN_TYPE
+    }
+
+    fn collect_dependencies(&self, struct_type: &str, found: &mut BTreeSet<String>) -> Result<(), Error> {
+        if found.contains(struct_type) {
+            return Ok(());
+        }
+        found.insert(struct_type.to_owned());
+        for field in self.fields(struct_type)? {
+            let base_type = field.field_type.split('[').next().unwrap_or_default();
+            if self.is_struct(base_type) {
+                self.collect_dependencies(base_type, found)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes a single member value into its 32 bytes word.
+    fn encode_value(&self, field_type: &str, value: &Value) -> Result<H256, Error> {
+        if field_type.ends_with(']') {
+            let open = field_type
+                .rfind('[')
+                .ok_or_else(|| invalid(format!("Invalid array type: {}", field_type)))?;
+            let (item_type, length) = (&field_type[..open], &field_type[open + 1..field_type.len() - 1]);
+            let items = value
+                .as_array()
+                .ok_or_else(|| invalid(format!("Expected array for {}, got: {}", field_type, value)))?;
+            if !length.is_empty() && length.parse::<usize>().ok() != Some(items.len()) {
+                return Err(invalid(format!(
+                    "Expected {} items for {}, got: {}",
+                    length,
+                    field_type,
+                    items.len()
+                )));
+            }
+
+            let mut data = Vec::with_capacity(32 * items.len());
+            for item in items {
+                data.extend_from_slice(self.encode_value(item_type, item)?.as_bytes());
+            }
+            return Ok(keccak(&data));
+        }
+
+        if self.is_struct(field_type) {
+            return self.hash_struct(field_type, value);
+        }
+
+        let mismatch = || invalid(format!("Invalid value for {}: {}", field_type, value));
+        match field_type {
+            "string" => value.as_str().map(|s| keccak(s.as_bytes())).ok_or_else(mismatch),
+            "bytes" => Ok(keccak(&parse_bytes(value).ok_or_else(mismatch)?)),
+            "bool" => value
+                .as_bool()
+                .map(|b| H256::from_low_u64_be(b as u64))
+                .ok_or_else(mismatch),
+            "address" => {
+                let address = value
+                    .as_str()
+                    .and_then(|s| Address::from_str(s.trim_start_matches("0x")).ok())
+                    .ok_or_else(mismatch)?;
+                Ok(H256::from(address))
+            }
+            _ if field_type.starts_with("bytes") => {
+                let bytes = parse_bytes(value).ok_or_else(mismatch)?;
+                match field_type["bytes".len()..].parse::<usize>() {
+                    Ok(size) if size >= 1 && size <= 32 && bytes.len() == size => {
+                        let mut word = H256::zero();
+                        word.as_bytes_mut()[..size].copy_from_slice(&bytes);
+                        Ok(word)
+                    }
+                    _ => Err(mismatch()),
+                }
+            }
+            _ if field_type.starts_with("uint") || field_type.starts_with("int") => {
+                let signed = field_type.starts_with("int");
+                let bits = match &field_type[if signed { 3 } else { 4 }..] {
+                    "" => 256,
+                    bits => match bits.parse::<usize>() {
+                        Ok(bits) if bits >= 8 && bits <= 256 && bits % 8 == 0 => bits,
+                        _ => return Err(invalid(format!("Unknown type: {}", field_type))),
+                    },
+                };
+                let (negative, magnitude) = parse_signed(value).ok_or_else(mismatch)?;
+                let fits = match (signed, negative) {
+                    (true, true) => magnitude <= U256::one() << (bits - 1),
+                    (true, false) => magnitude < U256::one() << (bits - 1),
+                    (false, true) => magnitude.is_zero(),
+                    (false, false) => bits == 256 || magnitude < U256::one() << bits,
+                };
+                if !fits {
+                    return Err(invalid(format!("Value out of range for {}: {}", field_type, value)));
+                }
+
+                let mut word = H256::zero();
+                twos_complement(negative, magnitude).to_big_endian(word.as_bytes_mut());
+                Ok(word)
+            }
+            _ => Err(invalid(format!("Unknown type: {}", field_type))),
+        }
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::Decoder(format!("Invalid typed data: {}", message))
+}
+
+fn parse_bytes(value: &Value) -> Option<Vec<u8>> {
+    value.as_str()?.trim_start_matches("0x").from_hex().ok()
+}
+
+/// Parses an integer given as a JSON number, a decimal string or a `0x`-prefixed hex string.
+///
+/// Negative numbers are returned in two's complement.
+fn parse_int(value: &Value) -> Option<U256> {
+    parse_signed(value).map(|(negative, magnitude)| twos_complement(negative, magnitude))
+}
+
+/// Parses an integer into its sign and magnitude.
+fn parse_signed(value: &Value) -> Option<(bool, U256)> {
+    match value {
+        Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+            (Some(int), _) => Some((false, int.into())),
+            (None, Some(int)) => Some((true, (-i128::from(int) as u64).into())),
+            _ => None,
+        },
+        Value::String(s) => {
+            let (negative, s) = match s.trim() {
+                s if s.starts_with('-') => (true, &s[1..]),
+                s => (false, s),
+            };
+            let int = if s.starts_with("0x") {
+                U256::from_str(&s[2..]).ok()?
+            } else {
+                U256::from_dec_str(s).ok()?
+            };
+            Some((negative, int))
+        }
+        _ => None,
+    }
+}
+
+fn twos_complement(negative: bool, magnitude: U256) -> U256 {
+    if negative {
+        (!magnitude).overflowing_add(1.into()).0
+    } else {
+        magnitude
+    }
+}
+
+fn deserialize_optional_uint<'de, D>(deserializer: D) -> Result<Option<U256>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => parse_int(&value)
+            .map(Some)
+            .ok_or_else(|| de::Error::custom(format!("Invalid integer: {}", value))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MAIL: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    fn h256(hex: &str) -> H256 {
+        H256::from_str(hex).unwrap()
+    }
+
+    #[test]
+    fn should_parse_typed_data() {
+        let typed_data: TypedData = MAIL.parse().unwrap();
+
+        assert_eq!(typed_data.primary_type, "Mail");
+        assert_eq!(
+            typed_data.domain,
+            Eip712Domain {
+                name: Some("Ether Mail".into()),
+                version: Some("1".into()),
+                chain_id: Some(1.into()),
+                verifying_contract: Some(Address::from_str("CcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC").unwrap()),
+                salt: None,
+                extra: Default::default(),
+            }
+        );
+        assert_eq!(
+            typed_data.types["Person"],
+            vec![
+                TypedDataField::new("name", "string"),
+                TypedDataField::new("wallet", "address")
+            ]
+        );
+    }
+
+    #[test]
+    fn should_hash_typed_data() {
+        let typed_data: TypedData = MAIL.parse().unwrap();
+
+        assert_eq!(
+            typed_data.encode_type("Mail").unwrap(),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            typed_data.type_hash("Mail").unwrap(),
+            h256("a0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2")
+        );
+        assert_eq!(
+            typed_data.domain_separator().unwrap(),
+            h256("f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f")
+        );
+        assert_eq!(
+            typed_data.struct_hash().unwrap(),
+            h256("c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e")
+        );
+        assert_eq!(
+            typed_data.signing_hash().unwrap(),
+            h256("be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2")
+        );
+    }
+
+    #[test]
+    fn should_derive_domain_type_when_missing() {
+        let mut typed_data: TypedData = MAIL.parse().unwrap();
+        typed_data.types.remove(EIP712_DOMAIN_TYPE);
+
+        assert_eq!(
+            typed_data.domain_separator().unwrap(),
+            h256("f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f")
+        );
+    }
+
+    #[test]
+    fn should_hash_arrays_and_atomic_types() {
+        let typed_data: TypedData = serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "chainId", "type": "uint256" }
+                ],
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallets", "type": "address[]" }
+                ],
+                "Group": [
+                    { "name": "name", "type": "string" },
+                    { "name": "members", "type": "Person[]" },
+                    { "name": "balance", "type": "int256" },
+                    { "name": "flags", "type": "bool[2]" },
+                    { "name": "data", "type": "bytes" },
+                    { "name": "tag", "type": "bytes4" }
+                ]
+            },
+            "primaryType": "Group",
+            "domain": { "name": "Groups", "chainId": "0x5" },
+            "message": {
+                "name": "Friends",
+                "members": [
+                    {
+                        "name": "Cow",
+                        "wallets": [
+                            "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826",
+                            "0xDeaDbeefdEAdbeefdEadbEEFdeadbeEFdEaDbeeF"
+                        ]
+                    },
+                    { "name": "Bob", "wallets": [] }
+                ],
+                "balance": -5,
+                "flags": [true, false],
+                "data": "0x0102",
+                "tag": "0xdeadbeef"
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            typed_data.encode_type("Group").unwrap(),
+            "Group(string name,Person[] members,int256 balance,bool[2] flags,bytes data,bytes4 tag)\
+             Person(string name,address[] wallets)"
+        );
+        assert_eq!(
+            typed_data.domain_separator().unwrap(),
+            h256("f895a6f41ef34f2601afbad240bb15bc0f5b9b54a7ef9948476d27724a4a3fde")
+        );
+        assert_eq!(
+            typed_data.struct_hash().unwrap(),
+            h256("e660006bf59a9879fa63f2cae740d32815e5aeea3fb291c585c0ee08a35bb6dc")
+        );
+        assert_eq!(
+            typed_data.signing_hash().unwrap(),
+            h256("a1bcfc99179fc4a8e36c646bd841974305d5006112696306d1dd1922b89d0ecd")
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_values() {
+        let mut typed_data: TypedData = MAIL.parse().unwrap();
+        typed_data.message["to"]["wallet"] = json!(true);
+
+        assert_eq!(
+            typed_data.struct_hash(),
+            Err(Error::Decoder(
+                "Invalid typed data: Invalid value for address: true".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn should_reject_integers_out_of_range() {
+        let typed_data = |field_type: &str, value: Value| -> TypedData {
+            serde_json::from_value(json!({
+                "types": { "Value": [{ "name": "value", "type": field_type }] },
+                "primaryType": "Value",
+                "domain": {},
+                "message": { "value": value }
+            }))
+            .unwrap()
+        };
+
+        assert!(typed_data("uint8", json!(255)).struct_hash().is_ok());
+        assert!(typed_data("uint8", json!(256)).struct_hash().is_err());
+        assert!(typed_data("uint256", json!("-1")).struct_hash().is_err());
+        assert!(typed_data("int8", json!(-128)).struct_hash().is_ok());
+        assert!(typed_data("int8", json!(-129)).struct_hash().is_err());
+        assert!(typed_data("int8", json!("0x80")).struct_hash().is_err());
+        assert!(typed_data("uint7", json!(1)).struct_hash().is_err());
+    }
+
+    #[test]
+    fn should_hash_domain_with_declared_type() {
+        let typed_data: TypedData = serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "name", "type": "string" },
+                    { "name": "revision", "type": "uint8" }
+                ],
+                "Empty": []
+            },
+            "primaryType": "Empty",
+            "domain": { "name": "Custom", "chainId": 5, "revision": 2 },
+            "message": {}
+        }))
+        .unwrap();
+        let mut data = typed_data.type_hash(EIP712_DOMAIN_TYPE).unwrap().as_bytes().to_vec();
+        data.extend_from_slice(H256::from_low_u64_be(5).as_bytes());
+        data.extend_from_slice(keccak(b"Custom").as_bytes());
+        data.extend_from_slice(H256::from_low_u64_be(2).as_bytes());
+
+        assert_eq!(
+            typed_data.encode_type(EIP712_DOMAIN_TYPE).unwrap(),
+            "EIP712Domain(uint256 chainId,string name,uint8 revision)"
+        );
+        assert_eq!(typed_data.domain_separator().unwrap(), keccak(&data));
+    }
+}